@@ -0,0 +1,197 @@
+//! Aligning and distributing whole objects: contours and components.
+//!
+//! Objects are moved as units, by their bounding boxes. Alignment is
+//! relative to a reference rectangle, which may be the bounds of the
+//! selection, the glyph's metrics box, or a 'key object' chosen by the user.
+//! Distribution is always within the bounds of the selection; the outermost
+//! objects stay where they are.
+
+use druid::kurbo::{Rect, Vec2};
+use druid::{Data, Lens};
+
+/// Which edge or center line of each object to line up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// The axis along which objects are distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// How objects are distributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Spacing {
+    /// The centers of the objects are equally far apart.
+    Centers,
+    /// The gaps between the objects are all the same.
+    Gaps,
+}
+
+/// What objects are aligned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum AlignTarget {
+    /// The bounds of all the selected objects.
+    Selection,
+    /// The glyph's advance and its ascender and descender.
+    Metrics,
+    /// The key object, which stays in place.
+    KeyObject,
+}
+
+/// An operation sent from the align panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum AlignOp {
+    Align(Alignment),
+    Distribute(Axis, Spacing),
+}
+
+/// The settings of the align panel.
+#[derive(Debug, Clone, Copy, Data, Lens)]
+pub struct AlignSettings {
+    pub target: AlignTarget,
+}
+
+impl Default for AlignSettings {
+    fn default() -> Self {
+        AlignSettings {
+            target: AlignTarget::Selection,
+        }
+    }
+}
+
+/// Returns how far to move each object, given their bounds, so as to
+/// perform `op`. `reference` is the rectangle objects are aligned to; it is
+/// ignored when distributing.
+///
+/// The offsets are rounded to whole units.
+pub(crate) fn offsets(bounds: &[Rect], op: AlignOp, reference: Rect) -> Vec<Vec2> {
+    let offsets = match op {
+        AlignOp::Align(alignment) => bounds
+            .iter()
+            .map(|rect| align_offset(*rect, alignment, reference))
+            .collect(),
+        AlignOp::Distribute(axis, spacing) => distribute(bounds, axis, spacing),
+    };
+    offsets
+        .into_iter()
+        .map(|v: Vec2| Vec2::new(v.x.round(), v.y.round()))
+        .collect()
+}
+
+fn align_offset(rect: Rect, alignment: Alignment, reference: Rect) -> Vec2 {
+    match alignment {
+        Alignment::Left => Vec2::new(reference.x0 - rect.x0, 0.0),
+        Alignment::Center => Vec2::new(reference.center().x - rect.center().x, 0.0),
+        Alignment::Right => Vec2::new(reference.x1 - rect.x1, 0.0),
+        // design space is y-up, so the top is the larger y
+        Alignment::Top => Vec2::new(0.0, reference.y1 - rect.y1),
+        Alignment::Middle => Vec2::new(0.0, reference.center().y - rect.center().y),
+        Alignment::Bottom => Vec2::new(0.0, reference.y0 - rect.y0),
+    }
+}
+
+fn distribute(bounds: &[Rect], axis: Axis, spacing: Spacing) -> Vec<Vec2> {
+    let mut offsets = vec![Vec2::ZERO; bounds.len()];
+    if bounds.len() < 3 {
+        return offsets;
+    }
+    // the start and end of each object along the axis
+    let span = |rect: &Rect| match axis {
+        Axis::Horizontal => (rect.x0, rect.x1),
+        Axis::Vertical => (rect.y0, rect.y1),
+    };
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    let key = |idx: &usize| {
+        let (start, end) = span(&bounds[*idx]);
+        match spacing {
+            Spacing::Centers => (start + end) / 2.0,
+            Spacing::Gaps => start,
+        }
+    };
+    order.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+
+    let first = order[0];
+    let last = order[order.len() - 1];
+    let n_gaps = (order.len() - 1) as f64;
+    let mut position = span(&bounds[first]).0;
+    let step = match spacing {
+        Spacing::Centers => (key(&last) - key(&first)) / n_gaps,
+        Spacing::Gaps => {
+            let total: f64 = order
+                .iter()
+                .map(|idx| {
+                    let (start, end) = span(&bounds[*idx]);
+                    end - start
+                })
+                .sum();
+            (span(&bounds[last]).1 - position - total) / n_gaps
+        }
+    };
+
+    for (i, idx) in order.iter().enumerate() {
+        let (start, end) = span(&bounds[*idx]);
+        let delta = match spacing {
+            Spacing::Centers => key(&first) + step * i as f64 - (start + end) / 2.0,
+            Spacing::Gaps => {
+                let delta = position - start;
+                position += end - start + step;
+                delta
+            }
+        };
+        offsets[*idx] = match axis {
+            Axis::Horizontal => Vec2::new(delta, 0.0),
+            Axis::Vertical => Vec2::new(0.0, delta),
+        };
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_edges() {
+        let bounds = [Rect::new(0., 0., 10., 10.), Rect::new(20., 30., 50., 40.)];
+        let reference = Rect::new(0., 0., 50., 40.);
+        let left = offsets(&bounds, AlignOp::Align(Alignment::Left), reference);
+        assert_eq!(left, vec![Vec2::ZERO, Vec2::new(-20., 0.)]);
+        let top = offsets(&bounds, AlignOp::Align(Alignment::Top), reference);
+        assert_eq!(top, vec![Vec2::new(0., 30.), Vec2::ZERO]);
+        let center = offsets(&bounds, AlignOp::Align(Alignment::Center), reference);
+        assert_eq!(center, vec![Vec2::new(20., 0.), Vec2::new(-10., 0.)]);
+    }
+
+    #[test]
+    fn distribute_gaps_and_centers() {
+        let bounds = [
+            Rect::new(0., 0., 10., 10.),
+            Rect::new(60., 0., 100., 10.),
+            Rect::new(15., 0., 25., 10.),
+        ];
+        // 100 wide, 60 of objects: gaps of 20
+        let gaps = offsets(
+            &bounds,
+            AlignOp::Distribute(Axis::Horizontal, Spacing::Gaps),
+            Rect::ZERO,
+        );
+        assert_eq!(gaps, vec![Vec2::ZERO, Vec2::ZERO, Vec2::new(15., 0.)]);
+
+        // centers at 5 and 80: the middle one is centered at 42.5
+        let centers = offsets(
+            &bounds,
+            AlignOp::Distribute(Axis::Horizontal, Spacing::Centers),
+            Rect::ZERO,
+        );
+        assert_eq!(centers, vec![Vec2::ZERO, Vec2::ZERO, Vec2::new(23., 0.)]);
+    }
+}
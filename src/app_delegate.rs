@@ -7,14 +7,14 @@ use druid::{
 };
 
 use druid::kurbo::Size;
-use druid::lens::LensExt;
 use druid::widget::WidgetExt;
 use norad::{GlyphName, Ufo};
 
 use crate::consts;
 use crate::data::{AppState, Workspace};
 use crate::edit_session::EditSession;
-use crate::widgets::{Editor, EditorController, RootWindowController, ScrollZoom};
+use crate::undo::SessionUndo;
+use crate::widgets::{Editor, EditorController, ModalHost, RootWindowController, ScrollZoom};
 
 pub const EDIT_GLYPH: Selector<GlyphName> = Selector::new("runebender.open-editor-with-glyph");
 
@@ -46,6 +46,37 @@ impl AppDelegate<AppState> for Delegate {
                 log::error!("saving failed: '{}'", e);
             }
             Handled::Yes
+        } else if cmd.is(consts::cmd::EXPORT) {
+            match data.workspace.export() {
+                Ok(path) => log::info!("exported to {:?}", path),
+                Err(e) => log::error!("export failed: '{}'", e),
+            }
+            Handled::Yes
+        } else if cmd.is(consts::cmd::TOGGLE_REMOVE_OVERLAPS_ON_EXPORT) {
+            data.workspace.remove_overlaps_on_export = !data.workspace.remove_overlaps_on_export;
+            ctx.submit_command(consts::cmd::REBUILD_MENUS);
+            Handled::Yes
+        } else if cmd.is(consts::cmd::TOGGLE_CURVATURE_COMB) {
+            let settings = &mut data.workspace.comb_settings;
+            settings.visible = !settings.visible;
+            ctx.submit_command(consts::cmd::REBUILD_MENUS);
+            Handled::Yes
+        } else if cmd.is(consts::cmd::REMOVE_OVERLAP_IN_SELECTED_GLYPH) {
+            let workspace = &mut data.workspace;
+            let window = workspace
+                .selected
+                .as_ref()
+                .and_then(|name| workspace.open_glyphs.get(name))
+                .copied();
+            match window {
+                Some(id) => ctx.submit_command(consts::cmd::REMOVE_OVERLAP_IN_GLYPH.to(id)),
+                None => workspace.remove_overlap_in_selected_glyph(),
+            }
+            Handled::Yes
+        } else if cmd.is(consts::cmd::TOGGLE_PERSIST_UNDO_HISTORY) {
+            data.workspace.persist_undo_history = !data.workspace.persist_undo_history;
+            ctx.submit_command(consts::cmd::REBUILD_MENUS);
+            Handled::Yes
         } else if cmd.is(consts::cmd::NEW_GLYPH) {
             let new_glyph_name = data.workspace.add_new_glyph();
             data.workspace.selected = Some(new_glyph_name);
@@ -66,7 +97,8 @@ impl AppDelegate<AppState> for Delegate {
                 None => {
                     let session = data.workspace.get_or_create_session(&payload);
                     let session_id = session.id;
-                    let new_win = WindowDesc::new(move || make_editor(&session))
+                    let undo = data.workspace.session_undo(session_id);
+                    let new_win = WindowDesc::new(move || make_editor(&session, &undo))
                         .title(move |d: &AppState, _: &_| {
                             d.workspace
                                 .sessions
@@ -114,8 +146,10 @@ impl AppDelegate<AppState> for Delegate {
     }
 }
 
-fn make_editor(session: &Arc<EditSession>) -> impl Widget<AppState> {
-    EditorController::new(ScrollZoom::new(Editor::new(session.clone())))
-        .lens(AppState::workspace.then(Workspace::editor_state(session.id)))
+fn make_editor(session: &Arc<EditSession>, undo: &SessionUndo) -> impl Widget<AppState> {
+    let editor = EditorController::new(ScrollZoom::new(Editor::new(session.clone(), undo.clone())))
+        .lens(Workspace::editor_state(session.id));
+    ModalHost::new(editor)
+        .lens(AppState::workspace)
         .controller(RootWindowController::default())
 }
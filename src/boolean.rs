@@ -0,0 +1,808 @@
+//! Boolean operations on paths.
+//!
+//! These work directly on the bezier segments of the paths, so curves are
+//! preserved. The approach is the usual one:
+//!
+//! - find every intersection between segments, and split the segments there;
+//! - for each resulting piece, look at the regions immediately on either side
+//!   of it, and keep the piece if it lies on the boundary of the result;
+//! - join the kept pieces back up into closed contours.
+//!
+//! Inputs are filled with the nonzero rule, as in a font. In the output,
+//! outer contours run counter-clockwise and counters run clockwise.
+//!
+//! We do not currently detect a single cubic segment that crosses itself.
+
+use druid::kurbo::{
+    BezPath, CubicBez, Line, ParamCurve, ParamCurveDeriv, ParamCurveNearest, PathEl, PathSeg,
+    Point, Rect, Shape, Vec2,
+};
+
+use crate::path::Path;
+
+/// Points closer than this (in design units) are considered the same point.
+const SNAP_DISTANCE: f64 = 1e-3;
+/// A curve is considered flat when its control points are no further than
+/// this from its chord.
+const FLATNESS: f64 = 1e-5;
+/// The distance from a piece at which we sample the regions on either side.
+const SAMPLE_DISTANCE: f64 = 1e-2;
+/// The accuracy of the polygons used to test whether a point is inside
+/// an operand; this must be smaller than `SAMPLE_DISTANCE`.
+const FLATTEN_TOLERANCE: f64 = 1e-3;
+/// Results with a smaller area than this are discarded as slivers.
+const MIN_AREA: f64 = 1.0;
+/// A bound on recursion when finding intersections.
+const MAX_DEPTH: usize = 48;
+
+/// A boolean operation on two sets of paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    /// Everything inside either operand.
+    Union,
+    /// Everything inside the first operand and not the second.
+    Subtract,
+    /// Everything inside both operands.
+    Intersect,
+    /// Everything inside exactly one operand.
+    Exclude,
+}
+
+impl BooleanOp {
+    fn contains(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            BooleanOp::Union => in_a || in_b,
+            BooleanOp::Subtract => in_a && !in_b,
+            BooleanOp::Intersect => in_a && in_b,
+            BooleanOp::Exclude => in_a != in_b,
+        }
+    }
+
+    /// The minimum number of paths needed for this operation to do anything.
+    ///
+    /// A union of a single path will still remove any self-overlap.
+    pub(crate) fn min_paths(self) -> usize {
+        match self {
+            BooleanOp::Union => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// How the winding number at a point determines whether it is filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FillRule {
+    /// Filled if the winding number is not zero; this is how fonts are drawn.
+    NonZero,
+    /// Filled if the winding number is greater than zero.
+    Positive,
+}
+
+impl FillRule {
+    fn is_filled(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::Positive => winding > 0,
+        }
+    }
+}
+
+/// A segment of one of the inputs, along with the places it should be split.
+struct InputSeg {
+    seg: PathSeg,
+    splits: Vec<f64>,
+}
+
+/// A piece of an input segment, between two vertices.
+struct Edge {
+    seg: PathSeg,
+    from: usize,
+    to: usize,
+}
+
+/// Combine the closed paths in `a` and `b` with the given operation.
+///
+/// Open paths are ignored. The returned paths are new; they do not share
+/// ids with the inputs.
+pub(crate) fn boolean_op(a: &[Path], b: &[Path], op: BooleanOp) -> Vec<Path> {
+    combine(a, b, op, FillRule::NonZero).0
+}
+
+/// Merge overlapping contours in `paths`, and correct their direction.
+///
+/// Returns `None` if the outline is already clean, in which case the paths
+/// should be left as they are. Open paths are returned unchanged.
+pub(crate) fn remove_overlap(paths: &[Path]) -> Option<Vec<Path>> {
+    let (merged, changed) = combine(paths, &[], BooleanOp::Union, FillRule::NonZero);
+    if !changed {
+        return None;
+    }
+    let open_paths = paths.iter().filter(|p| !p.is_closed()).cloned();
+    Some(open_paths.chain(merged).collect())
+}
+
+/// Merge `paths`, keeping only the regions with a positive winding number.
+///
+/// This cleans up outlines that were constructed so that unwanted loops
+/// wind clockwise, such as the result of offsetting a path.
+pub(crate) fn resolve_positive(paths: &[Path]) -> Vec<Path> {
+    combine(paths, &[], BooleanOp::Union, FillRule::Positive).0
+}
+
+/// Perform a boolean operation, also returning whether the result differs
+/// from the input in anything other than start points.
+fn combine(a: &[Path], b: &[Path], op: BooleanOp, fill: FillRule) -> (Vec<Path>, bool) {
+    let a: Vec<BezPath> = a
+        .iter()
+        .filter(|p| p.is_closed())
+        .map(Path::bezier)
+        .collect();
+    let b: Vec<BezPath> = b
+        .iter()
+        .filter(|p| p.is_closed())
+        .map(Path::bezier)
+        .collect();
+
+    let mut segs: Vec<InputSeg> = a
+        .iter()
+        .chain(b.iter())
+        .flat_map(|bez| bez.segments())
+        .filter(|seg| extent(control_box(*seg)) > SNAP_DISTANCE)
+        .map(|seg| InputSeg {
+            seg,
+            splits: Vec::new(),
+        })
+        .collect();
+
+    for i in 0..segs.len() {
+        for j in i + 1..segs.len() {
+            for (t1, t2) in intersections(segs[i].seg, segs[j].seg) {
+                segs[i].splits.push(t1);
+                segs[j].splits.push(t2);
+            }
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut edges = split_segments(&segs, &mut vertices);
+    remove_duplicate_edges(&mut edges);
+    let mut changed = edges.len() != segs.len();
+
+    let a_polys: Vec<_> = a.iter().map(flatten).collect();
+    let b_polys: Vec<_> = b.iter().map(flatten).collect();
+    let inside = |pt: Point| {
+        let in_a = fill.is_filled(a_polys.iter().map(|poly| winding(poly, pt)).sum());
+        let in_b = fill.is_filled(b_polys.iter().map(|poly| winding(poly, pt)).sum());
+        op.contains(in_a, in_b)
+    };
+
+    // keep the pieces with the result on exactly one side, oriented so that
+    // the inside is on the left.
+    let edge_count = edges.len();
+    let edges: Vec<Edge> = edges
+        .into_iter()
+        .filter_map(|edge| {
+            let mid = edge.seg.eval(0.5);
+            let normal = tangent(edge.seg, 0.5).normalize();
+            let normal = Vec2::new(-normal.y, normal.x);
+            let dist = SAMPLE_DISTANCE.min(extent(control_box(edge.seg)) * 0.25);
+            let left = inside(mid + normal * dist);
+            let right = inside(mid - normal * dist);
+            match (left, right) {
+                (true, false) => Some(edge),
+                (false, true) => {
+                    changed = true;
+                    Some(Edge {
+                        seg: reverse(edge.seg),
+                        from: edge.to,
+                        to: edge.from,
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    changed |= edges.len() != edge_count;
+
+    let paths = trace_contours(&edges)
+        .into_iter()
+        .filter_map(|contour| path_for_contour(contour.into_iter().map(|i| edges[i].seg)))
+        .collect();
+    (paths, changed)
+}
+
+/// Split each segment at its intersections, returning the pieces.
+///
+/// The ends of each piece are snapped to a shared vertex, which is added to
+/// `vertices`.
+fn split_segments(segs: &[InputSeg], vertices: &mut Vec<Point>) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for InputSeg { seg, splits } in segs {
+        let (start, end) = (seg.eval(0.0), seg.eval(1.0));
+        let mut splits: Vec<f64> = splits
+            .iter()
+            .copied()
+            .filter(|t| {
+                let pt = seg.eval(*t);
+                t.is_finite()
+                    && pt.distance(start) > SNAP_DISTANCE
+                    && pt.distance(end) > SNAP_DISTANCE
+            })
+            .collect();
+        splits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        splits.dedup_by(|a, b| seg.eval(*a).distance(seg.eval(*b)) <= SNAP_DISTANCE);
+
+        let bounds = std::iter::once(0.0)
+            .chain(splits)
+            .chain(std::iter::once(1.0))
+            .collect::<Vec<_>>();
+        for range in bounds.windows(2) {
+            let piece = seg.subsegment(range[0]..range[1]);
+            let from = vertex_for_point(vertices, piece.eval(0.0));
+            let to = vertex_for_point(vertices, piece.eval(1.0));
+            if from == to && extent(control_box(piece)) <= SNAP_DISTANCE {
+                continue;
+            }
+            let seg = with_endpoints(piece, vertices[from], vertices[to]);
+            edges.push(Edge { seg, from, to });
+        }
+    }
+    edges
+}
+
+fn vertex_for_point(vertices: &mut Vec<Point>, pt: Point) -> usize {
+    match vertices
+        .iter()
+        .position(|v| v.distance(pt) <= SNAP_DISTANCE)
+    {
+        Some(idx) => idx,
+        None => {
+            vertices.push(pt);
+            vertices.len() - 1
+        }
+    }
+}
+
+/// Where the inputs have coincident edges, we end up with two copies of
+/// the same piece; keep only one of them.
+fn remove_duplicate_edges(edges: &mut Vec<Edge>) {
+    let mut idx = 0;
+    while idx < edges.len() {
+        let is_dup = edges[..idx].iter().any(|other| {
+            let same_ends = (other.from == edges[idx].from && other.to == edges[idx].to)
+                || (other.from == edges[idx].to && other.to == edges[idx].from);
+            same_ends
+                && is_on_segment(other.seg, edges[idx].seg.eval(0.5))
+                && is_on_segment(edges[idx].seg, other.seg.eval(0.5))
+        });
+        if is_dup {
+            edges.remove(idx);
+        } else {
+            idx += 1;
+        }
+    }
+}
+
+/// Join edges into closed contours, returning the indices of the edges
+/// in each contour.
+fn trace_contours(edges: &[Edge]) -> Vec<Vec<usize>> {
+    let n_vertices = edges
+        .iter()
+        .map(|e| e.from.max(e.to) + 1)
+        .max()
+        .unwrap_or(0);
+    let mut outgoing = vec![Vec::new(); n_vertices];
+    for (i, edge) in edges.iter().enumerate() {
+        outgoing[edge.from].push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut contour = vec![start];
+        let mut current = start;
+        let closed = loop {
+            let edge = &edges[current];
+            if edge.to == edges[start].from {
+                break true;
+            }
+            // where there is a choice, take the sharpest left turn; this keeps
+            // separate regions that touch at a point in separate contours.
+            let dir_in = end_direction(edge.seg);
+            let next = outgoing[edge.to]
+                .iter()
+                .copied()
+                .filter(|i| !used[*i])
+                .map(|i| (i, turn_angle(dir_in, start_direction(edges[i].seg))))
+                .filter(|(_, turn)| turn.is_finite())
+                .max_by(|(_, turn_i), (_, turn_j)| turn_i.partial_cmp(turn_j).unwrap())
+                .map(|(i, _)| i);
+            match next {
+                Some(next) => {
+                    used[next] = true;
+                    contour.push(next);
+                    current = next;
+                }
+                None => break false,
+            }
+        };
+        if closed {
+            contours.push(contour);
+        } else {
+            log::warn!("boolean operation produced an unclosed contour");
+        }
+    }
+    contours
+}
+
+/// Convert a contour to a `Path`, discarding it if it is degenerate.
+fn path_for_contour(segs: impl Iterator<Item = PathSeg>) -> Option<Path> {
+    let mut bez = BezPath::new();
+    let mut current: Option<Point> = None;
+    for seg in segs {
+        let start = round_point(seg.eval(0.0));
+        if current.is_none() {
+            bez.move_to(start);
+            current = Some(start);
+        }
+        let end = round_point(seg.eval(1.0));
+        match seg {
+            PathSeg::Line(_) if Some(end) == current => {}
+            PathSeg::Line(_) => bez.line_to(end),
+            PathSeg::Cubic(c)
+                if Some(end) == current && round_point(c.p1) == end && round_point(c.p2) == end => {
+            }
+            PathSeg::Cubic(c) => bez.curve_to(c.p1, c.p2, end),
+            PathSeg::Quad(q) => bez.quad_to(q.p1, end),
+        }
+        current = Some(end);
+    }
+    bez.close_path();
+    if bez.area().abs() < MIN_AREA {
+        return None;
+    }
+    Path::from_bezpath(bez).ok()
+}
+
+/// Points in a `Path` have integer coordinates.
+fn round_point(pt: Point) -> Point {
+    Point::new(pt.x.round(), pt.y.round())
+}
+
+/// Approximate a closed path with a polygon.
+fn flatten(bez: &BezPath) -> Vec<Point> {
+    let mut points = Vec::new();
+    bez.flatten(FLATTEN_TOLERANCE, |el| match el {
+        PathEl::MoveTo(pt) | PathEl::LineTo(pt) => points.push(pt),
+        _ => (),
+    });
+    points
+}
+
+/// The winding number of `polygon` around `pt`; positive if the polygon
+/// runs counter-clockwise.
+fn winding(polygon: &[Point], pt: Point) -> i32 {
+    let mut winding = 0;
+    for (i, p0) in polygon.iter().enumerate() {
+        let p1 = polygon[(i + 1) % polygon.len()];
+        let side = (p1 - *p0).cross(pt - *p0);
+        if p0.y <= pt.y {
+            if p1.y > pt.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if p1.y <= pt.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// Find the parameters at which `a` and `b` intersect.
+fn intersections(a: PathSeg, b: PathSeg) -> Vec<(f64, f64)> {
+    if !boxes_overlap(control_box(a), control_box(b)) {
+        return Vec::new();
+    }
+    if let Some(overlap) = coincident_overlap(a, b) {
+        return overlap;
+    }
+    let mut result = Vec::new();
+    subdivide(a, 0.0..1.0, b, 0.0..1.0, 0, &mut result);
+    // degenerate segments can give us NaNs, which we can't sort.
+    result.retain(|(t_a, t_b)| t_a.is_finite() && t_b.is_finite());
+    result.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+    result.dedup_by(|x, y| a.eval(x.0).distance(a.eval(y.0)) <= SNAP_DISTANCE);
+    result
+}
+
+/// Recursively subdivide two segments until they are flat, and then
+/// intersect their chords.
+fn subdivide(
+    a: PathSeg,
+    a_range: std::ops::Range<f64>,
+    b: PathSeg,
+    b_range: std::ops::Range<f64>,
+    depth: usize,
+    result: &mut Vec<(f64, f64)>,
+) {
+    let sub_a = a.subsegment(a_range.clone());
+    let sub_b = b.subsegment(b_range.clone());
+    let (box_a, box_b) = (control_box(sub_a), control_box(sub_b));
+    if !boxes_overlap(box_a, box_b) {
+        return;
+    }
+
+    let flat_a = flatness(sub_a) <= FLATNESS;
+    let flat_b = flatness(sub_b) <= FLATNESS;
+    if (flat_a && flat_b) || depth >= MAX_DEPTH {
+        let chord_a = Line::new(sub_a.eval(0.0), sub_a.eval(1.0));
+        let chord_b = Line::new(sub_b.eval(0.0), sub_b.eval(1.0));
+        if let Some((t, u)) = line_intersection(chord_a, chord_b) {
+            result.push((lerp(&a_range, t), lerp(&b_range, u)));
+        }
+        return;
+    }
+
+    let split_a = !flat_a && (flat_b || extent(box_a) >= extent(box_b));
+    if split_a {
+        let mid = lerp(&a_range, 0.5);
+        subdivide(a, a_range.start..mid, b, b_range.clone(), depth + 1, result);
+        subdivide(a, mid..a_range.end, b, b_range, depth + 1, result);
+    } else {
+        let mid = lerp(&b_range, 0.5);
+        subdivide(a, a_range.clone(), b, b_range.start..mid, depth + 1, result);
+        subdivide(a, a_range, b, mid..b_range.end, depth + 1, result);
+    }
+}
+
+/// If `a` and `b` run along each other for part of their length, returns
+/// the parameters of the ends of the shared part.
+fn coincident_overlap(a: PathSeg, b: PathSeg) -> Option<Vec<(f64, f64)>> {
+    let mut ends = Vec::new();
+    for t_b in &[0.0, 1.0] {
+        if let Some(t_a) = param_on_segment(a, b.eval(*t_b)) {
+            ends.push((t_a, *t_b));
+        }
+    }
+    for t_a in &[0.0, 1.0] {
+        if let Some(t_b) = param_on_segment(b, a.eval(*t_a)) {
+            ends.push((*t_a, t_b));
+        }
+    }
+    ends.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+    let first = *ends.first()?;
+    let last = *ends.last()?;
+    if a.eval(first.0).distance(a.eval(last.0)) <= SNAP_DISTANCE {
+        return None;
+    }
+
+    let all_shared = [0.25, 0.5, 0.75].iter().all(|frac| {
+        let t_a = first.0 + (last.0 - first.0) * frac;
+        is_on_segment(b, a.eval(t_a))
+    });
+    if all_shared {
+        Some(vec![first, last])
+    } else {
+        None
+    }
+}
+
+fn param_on_segment(seg: PathSeg, pt: Point) -> Option<f64> {
+    let (t, dist_sq) = seg.nearest(pt, 1e-9);
+    if t.is_finite() && dist_sq <= SNAP_DISTANCE * SNAP_DISTANCE {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn is_on_segment(seg: PathSeg, pt: Point) -> bool {
+    param_on_segment(seg, pt).is_some()
+}
+
+/// The parameters at which two lines cross, if they do.
+fn line_intersection(a: Line, b: Line) -> Option<(f64, f64)> {
+    const PARAM_SLOP: f64 = 1e-9;
+    let d_a = a.p1 - a.p0;
+    let d_b = b.p1 - b.p0;
+    let denom = d_a.cross(d_b);
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let offset = b.p0 - a.p0;
+    let t = offset.cross(d_b) / denom;
+    let u = offset.cross(d_a) / denom;
+    let in_range = |x: f64| (-PARAM_SLOP..=1.0 + PARAM_SLOP).contains(&x);
+    if in_range(t) && in_range(u) {
+        Some((t.clamp(0.0, 1.0), u.clamp(0.0, 1.0)))
+    } else {
+        None
+    }
+}
+
+/// The maximum distance of the control points from the chord.
+fn flatness(seg: PathSeg) -> f64 {
+    match seg {
+        PathSeg::Line(_) => 0.0,
+        PathSeg::Quad(q) => distance_to_line(q.p1, q.p0, q.p2),
+        PathSeg::Cubic(c) => {
+            distance_to_line(c.p1, c.p0, c.p3).max(distance_to_line(c.p2, c.p0, c.p3))
+        }
+    }
+}
+
+fn distance_to_line(pt: Point, p0: Point, p1: Point) -> f64 {
+    let chord = p1 - p0;
+    let len = chord.hypot();
+    if len < 1e-12 {
+        pt.distance(p0)
+    } else {
+        (pt - p0).cross(chord).abs() / len
+    }
+}
+
+fn control_box(seg: PathSeg) -> Rect {
+    match seg {
+        PathSeg::Line(l) => Rect::from_points(l.p0, l.p1),
+        PathSeg::Quad(q) => Rect::from_points(q.p0, q.p2).union_pt(q.p1),
+        PathSeg::Cubic(c) => Rect::from_points(c.p0, c.p3).union_pt(c.p1).union_pt(c.p2),
+    }
+}
+
+/// The length of the longer side of `rect`.
+fn extent(rect: Rect) -> f64 {
+    rect.width().max(rect.height())
+}
+
+fn boxes_overlap(a: Rect, b: Rect) -> bool {
+    a.x0 <= b.x1 + SNAP_DISTANCE
+        && b.x0 <= a.x1 + SNAP_DISTANCE
+        && a.y0 <= b.y1 + SNAP_DISTANCE
+        && b.y0 <= a.y1 + SNAP_DISTANCE
+}
+
+fn lerp(range: &std::ops::Range<f64>, t: f64) -> f64 {
+    range.start + (range.end - range.start) * t
+}
+
+/// Replace the ends of `seg` with `start` and `end`.
+fn with_endpoints(seg: PathSeg, start: Point, end: Point) -> PathSeg {
+    match seg {
+        PathSeg::Line(_) => PathSeg::Line(Line::new(start, end)),
+        PathSeg::Quad(mut q) => {
+            q.p0 = start;
+            q.p2 = end;
+            PathSeg::Quad(q)
+        }
+        PathSeg::Cubic(c) => PathSeg::Cubic(CubicBez::new(start, c.p1, c.p2, end)),
+    }
+}
+
+fn reverse(seg: PathSeg) -> PathSeg {
+    match seg {
+        PathSeg::Line(l) => PathSeg::Line(Line::new(l.p1, l.p0)),
+        PathSeg::Quad(mut q) => {
+            std::mem::swap(&mut q.p0, &mut q.p2);
+            PathSeg::Quad(q)
+        }
+        PathSeg::Cubic(c) => PathSeg::Cubic(CubicBez::new(c.p3, c.p2, c.p1, c.p0)),
+    }
+}
+
+fn tangent(seg: PathSeg, t: f64) -> Vec2 {
+    let deriv = match seg {
+        PathSeg::Line(l) => l.p1 - l.p0,
+        PathSeg::Quad(q) => q.deriv().eval(t).to_vec2(),
+        PathSeg::Cubic(c) => c.deriv().eval(t).to_vec2(),
+    };
+    if deriv.hypot() > 1e-9 {
+        deriv
+    } else {
+        seg.eval(1.0) - seg.eval(0.0)
+    }
+}
+
+/// The direction in which `seg` leaves its start point.
+///
+/// We measure this a short way along the curve, rather than using the
+/// tangent, so that curves that leave a point with the same tangent (as
+/// where two curves touch) are still distinguished by their curvature.
+fn start_direction(seg: PathSeg) -> Vec2 {
+    const DIRECTION_PARAM: f64 = 1e-3;
+    seg.eval(DIRECTION_PARAM) - seg.eval(0.0)
+}
+
+/// The direction in which `seg` arrives at its end point.
+fn end_direction(seg: PathSeg) -> Vec2 {
+    -start_direction(reverse(seg))
+}
+
+/// The signed angle to turn from `from` to `to`; positive is to the left.
+fn turn_angle(from: Vec2, to: Vec2) -> f64 {
+    from.cross(to).atan2(from.dot(to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A counter-clockwise rectangle.
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Path {
+        let mut bez = BezPath::new();
+        bez.move_to((x0, y0));
+        bez.line_to((x1, y0));
+        bez.line_to((x1, y1));
+        bez.line_to((x0, y1));
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    /// A counter-clockwise circle made of four cubics.
+    fn circle(x: f64, y: f64, r: f64) -> Path {
+        let k = 0.5523 * r;
+        let mut bez = BezPath::new();
+        bez.move_to((x + r, y));
+        bez.curve_to((x + r, y + k), (x + k, y + r), (x, y + r));
+        bez.curve_to((x - k, y + r), (x - r, y + k), (x - r, y));
+        bez.curve_to((x - r, y - k), (x - k, y - r), (x, y - r));
+        bez.curve_to((x + k, y - r), (x + r, y - k), (x + r, y));
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    fn total_area(paths: &[Path]) -> f64 {
+        paths.iter().map(|p| p.bezier().area()).sum()
+    }
+
+    fn on_curve_count(path: &Path) -> usize {
+        path.points().iter().filter(|p| p.is_on_curve()).count()
+    }
+
+    #[test]
+    fn overlapping_rects() {
+        let a = [rect(0., 0., 100., 100.)];
+        let b = [rect(50., 50., 150., 150.)];
+
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert_eq!(on_curve_count(&union[0]), 8);
+        assert!((total_area(&union) - 17_500.).abs() < 1e-6);
+
+        let subtract = boolean_op(&a, &b, BooleanOp::Subtract);
+        assert_eq!(subtract.len(), 1);
+        assert!((total_area(&subtract) - 7_500.).abs() < 1e-6);
+
+        let intersect = boolean_op(&a, &b, BooleanOp::Intersect);
+        assert_eq!(intersect.len(), 1);
+        assert!((total_area(&intersect) - 2_500.).abs() < 1e-6);
+
+        // the two halves touch at their corners; they should still be separate.
+        let exclude = boolean_op(&a, &b, BooleanOp::Exclude);
+        assert_eq!(exclude.len(), 2);
+        assert!((total_area(&exclude) - 15_000.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn degenerate_segments() {
+        // a rectangle with a zero-length curve at one corner
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((100., 0.));
+        bez.curve_to((100., 0.), (100., 0.), (100., 0.));
+        bez.line_to((100., 100.));
+        bez.line_to((0., 100.));
+        bez.close_path();
+        let a = [Path::from_bezpath(bez).unwrap()];
+        let b = [rect(50., 50., 150., 150.), rect(100., -50., 120., 20.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+    }
+
+    #[test]
+    fn coincident_edges() {
+        let a = [rect(0., 0., 100., 100.)];
+        let b = [rect(100., 0., 200., 100.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert!((total_area(&union) - 20_000.).abs() < 1e-6);
+        assert!(boolean_op(&a, &b, BooleanOp::Intersect).is_empty());
+
+        // partially shared edge
+        let b = [rect(100., 50., 200., 150.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert!((total_area(&union) - 20_000.).abs() < 1e-6);
+
+        let subtract = boolean_op(&a, &b, BooleanOp::Subtract);
+        assert_eq!(subtract.len(), 1);
+        assert!((total_area(&subtract) - 10_000.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn identical_paths() {
+        let a = [rect(0., 0., 100., 100.)];
+        let b = [rect(0., 0., 100., 100.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert!((total_area(&union) - 10_000.).abs() < 1e-6);
+        assert!(boolean_op(&a, &b, BooleanOp::Subtract).is_empty());
+        assert!(boolean_op(&a, &b, BooleanOp::Exclude).is_empty());
+    }
+
+    #[test]
+    fn counters_are_clockwise() {
+        let a = [rect(0., 0., 300., 300.)];
+        let b = [rect(100., 100., 200., 200.)];
+        let result = boolean_op(&a, &b, BooleanOp::Subtract);
+        assert_eq!(result.len(), 2);
+        let mut areas: Vec<f64> = result.iter().map(|p| p.bezier().area()).collect();
+        areas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((areas[0] + 10_000.).abs() < 1e-6);
+        assert!((areas[1] - 90_000.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curves_are_preserved() {
+        let a = [circle(0., 0., 100.)];
+        let b = [circle(100., 0., 100.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert!(union[0].points().iter().any(|p| !p.is_on_curve()));
+        let a_area = total_area(&a);
+        let intersect = boolean_op(&a, &b, BooleanOp::Intersect);
+        let union_area = total_area(&union);
+        let intersect_area = total_area(&intersect);
+        // inclusion-exclusion, allowing for rounding of the new points
+        assert!((union_area + intersect_area - 2.0 * a_area).abs() < 0.01 * a_area);
+    }
+
+    #[test]
+    fn tangent_circles() {
+        let a = [circle(0., 0., 100.)];
+        let b = [circle(200., 0., 100.)];
+        let union = boolean_op(&a, &b, BooleanOp::Union);
+        assert_eq!(union.len(), 2);
+        assert!((total_area(&union) - 2.0 * total_area(&a)).abs() < 1e-6);
+        assert!(boolean_op(&a, &b, BooleanOp::Intersect).is_empty());
+    }
+
+    #[test]
+    fn clean_outlines_are_untouched() {
+        // an 'o', with a clockwise counter
+        let mut counter = BezPath::new();
+        counter.move_to((100., 100.));
+        counter.line_to((100., 200.));
+        counter.line_to((200., 200.));
+        counter.line_to((200., 100.));
+        counter.close_path();
+        let o = [
+            rect(0., 0., 300., 300.),
+            Path::from_bezpath(counter).unwrap(),
+        ];
+        assert!(remove_overlap(&o).is_none());
+
+        let overlapping = [rect(0., 0., 100., 100.), rect(50., 50., 150., 150.)];
+        let merged = remove_overlap(&overlapping).unwrap();
+        assert_eq!(merged.len(), 1);
+    }
+
+    #[test]
+    fn self_overlap_is_removed() {
+        // a single path shaped like a bow tie
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((100., 100.));
+        bez.line_to((100., 0.));
+        bez.line_to((0., 100.));
+        bez.close_path();
+        let bowtie = [Path::from_bezpath(bez).unwrap()];
+        let union = boolean_op(&bowtie, &[], BooleanOp::Union);
+        assert_eq!(union.len(), 2);
+        // both halves are now counter-clockwise
+        assert!(union.iter().all(|p| p.bezier().area() > 0.0));
+        assert!((total_area(&union) - 5_000.).abs() < 1e-6);
+    }
+}
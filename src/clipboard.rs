@@ -468,6 +468,8 @@ impl From<&Path> for GlyphPlistPath {
                     PointType::OnCurve if next_is_curve => "CURVE",
                     PointType::OnCurve => "LINE",
                     PointType::OnCurveSmooth => "CURVE SMOOTH",
+                    PointType::OnCurveTangent if next_is_curve => "CURVE SMOOTH",
+                    PointType::OnCurveTangent => "LINE SMOOTH",
                     PointType::OffCurve => "OFFCURVE",
                 };
 
@@ -511,6 +513,7 @@ fn from_glyphs_plist_point(s: &str, parent_id: usize) -> Option<PathPoint> {
                 "CURVE" => PointType::OnCurve,
                 "LINE" => PointType::OnCurve,
                 "CURVE SMOOTH" => PointType::OnCurveSmooth,
+                "LINE SMOOTH" => PointType::OnCurveTangent,
                 "OFFCURVE" => PointType::OffCurve,
                 other => {
                     log::warn!("unhandled glyphs point type '{}'", other);
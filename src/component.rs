@@ -1,6 +1,10 @@
 //! A glyph embedded in another glyph.
 
-use druid::kurbo::Affine;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use druid::kurbo::{Affine, Point, Rect};
 use druid::Data;
 use norad::GlyphName;
 
@@ -13,8 +17,19 @@ pub struct Component {
     #[data(same_fn = "affine_eq")]
     pub transform: Affine,
     pub id: EntityId,
+    /// Locked components are drawn, but can't be selected.
+    pub locked: bool,
+    /// Hidden components are neither drawn nor selectable, but are still saved.
+    pub hidden: bool,
 }
 
+/// The bounds of the glyphs used as components, before any transform.
+///
+/// These are looked up in the workspace when needed, so that they follow
+/// edits to the base glyphs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BaseBounds(Arc<HashMap<GlyphName, Rect>>);
+
 fn affine_eq(left: &Affine, right: &Affine) -> bool {
     left.as_coeffs() == right.as_coeffs()
 }
@@ -23,14 +38,22 @@ impl Component {
     pub fn from_norad(src: &norad::glyph::Component) -> Self {
         let base = src.base.clone();
         let transform = src.transform.into();
-        let id = EntityId::new_with_parent(0);
+        let id = EntityId::new_for_component();
         Component {
             base,
             transform,
             id,
+            locked: false,
+            hidden: false,
         }
     }
 
+    /// Whether this component can be selected and edited: that is, whether
+    /// it is neither locked nor hidden.
+    pub(crate) fn is_editable(&self) -> bool {
+        !self.locked && !self.hidden
+    }
+
     pub fn to_norad(&self) -> norad::glyph::Component {
         let base = self.base.clone();
         let transform = self.transform.into();
@@ -46,4 +69,28 @@ impl Component {
         let [a, b, c, d, t_x, t_y] = self.transform.as_coeffs();
         self.transform = Affine::new([a, b, c, d, t_x + delta.x, t_y + delta.y]);
     }
+
+    /// The bounding box of the transformed component, in design space.
+    pub(crate) fn bounding_box(&self, bases: &BaseBounds) -> Rect {
+        let Rect { x0, y0, x1, y1 } = bases.get(&self.base);
+        let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        let mut points = corners.iter().map(|pt| self.transform * Point::from(*pt));
+        let first = points.next().unwrap();
+        points.fold(Rect::from_points(first, first), |rect, pt| {
+            rect.union_pt(pt)
+        })
+    }
+}
+
+impl BaseBounds {
+    /// The bounds of the glyph `base`, or an empty rect if it is unknown.
+    pub(crate) fn get(&self, base: &GlyphName) -> Rect {
+        self.0.get(base).copied().unwrap_or_default()
+    }
+}
+
+impl FromIterator<(GlyphName, Rect)> for BaseBounds {
+    fn from_iter<I: IntoIterator<Item = (GlyphName, Rect)>>(iter: I) -> Self {
+        BaseBounds(Arc::new(iter.into_iter().collect()))
+    }
 }
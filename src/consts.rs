@@ -8,13 +8,21 @@ pub const GLYPHS_APP_PASTEBOARD_TYPE: FormatId = "Glyphs elements pasteboard typ
 
 /// Commands and Selectors
 pub mod cmd {
-    use druid::kurbo::{Point, Vec2};
+    use druid::kurbo::{Affine, Point, Vec2};
     use druid::Selector;
     use norad::GlyphName;
 
+    use crate::align::{AlignOp, AlignTarget};
+    use crate::boolean::BooleanOp;
+    use crate::corner::CornerSettings;
     use crate::design_space::{DPoint, DVec2};
+    use crate::fit::SimplifySettings;
+    use crate::offset::{OffsetSettings, StrokeSettings};
     use crate::path::EntityId;
+    use crate::selection::SelectSimilar;
+    use crate::shapes::{ShapeArgs, ShapeKind};
     use crate::tools::ToolId;
+    use crate::undo::UndoHistory;
 
     /// Sent when windows should rebuild their menus.
     pub const REBUILD_MENUS: Selector = Selector::new("runebender.rebuid-menus");
@@ -28,6 +36,25 @@ pub mod cmd {
     /// sent by the 'deselect' menu item
     pub const DESELECT_ALL: Selector = Selector::new("runebender.deselect-all");
 
+    /// sent by the 'select contour' menu item
+    pub const SELECT_CONTOURS: Selector = Selector::new("runebender.select-contours");
+
+    /// sent by the 'invert selection' menu item
+    pub const INVERT_SELECTION: Selector = Selector::new("runebender.invert-selection");
+
+    /// sent by the 'grow selection' menu item
+    pub const GROW_SELECTION: Selector = Selector::new("runebender.grow-selection");
+
+    /// sent by the 'shrink selection' menu item
+    pub const SHRINK_SELECTION: Selector = Selector::new("runebender.shrink-selection");
+
+    /// sent by the 'select similar' menu items
+    pub const SELECT_SIMILAR: Selector<SelectSimilar> = Selector::new("runebender.select-similar");
+
+    /// Toggles whether undo history is saved alongside the font.
+    pub const TOGGLE_PERSIST_UNDO_HISTORY: Selector =
+        Selector::new("runebender.toggle-persist-undo-history");
+
     /// sent by the 'new glyph' menu item
     pub const NEW_GLYPH: Selector = Selector::new("runebender.new-glyph");
 
@@ -51,9 +78,111 @@ pub mod cmd {
     /// sent by 'align selection' menu item in Paths menu
     pub const ALIGN_SELECTION: Selector = Selector::new("runebender.align-selection");
 
+    /// sent by the 'align' menu item in the Paths menu; the editor shows a
+    /// panel for aligning and distributing contours and components.
+    pub const SHOW_ALIGN_PANEL: Selector = Selector::new("runebender.show-align-panel");
+
+    /// Sent from the align panel, to align or distribute the selected objects.
+    pub const ALIGN_OBJECTS: Selector<AlignArgs> = Selector::new("runebender.align-objects");
+
+    /// Arguments passed with the ALIGN_OBJECTS command.
+    pub struct AlignArgs {
+        pub op: AlignOp,
+        pub target: AlignTarget,
+    }
+
     // sent by 'reverse contours' menu item in Paths menu
     pub const REVERSE_CONTOURS: Selector = Selector::new("runebender.reverse-contours");
 
+    /// sent by the 'correct path directions' menu item in the Paths menu
+    pub const CORRECT_DIRECTIONS: Selector = Selector::new("runebender.correct-directions");
+
+    /// sent by the 'set start point' menu item in the Paths menu
+    pub const SET_START_POINT: Selector = Selector::new("runebender.set-start-point");
+
+    /// sent by the 'lock selection' menu item in the Paths menu
+    pub const LOCK_SELECTION: Selector = Selector::new("runebender.lock-selection");
+
+    /// sent by the 'unlock all' menu item in the Paths menu
+    pub const UNLOCK_ALL: Selector = Selector::new("runebender.unlock-all");
+
+    /// sent by the 'hide selection' menu item in the Paths menu
+    pub const HIDE_SELECTION: Selector = Selector::new("runebender.hide-selection");
+
+    /// sent by the 'show all' menu item in the Paths menu
+    pub const SHOW_ALL: Selector = Selector::new("runebender.show-all");
+
+    /// Sent by the 'flip horizontally' menu item in the Paths menu.
+    pub const FLIP_HORIZONTAL: Selector = Selector::new("runebender.flip-horizontal");
+
+    /// Sent by the 'flip vertically' menu item in the Paths menu.
+    pub const FLIP_VERTICAL: Selector = Selector::new("runebender.flip-vertical");
+
+    /// Sent by the boolean operation items in the Paths menu.
+    pub const BOOLEAN_OP: Selector<BooleanOp> = Selector::new("runebender.boolean-op");
+
+    /// sent by the 'remove overlap' menu item in the Paths menu
+    pub const REMOVE_OVERLAP: Selector = Selector::new("runebender.remove-overlap");
+
+    /// sent by the 'add extremes' menu item in the Paths menu
+    pub const ADD_EXTREMES: Selector = Selector::new("runebender.add-extremes");
+
+    /// sent by the 'break contour' menu item in the Paths menu
+    pub const BREAK_CONTOUR: Selector = Selector::new("runebender.break-contour");
+
+    /// sent by the 'harmonize' menu item in the Paths menu
+    pub const HARMONIZE: Selector = Selector::new("runebender.harmonize");
+
+    /// sent by the 'remove redundant points' menu item in the Paths menu
+    pub const REMOVE_REDUNDANT_POINTS: Selector =
+        Selector::new("runebender.remove-redundant-points");
+
+    /// sent by the 'simplify' menu item; the editor shows a panel where the
+    /// tolerance can be chosen.
+    pub const SHOW_SIMPLIFY_PANEL: Selector = Selector::new("runebender.show-simplify-panel");
+
+    /// Sent from the simplify panel, to refit the selected segments.
+    pub const SIMPLIFY: Selector<SimplifySettings> = Selector::new("runebender.simplify");
+
+    /// sent by the 'offset path' menu item; the editor shows a panel where
+    /// the offset can be chosen.
+    pub const SHOW_OFFSET_PATH_PANEL: Selector = Selector::new("runebender.show-offset-path-panel");
+
+    /// sent by the 'expand stroke' menu item; the editor shows a panel where
+    /// the stroke can be chosen.
+    pub const SHOW_EXPAND_STROKE_PANEL: Selector =
+        Selector::new("runebender.show-expand-stroke-panel");
+
+    /// Sent from the offset path panel, to offset the selected closed paths.
+    pub const OFFSET_PATH: Selector<OffsetSettings> = Selector::new("runebender.offset-path");
+
+    /// Sent from the expand stroke panel, to outline the selected open paths.
+    pub const EXPAND_STROKE: Selector<StrokeSettings> = Selector::new("runebender.expand-stroke");
+
+    /// sent by the 'corners' menu item; the editor shows a panel where the
+    /// corner shape can be chosen.
+    pub const SHOW_CORNER_PANEL: Selector = Selector::new("runebender.show-corner-panel");
+
+    /// Sent from the corner panel, to modify the selected corner points.
+    pub const MODIFY_CORNERS: Selector<CornerSettings> = Selector::new("runebender.modify-corners");
+
+    /// sent by the 'remove overlap' menu item in the Glyph menu; this acts
+    /// on the glyph selected in the main window.
+    pub const REMOVE_OVERLAP_IN_SELECTED_GLYPH: Selector =
+        Selector::new("runebender.remove-overlap-in-selected-glyph");
+
+    /// Sent to the editor of the glyph selected in the main window, when that
+    /// glyph is open, so that removing its overlaps can be undone there.
+    pub const REMOVE_OVERLAP_IN_GLYPH: Selector =
+        Selector::new("runebender.remove-overlap-in-glyph");
+
+    /// sent by the 'export' menu item
+    pub const EXPORT: Selector = Selector::new("runebender.export");
+
+    /// Toggles whether overlaps are removed from exported fonts.
+    pub const TOGGLE_REMOVE_OVERLAPS_ON_EXPORT: Selector =
+        Selector::new("runebender.toggle-remove-overlaps-on-export");
+
     /// Sent when a new tool has been selected.
     ///
     /// The payload must be a `ToolId`.
@@ -76,6 +205,35 @@ pub mod cmd {
     /// Sent when the 'reset zoom' menu item is selected
     pub const ZOOM_DEFAULT: Selector = Selector::new("runebender.zoom-default");
 
+    /// sent by the 'snapping' menu item in the View menu; the editor shows a
+    /// panel where snapping can be configured.
+    pub const SHOW_SNAP_PANEL: Selector = Selector::new("runebender.show-snap-panel");
+
+    /// sent by the 'knife options' menu item in the Tools menu; the editor
+    /// shows a panel where the knife's mode can be chosen.
+    pub const SHOW_KNIFE_PANEL: Selector = Selector::new("runebender.show-knife-panel");
+
+    /// sent by the 'shape options' menu item in the Tools menu; the editor
+    /// shows a panel with the options of the shape tools.
+    pub const SHOW_SHAPE_OPTIONS_PANEL: Selector =
+        Selector::new("runebender.show-shape-options-panel");
+
+    /// Sent by the shape tools when the canvas is clicked without dragging;
+    /// the editor shows a panel where the dimensions of a shape of this kind,
+    /// placed at this point, can be entered.
+    pub const SHOW_SHAPE_PANEL: Selector<(ShapeKind, DPoint)> =
+        Selector::new("runebender.show-shape-panel");
+
+    /// Sent from the shape panel, to add a shape with exact dimensions.
+    pub const ADD_SHAPE: Selector<ShapeArgs> = Selector::new("runebender.add-shape");
+
+    /// Toggles whether curvature combs are drawn in the editor.
+    pub const TOGGLE_CURVATURE_COMB: Selector = Selector::new("runebender.toggle-curvature-comb");
+
+    /// sent by the 'curvature comb' menu item in the View menu; the editor
+    /// shows a panel where the comb's scale and density can be chosen.
+    pub const SHOW_COMB_PANEL: Selector = Selector::new("runebender.show-comb-panel");
+
     /// Sent when the 'add guide' context menu item is selected
     ///
     /// The arguments **must** be a `Point`, where the guide will be added.
@@ -92,6 +250,21 @@ pub mod cmd {
         pub pos: Point,
     }
 
+    /// Sent when one of the 'add stem probe' context menu items is selected
+    ///
+    /// The arguments **must** be a `StemProbeCmdArgs`.
+    pub const ADD_STEM_PROBE: Selector<StemProbeCmdArgs> =
+        Selector::new("runebender.add-stem-probe");
+
+    /// Arguments passed along with the ADD_STEM_PROBE command
+    pub struct StemProbeCmdArgs {
+        pub horizontal: bool,
+        pub pos: Point,
+    }
+
+    /// Removes all pinned measurements and stem probes from the current glyph.
+    pub const CLEAR_MEASUREMENTS: Selector = Selector::new("runebender.clear-measurements");
+
     /// A hack: asks the editor view to take focus, so that it can handle
     /// keyboard events.
     ///
@@ -122,4 +295,23 @@ pub mod cmd {
         pub scale: Vec2,
         pub origin: DPoint,
     }
+
+    /// Sent from the coord panel when the selection is rotated or skewed.
+    pub const TRANSFORM_SELECTION: Selector<TransformSelectionArgs> =
+        Selector::new("runebender.editor-transform-selection");
+
+    pub struct TransformSelectionArgs {
+        pub affine: Affine,
+        pub origin: DPoint,
+    }
+
+    /// Sent by the editor when its undo stack changes, so that the history
+    /// panel can be updated.
+    pub const UNDO_HISTORY_CHANGED: Selector<UndoHistory> =
+        Selector::new("runebender.undo-history-changed");
+
+    /// Sent from the history panel when an entry is clicked.
+    ///
+    /// The argument is the index of the undo group to make current.
+    pub const JUMP_TO_UNDO_STATE: Selector<usize> = Selector::new("runebender.jump-to-undo-state");
 }
@@ -0,0 +1,355 @@
+//! Rounding, chamfering and ink-trapping corners.
+//!
+//! A corner is modified by cutting the two segments that meet there at some
+//! distance from the corner point, and connecting the new ends: with a cubic
+//! whose handles follow the tangents of the segments (a rounded corner), with
+//! a straight line (a chamfer), or with a notch that goes past the corner
+//! point (an ink trap.)
+
+use druid::kurbo::{ParamCurve, ParamCurveDeriv, PathSeg as KurboPathSeg, Point, Shape, Vec2};
+use druid::{Data, Lens};
+
+use crate::design_space::DPoint;
+use crate::path::{Path, PathPoint, PathSeg, PointType};
+use crate::selection::Selection;
+
+/// Corners that turn by less than this many radians are left alone.
+const MIN_TURN: f64 = 0.02;
+/// Vectors shorter than this have no direction.
+const EPSILON: f64 = 1e-9;
+
+/// The shape that replaces a corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum CornerKind {
+    /// A circular arc.
+    Round,
+    /// A rounding whose handles are halfway between those of a circular arc
+    /// and the corner point, so that it is flatter near its ends and fuller
+    /// at the corner.
+    SoftRound,
+    Chamfer,
+    /// A notch cut past the corner point. This only applies to inside corners.
+    InkTrap,
+}
+
+/// The arguments to the 'modify corners' command.
+#[derive(Debug, Clone, Copy, Data, Lens)]
+pub struct CornerSettings {
+    /// For rounded corners the radius, and otherwise the distance from the
+    /// corner at which the segments are cut.
+    pub size: f64,
+    pub kind: CornerKind,
+}
+
+impl Default for CornerSettings {
+    fn default() -> Self {
+        CornerSettings {
+            size: 20.0,
+            kind: CornerKind::Round,
+        }
+    }
+}
+
+/// The changes made at a single corner.
+struct Corner {
+    /// The parameter at which the incoming segment is cut.
+    t_in: f64,
+    /// The parameter at which the outgoing segment is cut.
+    t_out: f64,
+    /// The new end of the incoming segment.
+    start: PathPoint,
+    /// The points between `start` and `end`.
+    between: Vec<PathPoint>,
+    /// The new start of the outgoing segment.
+    end: PathPoint,
+}
+
+/// Modify the corners of `path` that are in `selection`.
+///
+/// Only on-curve points that are not smooth are considered corners. Returns
+/// `None` if no corner was changed.
+pub(crate) fn modify_corners(
+    path: &Path,
+    selection: &Selection,
+    settings: CornerSettings,
+) -> Option<Path> {
+    let segs: Vec<PathSeg> = path.iter_segments().collect();
+    let n_segs = segs.len();
+    let n_corners = if path.is_closed() {
+        n_segs
+    } else {
+        n_segs.saturating_sub(1)
+    };
+    let clockwise = path.is_closed() && path.bezier().area() < 0.0;
+
+    // the corner at index `i` is between segments `i` and `i + 1`.
+    let corners: Vec<Option<Corner>> = (0..n_segs)
+        .map(|i| {
+            let point = segs[i].end();
+            if i >= n_corners || point.typ != PointType::OnCurve || !selection.contains(&point.id) {
+                return None;
+            }
+            let incoming = segs[i];
+            let outgoing = segs[(i + 1) % n_segs];
+            modify_corner(path.id(), incoming, outgoing, settings, clockwise)
+        })
+        .collect();
+
+    if corners.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let mut points = Vec::new();
+    if !path.is_closed() {
+        points.push(segs[0].start());
+    }
+    for (i, seg) in segs.iter().enumerate() {
+        let prev = if i == 0 { n_segs - 1 } else { i - 1 };
+        let t_start = corners[prev].as_ref().map(|c| c.t_out).unwrap_or(0.0);
+        let corner = corners[i].as_ref();
+        let t_end = corner.map(|c| c.t_in).unwrap_or(1.0);
+
+        if t_start == 0.0 && t_end == 1.0 {
+            points.extend(seg.points().skip(1));
+        } else {
+            if let KurboPathSeg::Cubic(cubic) = seg.to_kurbo().subsegment(t_start..t_end) {
+                points.push(PathPoint::off_curve(path.id(), DPoint::from_raw(cubic.p1)));
+                points.push(PathPoint::off_curve(path.id(), DPoint::from_raw(cubic.p2)));
+            }
+            points.push(corner.map(|c| c.start).unwrap_or_else(|| seg.end()));
+        }
+
+        if let Some(corner) = corner {
+            points.extend(corner.between.iter().copied());
+            points.push(corner.end);
+        }
+    }
+
+    // where two corners use up all of a line between them, we are left with
+    // a line of zero length.
+    let mut prev = if path.is_closed() {
+        points.last().copied()
+    } else {
+        None
+    };
+    points.retain(|pt| {
+        let duplicate = prev
+            .map(|prev| prev.is_on_curve() && pt.is_on_curve() && prev.point == pt.point)
+            .unwrap_or(false);
+        prev = Some(*pt);
+        !duplicate
+    });
+
+    Some(Path::from_raw_parts(
+        path.id(),
+        points,
+        None,
+        path.is_closed(),
+    ))
+}
+
+fn modify_corner(
+    path_id: usize,
+    incoming: PathSeg,
+    outgoing: PathSeg,
+    settings: CornerSettings,
+    clockwise: bool,
+) -> Option<Corner> {
+    let incoming = incoming.to_kurbo();
+    let outgoing = outgoing.to_kurbo();
+    let corner = outgoing.start();
+    let dir_in = tangent(incoming, 1.0)?;
+    let dir_out = tangent(outgoing, 0.0)?;
+    let turn = dir_in.cross(dir_out).atan2(dir_in.dot(dir_out));
+    if turn.abs() < MIN_TURN || turn.abs() > std::f64::consts::PI - MIN_TURN {
+        return None;
+    }
+
+    let size = settings.size.abs();
+    let distance = match settings.kind {
+        CornerKind::Round | CornerKind::SoftRound => size * (turn.abs() / 2.0).tan(),
+        CornerKind::Chamfer | CornerKind::InkTrap => size,
+    };
+    // leave room for the corners at the other ends of the segments.
+    let max_distance = (incoming.start() - corner)
+        .hypot()
+        .min((outgoing.end() - corner).hypot())
+        / 2.0;
+    let distance = distance.min(max_distance);
+    if distance < 1.0 {
+        return None;
+    }
+
+    let t_in = param_at_distance(incoming, corner, distance, 1.0, 0.0);
+    let t_out = param_at_distance(outgoing, corner, distance, 0.0, 1.0);
+    let p_in = incoming.eval(t_in);
+    let p_out = outgoing.eval(t_out);
+
+    let mut start = PathPoint::on_curve(path_id, DPoint::from_raw(p_in));
+    let mut end = PathPoint::on_curve(path_id, DPoint::from_raw(p_out));
+    let between = match settings.kind {
+        CornerKind::Round | CornerKind::SoftRound => {
+            // for a circular arc turning by `turn`, the handles are this
+            // fraction of the distance from the ends to the corner.
+            let circular = 4.0 / 3.0 * (turn.abs() / 4.0).tan() / (turn.abs() / 2.0).tan();
+            let ratio = if settings.kind == CornerKind::Round {
+                circular
+            } else {
+                (1.0 + circular) / 2.0
+            };
+            let handle_in = tangent(incoming, t_in)? * (corner - p_in).hypot() * ratio;
+            let handle_out = tangent(outgoing, t_out)? * (corner - p_out).hypot() * ratio;
+            start.typ = PointType::OnCurveSmooth;
+            end.typ = PointType::OnCurveSmooth;
+            vec![
+                PathPoint::off_curve(path_id, DPoint::from_raw(p_in + handle_in)),
+                PathPoint::off_curve(path_id, DPoint::from_raw(p_out - handle_out)),
+            ]
+        }
+        CornerKind::Chamfer => Vec::new(),
+        CornerKind::InkTrap => {
+            // an inside corner turns away from the filled area.
+            let is_inside = if clockwise { turn > 0.0 } else { turn < 0.0 };
+            if !is_inside {
+                return None;
+            }
+            let away = corner - p_in.midpoint(p_out);
+            if away.hypot() < EPSILON {
+                return None;
+            }
+            let notch = corner + away.normalize() * distance / 2.0;
+            vec![PathPoint::on_curve(path_id, DPoint::from_raw(notch))]
+        }
+    };
+
+    Some(Corner {
+        t_in,
+        t_out,
+        start,
+        between,
+        end,
+    })
+}
+
+/// The parameter of the point on `seg` at `distance` from `corner`, searching
+/// from `near`, the parameter of the corner, towards `far`.
+fn param_at_distance(seg: KurboPathSeg, corner: Point, distance: f64, near: f64, far: f64) -> f64 {
+    let (mut near, mut far) = (near, far);
+    for _ in 0..32 {
+        let mid = (near + far) / 2.0;
+        if (seg.eval(mid) - corner).hypot() < distance {
+            near = mid;
+        } else {
+            far = mid;
+        }
+    }
+    (near + far) / 2.0
+}
+
+/// The unit tangent of `seg` at `t`, if it has one.
+fn tangent(seg: KurboPathSeg, t: f64) -> Option<Vec2> {
+    let deriv = match seg {
+        KurboPathSeg::Line(line) => line.p1 - line.p0,
+        KurboPathSeg::Quad(quad) => quad.deriv().eval(t).to_vec2(),
+        KurboPathSeg::Cubic(cubic) => {
+            let deriv = cubic.deriv().eval(t).to_vec2();
+            // where a handle is retracted, the direction comes from the
+            // other handle.
+            if deriv.hypot() > EPSILON {
+                deriv
+            } else if t < 0.5 {
+                cubic.p2 - cubic.p0
+            } else {
+                cubic.p3 - cubic.p1
+            }
+        }
+    };
+    if deriv.hypot() > EPSILON {
+        Some(deriv.normalize())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::kurbo::BezPath;
+
+    /// A counter-clockwise square, with all of its points selected.
+    fn square(size: f64) -> (Path, Selection) {
+        let mut bez = BezPath::new();
+        bez.move_to((0.0, 0.0));
+        bez.line_to((size, 0.0));
+        bez.line_to((size, size));
+        bez.line_to((0.0, size));
+        bez.close_path();
+        let path = Path::from_bezpath(bez).unwrap();
+        let selection = path.points().iter().map(|pt| pt.id).collect();
+        (path, selection)
+    }
+
+    fn settings(size: f64, kind: CornerKind) -> CornerSettings {
+        CornerSettings { size, kind }
+    }
+
+    #[test]
+    fn round_corners() {
+        let (path, selection) = square(100.0);
+        let rounded = modify_corners(&path, &selection, settings(10.0, CornerKind::Round)).unwrap();
+        // each corner becomes two on-curve points and two handles.
+        assert_eq!(rounded.points().len(), 16);
+        assert!(rounded
+            .points()
+            .iter()
+            .any(|pt| pt.point == DPoint::new(10.0, 0.0)));
+        let expected = 100.0 * 100.0 - (4.0 - std::f64::consts::PI) * 10.0 * 10.0;
+        // handles are rounded to whole units, so the arcs are approximate.
+        assert!((rounded.bezier().area() - expected).abs() / expected < 0.001);
+
+        // a radius larger than the square makes a circle.
+        let circle = modify_corners(&path, &selection, settings(80.0, CornerKind::Round)).unwrap();
+        let expected = std::f64::consts::PI * 50.0 * 50.0;
+        assert!((circle.bezier().area() - expected).abs() / expected < 0.01);
+    }
+
+    #[test]
+    fn chamfer_one_corner() {
+        let (path, _) = square(100.0);
+        let corner = path
+            .points()
+            .iter()
+            .find(|pt| pt.point == DPoint::new(100.0, 100.0))
+            .unwrap();
+        let selection = std::iter::once(corner.id).collect();
+        let chamfered =
+            modify_corners(&path, &selection, settings(10.0, CornerKind::Chamfer)).unwrap();
+        assert_eq!(chamfered.points().len(), 5);
+        assert!((chamfered.bezier().area() - (100.0 * 100.0 - 50.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ink_trap_inside_corner() {
+        // an 'L', with one inside corner at (20, 20).
+        let mut bez = BezPath::new();
+        bez.move_to((0.0, 0.0));
+        bez.line_to((60.0, 0.0));
+        bez.line_to((60.0, 20.0));
+        bez.line_to((20.0, 20.0));
+        bez.line_to((20.0, 100.0));
+        bez.line_to((0.0, 100.0));
+        bez.close_path();
+        let path = Path::from_bezpath(bez).unwrap();
+        let selection = path.points().iter().map(|pt| pt.id).collect();
+        let trapped =
+            modify_corners(&path, &selection, settings(10.0, CornerKind::InkTrap)).unwrap();
+        assert_eq!(trapped.points().len(), 8);
+        assert!(trapped
+            .points()
+            .iter()
+            .any(|pt| pt.point == DPoint::new(16.0, 16.0)));
+
+        let (square, selection) = square(100.0);
+        assert!(modify_corners(&square, &selection, settings(10.0, CornerKind::InkTrap)).is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! Curvature combs, for judging the quality of curves.
+//!
+//! A comb is a row of 'teeth' along a segment, each perpendicular to the
+//! curve and as long as the curvature at that point, so that uneven or
+//! kinked curvature is easy to see. We also find smooth points where the
+//! curvature jumps between the segments on either side.
+
+use druid::kurbo::{CubicBez, Line, ParamCurve, ParamCurveDeriv, Point, Vec2};
+use druid::{Data, Lens};
+
+use crate::path::{Path, PathSeg, PointType};
+
+/// How much the curvature on either side of a smooth point may differ, as a
+/// fraction of the larger of the two, before we consider it discontinuous.
+const DISCONTINUITY_TOLERANCE: f64 = 0.05;
+
+/// Whether the comb is shown, and how it is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct CombSettings {
+    pub visible: bool,
+    /// The length of a tooth, in design units, for a curvature of 1; a
+    /// circle of radius `r` has a curvature of `1/r`.
+    pub scale: f64,
+    /// The number of teeth along each segment.
+    pub density: usize,
+}
+
+impl Default for CombSettings {
+    fn default() -> Self {
+        CombSettings {
+            visible: false,
+            scale: 5000.0,
+            density: 24,
+        }
+    }
+}
+
+/// The signed curvature of `cubic` at `t`, or `None` where the curve has no
+/// direction, as at a retracted handle.
+///
+/// Curvature is positive where the curve turns to the left.
+pub(crate) fn curvature(cubic: CubicBez, t: f64) -> Option<f64> {
+    let d1 = cubic.deriv().eval(t).to_vec2();
+    let d2 = cubic.deriv().deriv().eval(t).to_vec2();
+    let speed = d1.hypot();
+    if speed < 1e-9 {
+        return None;
+    }
+    Some(d1.cross(d2) / speed.powi(3))
+}
+
+/// The teeth of the comb for `cubic`, from the curve outwards, in design
+/// space.
+///
+/// Teeth point away from the center of curvature.
+pub(crate) fn comb(cubic: CubicBez, settings: &CombSettings) -> Vec<Line> {
+    let density = settings.density.max(1);
+    (0..=density)
+        .filter_map(|i| {
+            let t = i as f64 / density as f64;
+            let k = curvature(cubic, t)?;
+            let tangent = cubic.deriv().eval(t).to_vec2().normalize();
+            let normal = Vec2::new(-tangent.y, tangent.x);
+            let p = cubic.eval(t);
+            Some(Line::new(p, p - normal * (k * settings.scale)))
+        })
+        .collect()
+}
+
+/// The smooth and tangent points in `path` where the curvature is not
+/// continuous.
+pub(crate) fn discontinuities(path: &Path) -> Vec<Point> {
+    let segs: Vec<PathSeg> = path.iter_segments().collect();
+    let n_joins = if path.is_closed() {
+        segs.len()
+    } else {
+        segs.len().saturating_sub(1)
+    };
+    (0..n_joins)
+        .filter_map(|i| {
+            let incoming = segs[i];
+            let outgoing = segs[(i + 1) % segs.len()];
+            let join = incoming.end();
+            if !matches!(
+                join.typ,
+                PointType::OnCurveSmooth | PointType::OnCurveTangent
+            ) {
+                return None;
+            }
+            let k_in = curvature(incoming.to_kurbo().to_cubic(), 1.0)?;
+            let k_out = curvature(outgoing.to_kurbo().to_cubic(), 0.0)?;
+            let largest = k_in.abs().max(k_out.abs());
+            if largest > 1e-9 && (k_in - k_out).abs() > largest * DISCONTINUITY_TOLERANCE {
+                Some(join.point.to_raw())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::kurbo::BezPath;
+
+    #[test]
+    fn circle_comb() {
+        // a quarter circle of radius 100, counter-clockwise about the origin.
+        let k = 4.0 / 3.0 * (2f64.sqrt() - 1.0) * 100.0;
+        let arc = CubicBez::new((100., 0.), (100., k), (k, 100.), (0., 100.));
+        let settings = CombSettings {
+            visible: true,
+            scale: 1000.0,
+            density: 4,
+        };
+        let teeth = comb(arc, &settings);
+        assert_eq!(teeth.len(), 5);
+        for tooth in teeth {
+            // about ten units long, pointing away from the center.
+            assert!((tooth.length() - 10.0).abs() < 0.5);
+            assert!(tooth.p1.to_vec2().hypot() > tooth.p0.to_vec2().hypot());
+        }
+    }
+
+    #[test]
+    fn find_discontinuities() {
+        // a straight line running smoothly into a curve
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.curve_to((50., 0.), (100., 0.), (150., 0.));
+        bez.curve_to((250., 0.), (300., 50.), (300., 150.));
+        let path = Path::from_bezpath(bez).unwrap();
+        assert_eq!(discontinuities(&path), vec![Point::new(150., 0.)]);
+
+        // two halves of a circle
+        let k = 4.0 / 3.0 * (2f64.sqrt() - 1.0) * 100.0;
+        let mut bez = BezPath::new();
+        bez.move_to((100., 0.));
+        bez.curve_to((100., k), (k, 100.), (0., 100.));
+        bez.curve_to((-k, 100.), (-100., k), (-100., 0.));
+        let path = Path::from_bezpath(bez).unwrap();
+        assert!(discontinuities(&path).is_empty());
+    }
+}
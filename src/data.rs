@@ -11,8 +11,19 @@ use druid::{Data, Lens, WindowId};
 use norad::glyph::{Contour, ContourPoint, Glyph, GlyphName, PointType};
 use norad::{FontInfo, Ufo};
 
+use crate::align::AlignSettings;
 use crate::bez_cache::BezCache;
+use crate::component::BaseBounds;
+use crate::corner::CornerSettings;
+use crate::curvature::CombSettings;
 use crate::edit_session::{EditSession, SessionId};
+use crate::fit::SimplifySettings;
+use crate::offset::{OffsetSettings, StrokeSettings};
+use crate::shapes::ShapeSettings;
+use crate::snap::SnapSettings;
+use crate::tools::KnifeSettings;
+use crate::undo::SessionUndo;
+use crate::undo_file;
 
 /// This is by convention.
 const DEFAULT_UNITS_PER_EM: f64 = 1000.;
@@ -37,6 +48,35 @@ pub struct Workspace {
     pub open_glyphs: Arc<HashMap<GlyphName, WindowId>>,
     pub sessions: Arc<HashMap<SessionId, Arc<EditSession>>>,
     session_map: Arc<HashMap<GlyphName, SessionId>>,
+    /// The undo history of each session. This lives here, and not in the
+    /// editor, so that history survives closing and reopening a glyph.
+    undo_states: Arc<HashMap<SessionId, SessionUndo>>,
+    /// If true, undo history is written alongside the font's backups when
+    /// saving, and restored when a glyph is next opened.
+    ///
+    /// This is on for fonts that have saved history when they are opened;
+    /// turning it off deletes that history on the next save.
+    pub persist_undo_history: bool,
+    /// If true, overlaps are removed from every glyph in exported fonts.
+    pub remove_overlaps_on_export: bool,
+    /// The most recently used 'offset path' settings.
+    pub offset_settings: OffsetSettings,
+    /// The most recently used 'expand stroke' settings.
+    pub stroke_settings: StrokeSettings,
+    /// The most recently used corner settings.
+    pub corner_settings: CornerSettings,
+    /// The most recently used 'simplify' settings.
+    pub simplify_settings: SimplifySettings,
+    /// The most recently used align settings.
+    pub align_settings: AlignSettings,
+    /// What points snap to while they are dragged.
+    pub snap_settings: SnapSettings,
+    /// Whether, and how, curvature combs are drawn.
+    pub comb_settings: CombSettings,
+    /// The knife tool's options.
+    pub knife_settings: KnifeSettings,
+    /// The options of the shape tools.
+    pub shape_settings: ShapeSettings,
     // really just a store of the fully resolved Beziers of all glyphs.
     cache: Arc<BezCache>,
     pub info: SimpleFontInfo,
@@ -129,6 +169,7 @@ impl Workspace {
             ufo,
             placeholder: Arc::new(placeholder_outline()),
         };
+        self.persist_undo_history = obj.path.as_deref().map_or(false, undo_file::exists);
         self.font = obj.into();
         self.info = SimpleFontInfo::from_font(&self.font);
         self.build_path_cache();
@@ -167,12 +208,99 @@ impl Workspace {
                     .insert_glyph(glyph);
             }
             font_obj.ufo.save(&path)?;
+            if self.persist_undo_history {
+                let session_map = &self.session_map;
+                let undo_states = &self.undo_states;
+                let histories = session_map
+                    .iter()
+                    .filter_map(|(name, id)| undo_states.get(id).map(|undo| (name, undo)));
+                if let Err(e) = undo_file::save(&path, histories) {
+                    log::warn!("failed to save undo history: '{}'", e);
+                }
+            } else if let Err(e) = undo_file::remove(&path) {
+                log::warn!("failed to remove undo history: '{}'", e);
+            }
         } else {
             log::error!("save called with no path set");
         }
         Ok(())
     }
 
+    /// Write a copy of the font for use by other tools.
+    ///
+    /// The copy is written next to the source, as `<name>_export.ufo`,
+    /// replacing any earlier export once it has been written successfully.
+    /// If `remove_overlaps_on_export` is set, overlaps are removed from the
+    /// copy; the source is never changed.
+    pub fn export(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let source_path = self
+            .font
+            .path
+            .as_ref()
+            .ok_or("export called with no path set")?;
+        let export_path = source_path.with_file_name(format!(
+            "{}_export.ufo",
+            source_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Untitled")
+        ));
+
+        let mut font_obj = self.font.as_ref().clone();
+        font_obj.update_info(&self.info);
+        let layer = font_obj.ufo.get_default_layer_mut().unwrap();
+        for session in self.sessions.values() {
+            layer.insert_glyph(session.to_norad_glyph());
+        }
+        if self.remove_overlaps_on_export {
+            let names: Vec<GlyphName> = font_obj.ufo.iter_names().collect();
+            for name in names {
+                if let Some(glyph) = font_obj.ufo.get_glyph_mut(&name) {
+                    remove_overlap_in_glyph(Arc::make_mut(glyph));
+                }
+            }
+        }
+
+        // write to a temporary location first, so that a failed export
+        // doesn't destroy the previous one.
+        let temp_path = export_path.with_extension("ufo.tmp");
+        if temp_path.exists() {
+            fs::remove_dir_all(&temp_path)?;
+        }
+        font_obj.ufo.save(&temp_path)?;
+        if export_path.exists() {
+            log::info!("replacing previous export at {:?}", export_path);
+            fs::remove_dir_all(&export_path)?;
+        }
+        fs::rename(&temp_path, &export_path)?;
+        Ok(export_path)
+    }
+
+    /// Merge overlapping contours in the glyph selected in the main window.
+    ///
+    /// This is used when the glyph isn't open in an editor; if it has a
+    /// session, the change is added to that session's undo history.
+    pub fn remove_overlap_in_selected_glyph(&mut self) {
+        let name = match self.selected.clone() {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(session_id) = self.session_map.get(&name).copied() {
+            let mut session = self.sessions[&session_id].as_ref().clone();
+            // with nothing selected, every path in the session is merged
+            session.selection.clear();
+            session.remove_overlap();
+            session.rebuild_glyph();
+            self.session_undo(session_id)
+                .lock()
+                .add_undo_group(session.clone(), "Remove overlap");
+            Arc::make_mut(&mut self.sessions).insert(session_id, Arc::new(session));
+        } else if let Some(glyph) = self.font_mut().ufo.get_glyph_mut(&name) {
+            remove_overlap_in_glyph(Arc::make_mut(glyph));
+        }
+        self.invalidate_path(&name);
+    }
+
     pub fn get_or_create_session(&mut self, glyph_name: &GlyphName) -> Arc<EditSession> {
         self.session_map
             .get(glyph_name)
@@ -181,12 +309,35 @@ impl Workspace {
             .unwrap_or_else(|| {
                 let session = Arc::new(EditSession::new(glyph_name, self));
                 let session_id = session.id;
+                let undo = self.load_undo_history(&session);
                 Arc::make_mut(&mut self.sessions).insert(session_id, session.clone());
+                Arc::make_mut(&mut self.undo_states).insert(session_id, undo);
                 Arc::make_mut(&mut self.session_map).insert(glyph_name.clone(), session_id);
                 session
             })
     }
 
+    /// The undo history for the session with this id.
+    pub(crate) fn session_undo(&mut self, id: SessionId) -> SessionUndo {
+        if let Some(undo) = self.undo_states.get(&id) {
+            return undo.clone();
+        }
+        let session = self.sessions.get(&id).expect("missing session");
+        let undo = SessionUndo::new(session.as_ref().clone());
+        Arc::make_mut(&mut self.undo_states).insert(id, undo.clone());
+        undo
+    }
+
+    fn load_undo_history(&self, session: &EditSession) -> SessionUndo {
+        self.font
+            .path
+            .as_ref()
+            .filter(|_| self.persist_undo_history)
+            .and_then(|path| undo_file::load(path, session))
+            .map(SessionUndo::from_undo_state)
+            .unwrap_or_else(|| SessionUndo::new(session.clone()))
+    }
+
     pub(crate) fn get_bezier(&self, name: &GlyphName) -> Option<Arc<BezPath>> {
         self.cache.get(name)
     }
@@ -378,6 +529,22 @@ impl EditorState {
         layout_bounds(&self.session.glyph, &self.metrics)
     }
 
+    /// The current bounds of the base glyphs of this session's components.
+    pub(crate) fn component_base_bounds(&self) -> BaseBounds {
+        self.session
+            .components
+            .iter()
+            .map(|comp| {
+                let bounds = self
+                    .font
+                    .get_bezier(&comp.base)
+                    .map(|bez| bez.bounding_box())
+                    .unwrap_or_default();
+                (comp.base.clone(), bounds)
+            })
+            .collect()
+    }
+
     /// Returns a `Rect` representing, in the coordinate space of the canvas,
     /// the total region occupied by outlines, components, anchors, and the metric
     /// bounds.
@@ -514,6 +681,20 @@ impl<'a> From<&'a FontInfo> for FontMetrics {
     }
 }
 
+impl FontMetrics {
+    /// The heights of the horizontal metric lines: the baseline, x-height,
+    /// cap height, ascender and descender.
+    pub(crate) fn heights(&self) -> Vec<f64> {
+        let upm = self.units_per_em;
+        let ascender = self.ascender.unwrap_or(upm * 0.8);
+        let descender = self.descender.unwrap_or(upm * -0.2);
+        let mut heights = vec![0.0, ascender, descender];
+        heights.extend(self.x_height);
+        heights.extend(self.cap_height);
+        heights
+    }
+}
+
 impl Default for FontMetrics {
     fn default() -> Self {
         FontMetrics {
@@ -923,6 +1104,35 @@ fn placeholder_outline() -> BezPath {
     bez
 }
 
+/// Merge overlapping contours in a glyph, leaving it untouched if there
+/// are none.
+fn remove_overlap_in_glyph(glyph: &mut Glyph) {
+    let outline = match glyph.outline.as_mut() {
+        Some(outline) => outline,
+        None => return,
+    };
+    let paths: Vec<crate::path::Path> = outline
+        .contours
+        .iter()
+        .filter(|contour| !contour.points.is_empty())
+        .map(crate::path::Path::from_norad)
+        .collect();
+    if let Some(merged) = crate::boolean::remove_overlap(&paths) {
+        outline.contours = merged.iter().map(crate::path::Path::to_norad).collect();
+    }
+}
+
+/// The directory in which backups of the font at `path` are kept.
+pub(crate) fn backup_dir(path: &Path) -> PathBuf {
+    let dir_name = format!(
+        "{}_backups",
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled")
+    );
+    path.with_file_name(dir_name)
+}
+
 /// Move the contents of the file at `path` to another location.
 ///
 /// If `path` exists, returns the backup location on success.
@@ -930,13 +1140,7 @@ fn backup_ufo_at_path(path: &Path) -> Result<Option<PathBuf>, std::io::Error> {
     if !path.exists() {
         return Ok(None);
     }
-    let backup_dir = format!(
-        "{}_backups",
-        path.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Untitled")
-    );
-    let mut backup_dir = path.with_file_name(backup_dir);
+    let mut backup_dir = backup_dir(path);
     if !backup_dir.exists() {
         fs::create_dir(&backup_dir)?;
     }
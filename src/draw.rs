@@ -3,20 +3,27 @@
 use std::sync::Arc;
 
 use crate::component::Component;
+use crate::curvature::{self, CombSettings};
 use crate::data::{FontMetrics, Workspace};
 use crate::design_space::ViewPort;
 use crate::edit_session::EditSession;
 use crate::guides::{Guide, GuideLine};
+use crate::measurement::{self, Measurement};
 use crate::path::{Path, PathSeg, PointType};
 use crate::selection::Selection;
 
-use druid::kurbo::{self, Affine, BezPath, Circle, CubicBez, Line, Point, Rect, Vec2};
-use druid::piet::{Color, Piet, RenderContext};
+use druid::kurbo::{
+    self, Affine, BezPath, Circle, CubicBez, Line, ParamCurve, Point, Rect, Size, Vec2,
+};
+use druid::piet::{
+    Color, FontFamily, Piet, RenderContext, StrokeStyle, Text, TextLayout, TextLayoutBuilder,
+};
 use druid::PaintCtx;
 
 use norad::Glyph;
 
 const PATH_COLOR: Color = Color::rgb8(0x00, 0x00, 0x00);
+const LOCKED_PATH_COLOR: Color = Color::grey8(0x99);
 const METRICS_COLOR: Color = Color::rgb8(0xA0, 0xA0, 0xA0);
 const GUIDE_COLOR: Color = Color::rgb8(0xFC, 0x54, 0x93);
 const SELECTED_GUIDE_COLOR: Color = Color::rgb8(0xFE, 0xCD, 0xCD);
@@ -32,6 +39,15 @@ const OFF_CURVE_POINT_INNER_COLOR: Color = Color::grey8(0xCC);
 const OFF_CURVE_HANDLE_COLOR: Color = Color::grey8(0xBB);
 const DIRECTION_ARROW_COLOR: Color = Color::rgba8(0x20, 0x8E, 0x56, 0x99);
 const COMPONENT_FILL_COLOR: Color = Color::rgba8(0, 0, 0, 0x44);
+const SELECTED_COMPONENT_FILL_COLOR: Color = Color::rgba8(0x93, 0xC6, 0xF4, 0xAA);
+const LOCKED_COMPONENT_FILL_COLOR: Color = Color::rgba8(0, 0, 0, 0x22);
+const COMB_COLOR: Color = Color::rgba8(0x57, 0x9A, 0xFF, 0x66);
+const COMB_OUTLINE_COLOR: Color = Color::rgba8(0x44, 0x28, 0xEC, 0x99);
+const CURVATURE_DISCONTINUITY_COLOR: Color = Color::rgb8(0xE8, 0x3A, 0x3A);
+const MEASUREMENT_COLOR: Color = Color::rgb8(0x73, 0x9B, 0xCB);
+const INFO_BUBBLE_BG_COLOR: Color = Color::rgb8(0x70, 0x70, 0x70);
+const INFO_BUBBLE_FG_COLOR: Color = Color::rgb8(0xf8, 0xf8, 0xf8);
+const INFO_BUBBLE_FONT_SIZE: f64 = 9.0;
 
 const SMOOTH_RADIUS: f64 = 5.;
 const SMOOTH_SELECTED_RADIUS: f64 = 6.5;
@@ -39,6 +55,8 @@ const CORNER_RADIUS: f64 = 4.5;
 const CORNER_SELECTED_RADIUS: f64 = 6.;
 const OFF_CURVE_RADIUS: f64 = 3.5;
 const OFF_CURVE_SELECTED_RADIUS: f64 = 5.;
+const CURVATURE_DISCONTINUITY_RADIUS: f64 = 9.;
+const MEASUREMENT_INTERSECTION_RADIUS: f64 = 3.0;
 
 /// A context for drawing that maps between screen space and design space.
 struct DrawCtx<'a, 'b: 'a> {
@@ -90,32 +108,32 @@ impl<'a, 'b: 'a> DrawCtx<'a, 'b> {
         self.stroke(baseline, &METRICS_COLOR, 1.0);
     }
 
-    fn draw_grid(&mut self) {
+    /// Draw a grid with lines every `spacing` design units.
+    fn draw_grid(&mut self, spacing: f64) {
         const MIN_SCALE_FOR_GRID: f64 = 4.0;
 
-        if self.space.zoom >= MIN_SCALE_FOR_GRID {
+        let scale = self.space.zoom * spacing;
+        if spacing > 0.0 && scale >= MIN_SCALE_FOR_GRID {
             // we draw the grid very lightly at low zoom levels.
-            let grid_fade = ((self.space.zoom - MIN_SCALE_FOR_GRID) / 10.)
-                .min(1.0)
-                .max(0.05);
+            let grid_fade = ((scale - MIN_SCALE_FOR_GRID) / 10.).min(1.0).max(0.05);
             let gray_val = 0xFF - (68. * grid_fade) as u8;
             let brush = Color::rgb8(gray_val, gray_val, gray_val);
 
-            let visible_pixels =
-                self.visible_rect.width().max(self.visible_rect.height()) / self.space.zoom;
-            let visible_pixels = visible_pixels.ceil() as usize;
+            let visible_lines = self.visible_rect.width().max(self.visible_rect.height()) / scale;
+            let visible_lines = visible_lines.ceil() as usize;
 
             let view_origin = self.space.inverse_affine() * self.visible_rect.origin();
-            let Point { x, y } = view_origin.round();
+            let x = (view_origin.x / spacing).round() * spacing;
+            let y = (view_origin.y / spacing).round() * spacing;
 
             //NOTE: we are drawing in glyph space; y is up.
 
             // draw one line past what is visible.
-            let x1 = x - 1.;
-            let y1 = y + 1.;
-            let len = 2.0 + visible_pixels as f64;
-            for i in 0..=visible_pixels {
-                let off = i as f64;
+            let x1 = x - spacing;
+            let y1 = y + spacing;
+            let len = (2.0 + visible_lines as f64) * spacing;
+            for i in 0..=visible_lines {
+                let off = i as f64 * spacing;
                 let xmin = self.space.to_screen((x1 + off, y1));
                 let xmax = self.space.to_screen((x1 + off, y1 - len));
                 //TODO: this might mean that we draw lines at different pixel
@@ -194,10 +212,13 @@ impl<'a, 'b: 'a> DrawCtx<'a, 'b> {
     }
 
     fn draw_filled(&mut self, session: &EditSession, font: &Workspace) {
-        let bez = self.space.affine() * session.to_bezier();
-        self.fill(bez, &Color::BLACK);
+        let mut bez = BezPath::new();
+        for path in session.paths.iter().filter(|path| !path.is_hidden()) {
+            path.append_to_bezier(&mut bez);
+        }
+        self.fill(self.space.affine() * bez, &Color::BLACK);
 
-        for comp in session.components.iter() {
+        for comp in session.components.iter().filter(|comp| !comp.hidden) {
             self.draw_component(comp, font, Color::BLACK);
         }
     }
@@ -226,6 +247,65 @@ impl<'a, 'b: 'a> DrawCtx<'a, 'b> {
         }
     }
 
+    /// Draw a curvature comb along each curve in `path`, and circle the
+    /// smooth points where the curvature jumps.
+    fn draw_curvature_comb(&mut self, path: &Path, settings: &CombSettings) {
+        let affine = self.space.affine();
+        for seg in path.iter_segments() {
+            if let PathSeg::Line(..) = seg {
+                continue;
+            }
+            let teeth = curvature::comb(seg.to_kurbo().to_cubic(), settings);
+            let mut outline = BezPath::new();
+            for (i, tooth) in teeth.iter().enumerate() {
+                let tip = affine * tooth.p1;
+                self.stroke(Line::new(affine * tooth.p0, tip), &COMB_COLOR, 1.0);
+                if i == 0 {
+                    outline.move_to(tip);
+                } else {
+                    outline.line_to(tip);
+                }
+            }
+            self.stroke(outline, &COMB_OUTLINE_COLOR, 1.0);
+        }
+
+        for point in curvature::discontinuities(path) {
+            let circ = Circle::new(affine * point, CURVATURE_DISCONTINUITY_RADIUS);
+            self.stroke(circ, &CURVATURE_DISCONTINUITY_COLOR, 2.0);
+        }
+    }
+
+    /// Draw pinned measurements and stem probes, labeled with the distances
+    /// measured along them.
+    fn draw_measurements(&mut self, measurements: &[Measurement], paths: &[Path]) {
+        let affine = self.space.affine();
+        let dashed = StrokeStyle::new().dash(vec![4.0, 3.0], 0.0);
+        for measurement in measurements {
+            let line = match measurement.line(paths) {
+                Some(line) => line,
+                None => continue,
+            };
+            let is_probe = matches!(measurement, Measurement::StemProbe { .. });
+            if is_probe {
+                self.stroke_styled(affine * line, &MEASUREMENT_COLOR, 1.0, &dashed);
+            } else {
+                self.stroke(affine * line, &MEASUREMENT_COLOR, 1.0);
+            }
+            for (t0, t1) in measurement.spans(paths, line) {
+                let span = affine * line.subsegment(t0..t1);
+                if is_probe {
+                    self.stroke(span, &MEASUREMENT_COLOR, 2.0);
+                }
+                for pt in &[span.p0, span.p1] {
+                    let circle = Circle::new(*pt, MEASUREMENT_INTERSECTION_RADIUS);
+                    self.fill(circle, &MEASUREMENT_COLOR);
+                }
+                let (center, len) = measurement::span_label(line, t0, t1);
+                draw_info_bubble(self.ctx, affine * center, format!("{:.1}", len));
+            }
+        }
+    }
+
     fn draw_control_handle(&mut self, p1: Point, p2: Point) {
         let l = Line::new(p1, p2);
         self.stroke(l, &OFF_CURVE_HANDLE_COLOR, 1.0);
@@ -242,7 +322,7 @@ impl<'a, 'b: 'a> DrawCtx<'a, 'b> {
             Style::Close(seg) => self.draw_open_path_terminal(&seg, selected),
             Style::OffCurve => self.draw_off_curve_point(point, selected),
             Style::Smooth => self.draw_smooth_point(point, selected),
-            Style::Tangent => self.draw_smooth_point(point, selected),
+            Style::Tangent => self.draw_tangent_point(point, selected),
             Style::Corner => self.draw_corner_point(point, selected),
         }
     }
@@ -272,6 +352,28 @@ impl<'a, 'b: 'a> DrawCtx<'a, 'b> {
         }
     }
 
+    /// Tangent points are drawn as diamonds, in the smooth point colors.
+    fn draw_tangent_point(&mut self, p: Point, selected: bool) {
+        let radius = if selected {
+            SMOOTH_SELECTED_RADIUS
+        } else {
+            SMOOTH_RADIUS
+        };
+        let mut diamond = BezPath::new();
+        diamond.move_to((p.x, p.y - radius));
+        diamond.line_to((p.x + radius, p.y));
+        diamond.line_to((p.x, p.y + radius));
+        diamond.line_to((p.x - radius, p.y));
+        diamond.close_path();
+        if selected {
+            self.fill(&diamond, &SELECTED_POINT_INNER_COLOR);
+            self.stroke(&diamond, &SELECTED_POINT_OUTER_COLOR, 2.0);
+        } else {
+            self.fill(&diamond, &SMOOTH_POINT_INNER_COLOR);
+            self.stroke(&diamond, &SMOOTH_POINT_OUTER_COLOR, 2.0);
+        }
+    }
+
     fn draw_corner_point(&mut self, p: Point, selected: bool) {
         let radius = if selected {
             CORNER_SELECTED_RADIUS
@@ -383,6 +485,7 @@ impl<'a> PointIter<'a> {
         match this.typ {
             PointType::OnCurve => Style::Corner,
             PointType::OffCurve => Style::OffCurve,
+            PointType::OnCurveTangent => Style::Tangent,
             PointType::OnCurveSmooth => {
                 let prev = self.path.prev_point(this.id);
                 let next = self.path.next_point(this.id);
@@ -428,15 +531,24 @@ pub(crate) fn draw_session(
         return;
     }
 
-    draw_ctx.draw_grid();
+    draw_ctx.draw_grid(font.snap_settings.grid_spacing);
     draw_ctx.draw_metrics(&session.glyph, metrics);
     draw_ctx.draw_guides(&session.guides, &session.selection);
 
-    for path in session.paths.iter() {
+    for path in session.paths.iter().filter(|path| !path.is_hidden()) {
+        if path.is_locked() {
+            // locked paths can't be edited, so we don't draw their points
+            let bez = space.affine() * path.bezier();
+            draw_ctx.stroke(bez, &LOCKED_PATH_COLOR, 1.0);
+            continue;
+        }
         if session.selection.len() > 1 {
             // for a segment to be selected at least two points must be selected
             draw_ctx.draw_selected_segments(path, &session.selection);
         }
+        if font.comb_settings.visible {
+            draw_ctx.draw_curvature_comb(path, &font.comb_settings);
+        }
         let bez = space.affine() * path.bezier();
         draw_ctx.draw_path(&bez);
         draw_ctx.draw_control_point_lines(path);
@@ -453,9 +565,34 @@ pub(crate) fn draw_session(
         }
     }
 
-    for component in session.components.iter() {
-        draw_ctx.draw_component(component, font, COMPONENT_FILL_COLOR);
+    for component in session.components.iter().filter(|comp| !comp.hidden) {
+        let color = if session.selection.contains(&component.id) {
+            SELECTED_COMPONENT_FILL_COLOR
+        } else if component.locked {
+            LOCKED_COMPONENT_FILL_COLOR
+        } else {
+            COMPONENT_FILL_COLOR
+        };
+        draw_ctx.draw_component(component, font, color);
     }
+
+    draw_ctx.draw_measurements(&session.measurements, &session.paths);
+}
+
+/// Draw `label` in a rounded box centered on `pos`, which is in screen space.
+pub(crate) fn draw_info_bubble(ctx: &mut Piet, pos: Point, label: impl Into<String>) {
+    let layout = ctx
+        .text()
+        .new_text_layout(label.into())
+        .font(FontFamily::SYSTEM_UI, INFO_BUBBLE_FONT_SIZE)
+        .text_color(INFO_BUBBLE_FG_COLOR)
+        .build()
+        .unwrap();
+    let width = layout.size().width;
+    let bubble = Rect::from_center_size(pos, Size::new(width + 6.0, 12.0)).to_rounded_rect(6.0);
+    let origin = pos - Vec2::new(0.5 * width, 6.5);
+    ctx.fill(bubble, &INFO_BUBBLE_BG_COLOR);
+    ctx.draw_text(&layout, origin);
 }
 
 /// Return the tangent of the cubic bezier `cb`, at time `t`, as a vector
@@ -1,18 +1,28 @@
 use std::collections::BTreeSet;
 use std::sync::Arc;
 
-use druid::kurbo::{BezPath, ParamCurveNearest, Point, Rect, Shape, Size, Vec2};
+use druid::kurbo::{Affine, BezPath, Line, ParamCurveNearest, Point, Rect, Shape, Size, Vec2};
 use druid::{Data, Lens};
 use norad::glyph::Outline;
 use norad::{Glyph, GlyphName};
 
-use crate::component::Component;
+use crate::align::{self, AlignOp, AlignTarget};
+use crate::boolean::{self, BooleanOp};
+use crate::component::{BaseBounds, Component};
+use crate::corner::{self, CornerSettings};
 use crate::data::Workspace;
 use crate::design_space::{DPoint, DVec2, ViewPort};
-use crate::guides::Guide;
-use crate::path::{EntityId, Path, PathPoint, PathSeg};
+use crate::fit::{self, SimplifySettings};
+use crate::guides::{Guide, GuideLine};
+use crate::locking;
+use crate::measurement::{self, Measurement};
+use crate::offset::{self, OffsetSettings, StrokeSettings};
+use crate::path::{nesting_depth, EntityId, Path, PathPoint, PathSeg, PointType};
 use crate::quadrant::Quadrant;
-use crate::selection::Selection;
+use crate::selection::{SelectSimilar, Selection};
+use crate::shapes::ShapeArgs;
+use crate::snap::{Snap, SnapCandidates, Snapping, SNAP_DISTANCE};
+use crate::spline;
 
 /// Minimum distance in screen units that a click must occur to be considered
 /// on a point?
@@ -21,6 +31,11 @@ pub const MIN_CLICK_DISTANCE: f64 = 10.0;
 /// Amount of bias penalizing on-curve points; we want to break ties in favor
 /// of off-curve.
 pub const ON_CURVE_PENALTY: f64 = MIN_CLICK_DISTANCE / 2.0;
+/// How close, in design units, points must be in height to be selected by
+/// 'select similar' with `SelectSimilar::SameY`.
+const SAME_Y_TOLERANCE: f64 = 0.5;
+/// How far, in design units, removing a redundant point may move the outline.
+const REDUNDANT_POINT_TOLERANCE: f64 = 1.0;
 
 /// A unique identifier for a session. A session keeps the same identifier
 /// even if the name of the glyph changes.
@@ -46,9 +61,15 @@ pub struct EditSession {
     pub selection: Selection,
     pub components: Arc<Vec<Component>>,
     pub guides: Arc<Vec<Guide>>,
+    /// Pinned measurements and stem probes, saved in the glyph lib.
+    pub measurements: Arc<Vec<Measurement>>,
     pub viewport: ViewPort,
     work_bounds: Rect,
     quadrant: Quadrant,
+    /// The object that others are aligned to, when aligning to a key object.
+    ///
+    /// This is a component id, or the id of any point in a path.
+    key_object: Option<EntityId>,
 }
 
 /// A type that is only created by a lens, for our coordinate editing panel
@@ -59,6 +80,20 @@ pub struct CoordinateSelection {
     /// the bounding box of the selection
     pub frame: Rect,
     pub quadrant: Quadrant,
+    /// An angle, in degrees, by which to rotate the selection. This is always
+    /// zero; editing it applies the rotation.
+    pub rotate: f64,
+    /// An angle, in degrees, by which to skew the selection horizontally.
+    /// Like `rotate`, this is always zero.
+    pub skew: f64,
+}
+
+/// Something that is moved as a unit when aligning: a path or a component,
+/// identified by its index.
+#[derive(Debug, Clone, Copy)]
+enum AlignObject {
+    Path(usize),
+    Component(usize),
 }
 
 impl EditSession {
@@ -69,12 +104,20 @@ impl EditSession {
     pub fn new(name: &GlyphName, glyphs: &Workspace) -> Self {
         let name = name.to_owned();
         let glyph = glyphs.font.ufo.get_glyph(&name).unwrap().to_owned();
-        let paths: Vec<Path> = glyph
+        let mut paths: Vec<Path> = glyph
             .outline
             .as_ref()
             .map(|ol| ol.contours.iter().map(Path::from_norad).collect())
             .unwrap_or_default();
-        let components = glyph
+        if let Some(lib) = glyph.lib.as_ref() {
+            attach_splines(&mut paths, lib);
+        }
+        let measurements = glyph
+            .lib
+            .as_ref()
+            .map(measurement::measurements_from_lib)
+            .unwrap_or_default();
+        let mut components: Vec<Component> = glyph
             .outline
             .as_ref()
             .map(|ol| ol.components.iter().map(Component::from_norad).collect())
@@ -84,6 +127,9 @@ impl EditSession {
             .as_ref()
             .map(|guides| guides.iter().map(Guide::from_norad).collect())
             .unwrap_or_default();
+        if let Some(lib) = glyph.lib.as_ref() {
+            locking::flags_from_lib(lib, &mut paths, &mut components);
+        }
 
         //FIXME: this is never updated, and shouldn't be relied on
         let work_bounds = glyphs
@@ -99,9 +145,11 @@ impl EditSession {
             selection: Selection::new(),
             components: Arc::new(components),
             guides: Arc::new(guides),
+            measurements: Arc::new(measurements),
             viewport: ViewPort::default(),
             quadrant: Quadrant::Center,
             work_bounds,
+            key_object: None,
         }
     }
 
@@ -154,6 +202,23 @@ impl EditSession {
         self.paths.iter().flat_map(|p| p.points().iter())
     }
 
+    /// Iterate the points of the paths that are neither locked nor hidden.
+    pub(crate) fn iter_editable_points(&self) -> impl Iterator<Item = &PathPoint> {
+        self.paths
+            .iter()
+            .filter(|p| p.is_editable())
+            .flat_map(|p| p.points().iter())
+    }
+
+    /// Iterate the points of the paths that are not hidden; locked paths are
+    /// still drawn, and so can still be snapped and aligned to.
+    pub(crate) fn iter_visible_points(&self) -> impl Iterator<Item = &PathPoint> {
+        self.paths
+            .iter()
+            .filter(|p| !p.is_hidden())
+            .flat_map(|p| p.points().iter())
+    }
+
     pub(crate) fn paths_for_selection(&self) -> Vec<Path> {
         let mut result = Vec::new();
         for paths in self
@@ -223,7 +288,7 @@ impl EditSession {
     ) -> Option<EntityId> {
         let max_dist = max_dist.unwrap_or(MIN_CLICK_DISTANCE);
         let mut best = None;
-        for p in self.iter_points() {
+        for p in self.iter_editable_points() {
             if f(p) {
                 let dist = p.screen_dist(self.viewport, point);
                 let score = dist
@@ -245,7 +310,7 @@ impl EditSession {
         let max_dist = max_dist.unwrap_or(MIN_CLICK_DISTANCE);
         let dpt = self.viewport.from_screen(point);
         let mut best = None;
-        for path in &*self.paths {
+        for path in self.paths.iter().filter(|p| p.is_editable()) {
             for seg in path.iter_segments() {
                 let kurbo_seg = seg.to_kurbo();
                 let (t, d2) = kurbo_seg.nearest(dpt.to_raw(), 0.1);
@@ -322,6 +387,13 @@ impl EditSession {
         self.paths_mut().extend(paths);
     }
 
+    /// Add a shape with exact dimensions, and select it.
+    pub(crate) fn add_shape(&mut self, args: ShapeArgs) {
+        if let Some(path) = args.to_path() {
+            self.paste_paths(vec![path]);
+        }
+    }
+
     pub fn add_point(&mut self, point: Point) {
         if self
             .active_path_idx()
@@ -341,6 +413,89 @@ impl EditSession {
         self.active_path_mut().unwrap().update_for_drag(drag_point);
     }
 
+    /// Returns `true` if `point` is the first or last point of an open path.
+    pub(crate) fn is_open_endpoint(&self, point: EntityId) -> bool {
+        self.path_for_point(point)
+            .map(|path| path.is_endpoint(point))
+            .unwrap_or(false)
+    }
+
+    /// Continue drawing from an endpoint of an open path, by selecting it.
+    ///
+    /// If it is the start of the path the path is reversed, so that new
+    /// points are added after it. Returns `true` if the path was reversed.
+    pub(crate) fn resume_path(&mut self, point: EntityId) -> bool {
+        self.selection.select_one(point);
+        let path = match self.path_for_point_mut(point) {
+            Some(path) if path.is_endpoint(point) => path,
+            _ => return false,
+        };
+        if path.points().len() > 1 && path.start_point().id == point {
+            path.reverse_contour();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// If we are drawing an open path, and `point` is an endpoint of another
+    /// open path, join the two paths with a segment ending at `point`.
+    ///
+    /// Drawing continues from the far end of the other path. Returns `true`
+    /// if the paths were joined.
+    pub(crate) fn connect_paths(&mut self, point: EntityId) -> bool {
+        let active_ix = match self.active_path_idx() {
+            Some(ix) if !self.paths[ix].is_closed() => ix,
+            _ => return false,
+        };
+        let other_ix = match self.path_idx_for_point(point) {
+            Some(ix) if ix != active_ix && self.paths[ix].is_endpoint(point) => ix,
+            _ => return false,
+        };
+        let mut other = self.paths[other_ix].clone();
+        if other.points().len() > 1 && other.start_point().id != point {
+            other.reverse_contour();
+        }
+        let paths = self.paths_mut();
+        paths[active_ix].append_path(&other);
+        let new_end = paths[active_ix].end_point().id;
+        paths.remove(other_ix);
+        self.selection.select_one(new_end);
+        true
+    }
+
+    /// Cut paths at the selected on-curve points.
+    ///
+    /// Closed paths are opened at a selected point; open paths are split in
+    /// two. The new endpoints are selected.
+    pub(crate) fn break_contours(&mut self) {
+        let selected: Vec<EntityId> = self.selection.iter().copied().collect();
+        let mut selection = self.selection.clone();
+        let mut new_paths = Vec::new();
+        for path in self.paths_mut().iter_mut() {
+            let mut pieces = match path.break_at(&selected) {
+                Some(pieces) => pieces,
+                None => continue,
+            };
+            for pt in path.points() {
+                selection.remove(&pt.id);
+            }
+            let n_pieces = pieces.len();
+            for (i, piece) in pieces.iter().enumerate() {
+                if i > 0 || path.is_closed() {
+                    selection.insert(piece.start_point().id);
+                }
+                if i < n_pieces - 1 || path.is_closed() {
+                    selection.insert(piece.end_point().id);
+                }
+            }
+            *path = pieces.remove(0);
+            new_paths.extend(pieces);
+        }
+        self.paths_mut().extend(new_paths);
+        self.selection = selection;
+    }
+
     /// If there is a single on curve point selected, toggle it between corner and smooth
     pub fn toggle_selected_on_curve_type(&mut self) {
         if self.selection.len() == 1 {
@@ -366,16 +521,19 @@ impl EditSession {
                 path.delete_points(path_points);
             } else if path_points[0].is_guide() {
                 self.guides_mut().retain(|g| !path_points.contains(&g.id));
+            } else if path_points[0].is_component() {
+                self.components_mut()
+                    .retain(|c| !path_points.contains(&c.id));
             }
         }
         self.paths_mut().retain(|p| !p.points().is_empty());
     }
 
-    /// Select all points.
+    /// Select all points, except those in locked or hidden paths.
     //NOTE: should this select other things too? Which ones?
     pub fn select_all(&mut self) {
         self.selection.clear();
-        self.selection = self.iter_points().map(|p| p.id).collect();
+        self.selection = self.iter_editable_points().map(|p| p.id).collect();
     }
 
     /// returns a rect representing the containing rect of the current selection
@@ -420,12 +578,160 @@ impl EditSession {
         self.selection.select_one(id);
     }
 
-    pub fn select_path(&mut self, point: Point, toggle: bool) -> bool {
-        let path_idx = match self
+    /// Select every point of each path that has a selected point.
+    pub fn select_contours(&mut self) {
+        let contours: Vec<_> = self
             .paths
             .iter()
-            .position(|p| p.screen_dist(self.viewport, point) < MIN_CLICK_DISTANCE)
-        {
+            .filter(|path| {
+                path.points()
+                    .iter()
+                    .any(|pt| self.selection.contains(&pt.id))
+            })
+            .flat_map(|path| path.points().iter().map(|pt| pt.id))
+            .collect();
+        self.selection.extend(contours);
+    }
+
+    /// Select the points and components that are not selected, and deselect
+    /// those that are. Locked and hidden items are not selected.
+    pub fn invert_selection(&mut self) {
+        let points = self.iter_editable_points().map(|pt| pt.id);
+        let components = self
+            .components
+            .iter()
+            .filter(|comp| comp.is_editable())
+            .map(|comp| comp.id);
+        let inverted = points
+            .chain(components)
+            .filter(|id| !self.selection.contains(id))
+            .collect();
+        self.selection = inverted;
+    }
+
+    /// Add the points on either side of each selected point to the selection.
+    pub fn grow_selection(&mut self) {
+        let grown: Vec<_> = self
+            .paths
+            .iter()
+            .flat_map(|path| path.grown_selection(&self.selection))
+            .collect();
+        self.selection.extend(grown);
+    }
+
+    /// Deselect the selected points that are next to an unselected point, or
+    /// at the end of an open path.
+    pub fn shrink_selection(&mut self) {
+        let mut shrunk: Selection = self
+            .paths
+            .iter()
+            .flat_map(|path| path.shrunk_selection(&self.selection))
+            .collect();
+        // keep anything that isn't a point, such as components
+        shrunk.extend(
+            self.selection
+                .iter()
+                .filter(|id| self.path_point_for_id(**id).is_none())
+                .copied(),
+        );
+        self.selection = shrunk;
+    }
+
+    /// Select every point of the kind described by `similar`, replacing the
+    /// current selection.
+    pub fn select_similar(&mut self, similar: SelectSimilar) {
+        let heights: Vec<_> = self
+            .selection
+            .iter()
+            .filter_map(|id| self.path_point_for_id(*id))
+            .map(|pt| pt.point.y)
+            .collect();
+        let matches = self
+            .iter_editable_points()
+            .filter(|pt| match similar {
+                SelectSimilar::OffCurve => !pt.is_on_curve(),
+                SelectSimilar::Smooth => {
+                    matches!(pt.typ, PointType::OnCurveSmooth | PointType::OnCurveTangent)
+                }
+                SelectSimilar::SameY => heights
+                    .iter()
+                    .any(|y| (pt.point.y - y).abs() < SAME_Y_TOLERANCE),
+            })
+            .map(|pt| pt.id)
+            .collect();
+        self.selection = matches;
+    }
+
+    /// Snap `pos`, a point in screen space, according to `snapping`. If
+    /// `exclude_selection` is true, selected points are not snapped to; this
+    /// is used when they are the ones being moved.
+    ///
+    /// The returned point is in screen space, and the targets are in design
+    /// space.
+    pub(crate) fn snap_point(
+        &self,
+        pos: Point,
+        exclude_selection: bool,
+        snapping: &Snapping,
+    ) -> Snap {
+        let settings = snapping.settings;
+        let mut candidates = SnapCandidates::default();
+        if settings.grid && settings.grid_spacing > 0.0 {
+            candidates.grid = Some(settings.grid_spacing);
+        }
+        if settings.points {
+            candidates.points = self
+                .iter_visible_points()
+                .filter(|pt| pt.is_on_curve())
+                .filter(|pt| !(exclude_selection && self.selection.contains(&pt.id)))
+                .map(|pt| pt.point.to_raw())
+                .collect();
+        }
+        if settings.guides {
+            for guide in self.guides.iter() {
+                match guide.guide {
+                    GuideLine::Horiz(p) => candidates.horizontals.push(p.y),
+                    GuideLine::Vertical(p) => candidates.verticals.push(p.x),
+                    GuideLine::Angle { p1, p2 } => {
+                        candidates.lines.push(Line::new(p1.to_raw(), p2.to_raw()))
+                    }
+                }
+            }
+        }
+        if settings.metrics {
+            candidates
+                .horizontals
+                .extend(snapping.heights.iter().copied());
+            candidates.verticals.push(0.0);
+            if let Some(advance) = self.glyph.advance.as_ref() {
+                candidates.verticals.push(advance.width as f64);
+            }
+        }
+
+        let design_pos = self.viewport.inverse_affine() * pos;
+        let snap = candidates.snap(design_pos, SNAP_DISTANCE / self.viewport.zoom);
+        Snap {
+            point: self.viewport.affine() * snap.point,
+            targets: snap.targets,
+        }
+    }
+
+    /// Returns the id of the topmost component whose bounds contain `point`.
+    pub fn hit_test_components(&self, point: Point, bases: &BaseBounds) -> Option<EntityId> {
+        self.components
+            .iter()
+            .rev()
+            .find(|comp| {
+                let bbox = self.viewport.rect_to_screen(comp.bounding_box(bases));
+                comp.is_editable() && bbox.contains(point)
+            })
+            .map(|comp| comp.id)
+    }
+
+    pub fn select_path(&mut self, point: Point, toggle: bool) -> bool {
+        let path_idx = match self.paths.iter().position(|p| {
+            p.is_editable() && p.screen_dist(self.viewport, point) < MIN_CLICK_DISTANCE
+        }) {
             Some(idx) => idx,
             None => return false,
         };
@@ -439,6 +745,18 @@ impl EditSession {
         true
     }
 
+    /// The ids of the points that move along with the selection, including
+    /// the handles of selected on-curve points.
+    pub(crate) fn points_moved_with_selection(&self) -> BTreeSet<EntityId> {
+        let mut moved = BTreeSet::new();
+        for path_points in self.selection.per_path_selection().iter() {
+            if let Some(path) = self.path_for_point(path_points[0]) {
+                moved.extend(path.points_moved_with(path_points));
+            }
+        }
+        moved
+    }
+
     pub(crate) fn nudge_selection(&mut self, nudge: DVec2) {
         if self.selection.is_empty() {
             return;
@@ -454,6 +772,12 @@ impl EditSession {
                         guide.nudge(nudge);
                     }
                 }
+            } else if path_points[0].is_component() {
+                for id in path_points {
+                    if let Some(comp) = self.components_mut().iter_mut().find(|c| c.id == *id) {
+                        comp.nudge(nudge);
+                    }
+                }
             }
         }
     }
@@ -496,6 +820,29 @@ impl EditSession {
         }
     }
 
+    /// Apply `affine` to the selection, around `anchor`. Contours that are
+    /// mirrored in their entirety are reversed, to keep their direction.
+    pub(crate) fn transform_selection(&mut self, affine: Affine, anchor: DPoint) {
+        let sel = self.selection.per_path_selection();
+        for path_points in sel.iter() {
+            if let Some(path) = self.path_for_point_mut(path_points[0]) {
+                path.apply_transform(path_points, affine, anchor);
+            }
+        }
+    }
+
+    /// Mirror the selection around the origin chosen in the coord panel.
+    pub(crate) fn flip_selection(&mut self, horizontal: bool) {
+        let bbox = self.selection_dpoint_bbox();
+        let anchor = self.quadrant.point_in_dspace_rect(bbox);
+        let affine = if horizontal {
+            Affine::scale_non_uniform(-1.0, 1.0)
+        } else {
+            Affine::scale_non_uniform(1.0, -1.0)
+        };
+        self.transform_selection(affine, DPoint::from_raw(anchor));
+    }
+
     pub(crate) fn update_handle(&mut self, point: Point, is_locked: bool) {
         let dpoint = self.viewport.from_screen(point);
         let id = *self.selection.iter().next().unwrap();
@@ -525,7 +872,87 @@ impl EditSession {
         }
     }
 
-    pub(crate) fn reverse_contours(&mut self) {
+    /// Set the object that other objects are aligned to.
+    pub(crate) fn set_key_object(&mut self, id: Option<EntityId>) {
+        self.key_object = id;
+    }
+
+    /// The bounds of the key object, if it is part of the selection.
+    pub(crate) fn key_object_bounds(&self, bases: &BaseBounds) -> Option<Rect> {
+        let key = self.key_object?;
+        self.selected_objects(bases)
+            .into_iter()
+            .find(|(obj, _)| match obj {
+                AlignObject::Path(idx) => self.paths[*idx].contains(&key),
+                AlignObject::Component(idx) => self.components[*idx].id == key,
+            })
+            .map(|(_, bounds)| bounds)
+    }
+
+    /// The paths with a selected point, and the selected components, along
+    /// with their bounds.
+    fn selected_objects(&self, bases: &BaseBounds) -> Vec<(AlignObject, Rect)> {
+        let paths = self
+            .paths
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| self.selection.iter().any(|id| path.contains(id)))
+            .map(|(idx, path)| (AlignObject::Path(idx), path.bezier().bounding_box()));
+        let components = self
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, comp)| self.selection.contains(&comp.id))
+            .map(|(idx, comp)| (AlignObject::Component(idx), comp.bounding_box(bases)));
+        paths.chain(components).collect()
+    }
+
+    /// Align or distribute the selected contours and components, moving each
+    /// as a unit.
+    ///
+    /// `metrics` is the glyph's metrics box, used with `AlignTarget::Metrics`.
+    /// If the target is the key object and there isn't one, this aligns to
+    /// the bounds of the selection.
+    pub(crate) fn align_objects(
+        &mut self,
+        op: AlignOp,
+        target: AlignTarget,
+        metrics: Rect,
+        bases: &BaseBounds,
+    ) {
+        let objects = self.selected_objects(bases);
+        if objects.len() < 2 && target == AlignTarget::Selection {
+            return;
+        }
+        let bounds: Vec<Rect> = objects.iter().map(|(_, bounds)| *bounds).collect();
+        let selection_bounds = bounds
+            .iter()
+            .fold(None, |acc: Option<Rect>, rect| {
+                Some(acc.map(|acc| acc.union(*rect)).unwrap_or(*rect))
+            })
+            .unwrap_or_default();
+        let reference = match target {
+            AlignTarget::Selection => selection_bounds,
+            AlignTarget::Metrics => metrics,
+            AlignTarget::KeyObject => self.key_object_bounds(bases).unwrap_or(selection_bounds),
+        };
+
+        let offsets = align::offsets(&bounds, op, reference);
+        for ((obj, _), offset) in objects.iter().zip(offsets) {
+            if offset == Vec2::ZERO {
+                continue;
+            }
+            let offset = DVec2::from_raw(offset);
+            match obj {
+                AlignObject::Path(idx) => self.paths_mut()[*idx].nudge_all_points(offset),
+                AlignObject::Component(idx) => self.components_mut()[*idx].nudge(offset),
+            }
+        }
+    }
+
+    /// The indices of paths with a selected point, or of all paths if
+    /// nothing is selected. Locked and hidden paths are never included.
+    fn selected_path_indices(&self) -> BTreeSet<usize> {
         let mut path_ixs = BTreeSet::new();
         for entity in self.selection.iter() {
             if let Some(path_ix) = self.path_idx_for_point(*entity) {
@@ -535,12 +962,224 @@ impl EditSession {
         if path_ixs.is_empty() {
             path_ixs.extend(0..self.paths.len());
         }
+        path_ixs.retain(|ix| self.paths[*ix].is_editable());
+        path_ixs
+    }
+
+    pub(crate) fn reverse_contours(&mut self) {
+        let path_ixs = self.selected_path_indices();
         let paths = self.paths_mut();
         for ix in path_ixs {
             paths[ix].reverse_contour();
         }
     }
 
+    /// Make each selected on-curve point the start of its closed path.
+    pub(crate) fn set_start_points(&mut self) {
+        let selected: Vec<EntityId> = self.selection.iter().copied().collect();
+        for id in selected {
+            if let Some(ix) = self.path_idx_for_point(id) {
+                self.paths_mut()[ix].set_start_point(id);
+            }
+        }
+    }
+
+    /// Make the selected closed paths run counter-clockwise if they are
+    /// outer contours, and clockwise if they are counters, as determined by
+    /// how they nest inside the other closed paths.
+    pub(crate) fn correct_directions(&mut self) {
+        let path_ixs = self.selected_path_indices();
+        let to_reverse: Vec<usize> = path_ixs
+            .into_iter()
+            .filter(|ix| {
+                let path = &self.paths[*ix];
+                let is_counter = nesting_depth(&self.paths, *ix) % 2 == 1;
+                path.is_closed() && path.is_clockwise() != is_counter
+            })
+            .collect();
+        let paths = self.paths_mut();
+        for ix in to_reverse {
+            paths[ix].reverse_contour();
+        }
+    }
+
+    /// Combine the selected closed paths with a boolean operation, replacing
+    /// them with the result.
+    ///
+    /// The topmost of these paths (the last drawn) is the second operand,
+    /// and the rest are the first; for instance, `Subtract` cuts the topmost
+    /// path out of the others. If nothing is selected, all paths are used.
+    pub(crate) fn boolean_op(&mut self, op: BooleanOp) {
+        let path_ixs: Vec<usize> = self
+            .selected_path_indices()
+            .into_iter()
+            .filter(|ix| self.paths[*ix].is_closed())
+            .collect();
+        if path_ixs.len() < op.min_paths() {
+            return;
+        }
+
+        let (last, rest) = path_ixs.split_last().unwrap();
+        let first_operand: Vec<Path> = rest.iter().map(|ix| self.paths[*ix].clone()).collect();
+        let second_operand = [self.paths[*last].clone()];
+        let result = boolean::boolean_op(&first_operand, &second_operand, op);
+        self.replace_paths(&path_ixs, result);
+    }
+
+    /// Merge overlapping contours. If any paths are selected, only those
+    /// are merged.
+    pub(crate) fn remove_overlap(&mut self) {
+        let path_ixs: Vec<usize> = self.selected_path_indices().into_iter().collect();
+        let paths: Vec<Path> = path_ixs.iter().map(|ix| self.paths[*ix].clone()).collect();
+        if let Some(result) = boolean::remove_overlap(&paths) {
+            self.replace_paths(&path_ixs, result);
+        }
+    }
+
+    /// Replace the selected closed paths with their offset outlines.
+    pub(crate) fn offset_paths(&mut self, settings: OffsetSettings) {
+        let path_ixs: Vec<usize> = self
+            .selected_path_indices()
+            .into_iter()
+            .filter(|ix| self.paths[*ix].is_closed())
+            .collect();
+        self.replace_each_path(&path_ixs, "offset", |path| {
+            offset::offset_path(path, settings)
+        });
+    }
+
+    /// Replace the selected open paths with the outlines of their strokes.
+    pub(crate) fn expand_strokes(&mut self, settings: StrokeSettings) {
+        let path_ixs: Vec<usize> = self
+            .selected_path_indices()
+            .into_iter()
+            .filter(|ix| !self.paths[*ix].is_closed())
+            .collect();
+        self.replace_each_path(&path_ixs, "stroke", |path| {
+            offset::expand_stroke(path, settings)
+        });
+    }
+
+    /// Add on-curve points at the extrema of the curves in the selected paths.
+    pub(crate) fn add_extremes(&mut self) {
+        let path_ixs = self.selected_path_indices();
+        let paths = self.paths_mut();
+        for ix in path_ixs {
+            paths[ix].add_extremes();
+        }
+    }
+
+    /// Make the curvature continuous across the selected smooth points, or
+    /// across every smooth point if nothing is selected.
+    pub(crate) fn harmonize(&mut self) {
+        let path_ixs = self.selected_path_indices();
+        let selected: Vec<EntityId> = self.selection.iter().copied().collect();
+        let paths = self.paths_mut();
+        for ix in path_ixs {
+            if selected.is_empty() {
+                let all: Vec<EntityId> = paths[ix].points().iter().map(|pt| pt.id).collect();
+                paths[ix].harmonize(&all);
+            } else {
+                paths[ix].harmonize(&selected);
+            }
+        }
+    }
+
+    /// Remove points from the selected paths that do not change their shape.
+    pub(crate) fn remove_redundant_points(&mut self) {
+        let path_ixs = self.selected_path_indices();
+        let mut removed = Vec::new();
+        let paths = self.paths_mut();
+        for ix in path_ixs {
+            removed.extend(paths[ix].remove_redundant_points(REDUNDANT_POINT_TOLERANCE));
+        }
+        for id in removed {
+            self.selection.remove(&id);
+        }
+    }
+
+    /// Round, chamfer or ink-trap the selected corner points.
+    ///
+    /// The new points at each modified corner are selected in place of the
+    /// corner point.
+    pub(crate) fn modify_corners(&mut self, settings: CornerSettings) {
+        self.modify_selected_points(|path, selection| {
+            corner::modify_corners(path, selection, settings)
+        });
+    }
+
+    /// Refit the selected segments with as few curves as possible.
+    pub(crate) fn simplify(&mut self, settings: SimplifySettings) {
+        self.modify_selected_points(|path, selection| {
+            fit::simplify_path(path, selection, settings)
+        });
+    }
+
+    /// Replace each path with the result of `f`, if it returns one.
+    ///
+    /// Points that no longer exist are removed from the selection, and new
+    /// on-curve points are added to it.
+    fn modify_selected_points(&mut self, f: impl Fn(&Path, &Selection) -> Option<Path>) {
+        let mut selection = self.selection.clone();
+        for path in self.paths_mut().iter_mut() {
+            if let Some(new_path) = f(path, &selection) {
+                let old_ids: BTreeSet<EntityId> = path.points().iter().map(|pt| pt.id).collect();
+                let new_ids: BTreeSet<EntityId> =
+                    new_path.points().iter().map(|pt| pt.id).collect();
+                for id in old_ids.difference(&new_ids) {
+                    selection.remove(id);
+                }
+                for pt in new_path.points() {
+                    if pt.is_on_curve() && !old_ids.contains(&pt.id) {
+                        selection.insert(pt.id);
+                    }
+                }
+                *path = new_path;
+            }
+        }
+        self.selection = selection;
+    }
+
+    /// Replace each of the paths at `path_ixs` with the paths `f` makes from
+    /// it, and select them. A path for which `f` makes nothing, such as a
+    /// contour offset inwards by more than its width, is kept as it was.
+    fn replace_each_path(
+        &mut self,
+        path_ixs: &[usize],
+        operation: &str,
+        f: impl Fn(&Path) -> Vec<Path>,
+    ) {
+        let mut replaced = Vec::new();
+        let mut result = Vec::new();
+        for ix in path_ixs {
+            let new_paths = f(&self.paths[*ix]);
+            if new_paths.is_empty() {
+                log::warn!(
+                    "{} of path {} is empty, keeping the original",
+                    operation,
+                    ix
+                );
+            } else {
+                replaced.push(*ix);
+                result.extend(new_paths);
+            }
+        }
+        if !replaced.is_empty() {
+            self.replace_paths(&replaced, result);
+        }
+    }
+
+    /// Remove the paths at `path_ixs`, and add and select `new_paths`.
+    fn replace_paths(&mut self, path_ixs: &[usize], new_paths: Vec<Path>) {
+        let mut ix = 0;
+        self.paths_mut().retain(|_| {
+            let keep = !path_ixs.contains(&ix);
+            ix += 1;
+            keep
+        });
+        self.paste_paths(new_paths);
+    }
+
     pub(crate) fn add_guide(&mut self, point: Point) {
         // if one or two points are selected, use them. else use argument point.
         let guide = match self.selection.len() {
@@ -574,6 +1213,80 @@ impl EditSession {
         self.guides_mut().push(guide);
     }
 
+    /// Pin a measurement, so that it is drawn until it is cleared.
+    pub(crate) fn add_measurement(&mut self, measurement: Measurement) {
+        Arc::make_mut(&mut self.measurements).push(measurement);
+    }
+
+    /// Add a stem probe through `point`, which is in screen space.
+    pub(crate) fn add_stem_probe(&mut self, horizontal: bool, point: Point) {
+        let point = DPoint::from_screen(point, self.viewport);
+        let pos = if horizontal { point.y } else { point.x };
+        self.add_measurement(Measurement::StemProbe { horizontal, pos });
+    }
+
+    pub(crate) fn clear_measurements(&mut self) {
+        self.measurements = Arc::new(Vec::new());
+    }
+
+    /// Lock every path with a selected point, and every selected component.
+    pub(crate) fn lock_selection(&mut self) {
+        self.flag_selection(|path| path.set_locked(true), |comp| comp.locked = true);
+    }
+
+    /// Hide every path with a selected point, and every selected component.
+    pub(crate) fn hide_selection(&mut self) {
+        self.flag_selection(|path| path.set_hidden(true), |comp| comp.hidden = true);
+    }
+
+    pub(crate) fn unlock_all(&mut self) {
+        if self.paths.iter().any(Path::is_locked) {
+            self.paths_mut()
+                .iter_mut()
+                .for_each(|path| path.set_locked(false));
+        }
+        if self.components.iter().any(|comp| comp.locked) {
+            self.components_mut()
+                .iter_mut()
+                .for_each(|comp| comp.locked = false);
+        }
+    }
+
+    pub(crate) fn show_all(&mut self) {
+        if self.paths.iter().any(Path::is_hidden) {
+            self.paths_mut()
+                .iter_mut()
+                .for_each(|path| path.set_hidden(false));
+        }
+        if self.components.iter().any(|comp| comp.hidden) {
+            self.components_mut()
+                .iter_mut()
+                .for_each(|comp| comp.hidden = false);
+        }
+    }
+
+    /// Apply `path_fn` to every path with a selected point, and `comp_fn` to
+    /// every selected component, and then clear the selection; locked and
+    /// hidden items can't stay selected.
+    fn flag_selection(&mut self, path_fn: impl Fn(&mut Path), comp_fn: impl Fn(&mut Component)) {
+        let selection = self.selection.clone();
+        if selection.is_empty() {
+            return;
+        }
+        for path in self.paths_mut() {
+            if path.points().iter().any(|pt| selection.contains(&pt.id)) {
+                path_fn(path);
+            }
+        }
+        for comp in self.components_mut() {
+            if selection.contains(&comp.id) {
+                comp_fn(comp);
+            }
+        }
+        self.selection.clear();
+        self.key_object = None;
+    }
+
     /// Convert the current session back into a norad `Glyph`, for saving.
     pub fn to_norad_glyph(&self) -> Glyph {
         let mut glyph = Glyph::new_named("");
@@ -593,10 +1306,51 @@ impl EditSession {
         if !guidelines.is_empty() {
             glyph.guidelines = Some(guidelines);
         }
+
+        let splines: Vec<_> = self
+            .paths
+            .iter()
+            .enumerate()
+            .filter_map(|(i, path)| path.spline().map(|spline| (i, spline)))
+            .collect();
+        glyph.lib = self.glyph.lib.clone();
+        let has_lib_data = !splines.is_empty()
+            || !self.measurements.is_empty()
+            || locking::has_flags(&self.paths, &self.components);
+        if glyph.lib.is_some() || has_lib_data {
+            let mut lib = glyph.lib.take().unwrap_or_default();
+            spline::splines_to_lib(&mut lib, splines);
+            measurement::measurements_to_lib(&mut lib, &self.measurements);
+            locking::flags_to_lib(&mut lib, &self.paths, &self.components);
+            if !lib.is_empty() {
+                glyph.lib = Some(lib);
+            }
+        }
         glyph
     }
 }
 
+/// Reattach the splines saved in a glyph's lib to the paths they generated.
+///
+/// A spline is only attached if solving it still produces the same points,
+/// that is, if the contour has not been edited elsewhere.
+fn attach_splines(paths: &mut [Path], lib: &plist::Dictionary) {
+    for (idx, spline) in spline::splines_from_lib(lib) {
+        if let Some(path) = paths.get_mut(idx) {
+            let solved = Path::from_spline(spline);
+            let same_points = solved.points().len() == path.points().len()
+                && solved
+                    .points()
+                    .iter()
+                    .zip(path.points())
+                    .all(|(one, two)| one.point == two.point && one.typ == two.typ);
+            if same_points && solved.is_closed() == path.is_closed() {
+                *path = solved;
+            }
+        }
+    }
+}
+
 impl CoordinateSelection {
     /// a lens to return the point representation of the current selected coord(s)
     #[allow(non_upper_case_globals)]
@@ -622,6 +1376,8 @@ pub mod lenses {
                 count,
                 quadrant,
                 frame,
+                rotate: 0.0,
+                skew: 0.0,
             })
         }
 
@@ -637,6 +1393,8 @@ pub mod lenses {
                 count,
                 quadrant,
                 frame,
+                rotate: 0.0,
+                skew: 0.0,
             };
             let r = f(&mut sel);
             data.quadrant = sel.quadrant;
@@ -692,3 +1450,85 @@ pub mod lenses {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snap::SnapTarget;
+
+    fn square(x: f64) -> Path {
+        let mut bez = BezPath::new();
+        bez.move_to((x, 0.));
+        bez.line_to((x + 50., 0.));
+        bez.line_to((x + 50., 50.));
+        bez.line_to((x, 50.));
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    fn make_session(paths: Vec<Path>) -> EditSession {
+        let mut workspace = Workspace::default();
+        let name = workspace.add_new_glyph();
+        let mut session = EditSession::new(&name, &workspace);
+        session.paste_paths(paths);
+        session.selection.clear();
+        session
+    }
+
+    #[test]
+    fn commands_skip_locked_paths() {
+        let mut session = make_session(vec![square(0.), square(100.)]);
+        session
+            .selection
+            .extend(session.paths[0].points().iter().map(|pt| pt.id));
+        session.lock_selection();
+        assert!(session.selection.is_empty());
+
+        let before: Vec<_> = session.paths[0].points().to_owned();
+        session.reverse_contours();
+        assert_eq!(session.paths[0].points(), &before[..]);
+        assert!(session.paths[1].is_clockwise());
+        assert!(session.selected_path_indices().iter().all(|ix| *ix == 1));
+    }
+
+    #[test]
+    fn hidden_paths_are_not_snapped_to() {
+        let mut session = make_session(vec![square(0.), square(100.)]);
+        let snapping = Snapping {
+            settings: crate::snap::SnapSettings {
+                metrics: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pos = session.viewport.affine() * Point::new(102., 2.);
+        let snap = session.snap_point(pos, false, &snapping);
+        assert_eq!(snap.targets, vec![SnapTarget::Point(Point::new(100., 0.))]);
+
+        session.paths_mut()[1].set_hidden(true);
+        let snap = session.snap_point(pos, false, &snapping);
+        assert!(snap.targets.is_empty());
+    }
+
+    #[test]
+    fn empty_offsets_keep_the_original() {
+        let mut session = make_session(vec![square(0.)]);
+        let before: Vec<_> = session.paths[0].points().to_owned();
+        session.selection.extend(before.iter().map(|pt| pt.id));
+
+        // further in than the square is wide
+        session.offset_paths(OffsetSettings {
+            distance: -30.,
+            ..Default::default()
+        });
+        assert_eq!(session.paths.len(), 1);
+        assert_eq!(session.paths[0].points(), &before[..]);
+
+        session.offset_paths(OffsetSettings {
+            distance: -20.,
+            ..Default::default()
+        });
+        assert_eq!(session.paths.len(), 1);
+        assert_ne!(session.paths[0].points(), &before[..]);
+    }
+}
@@ -0,0 +1,603 @@
+//! Fitting cubic béziers to points: for simplifying paths by refitting them,
+//! and for turning freehand drawing into paths.
+//!
+//! The fitting is the algorithm from Philip J. Schneider's "An Algorithm for
+//! Automatically Fitting Digitized Curves" (Graphics Gems, 1990): a single
+//! cubic is fit to the points with least squares, and if it is not within
+//! the tolerance the points are split where the error is largest, and each
+//! half is fit on its own.
+
+use druid::kurbo::{
+    BezPath, CubicBez, ParamCurve, ParamCurveArclen, ParamCurveDeriv, PathSeg as KurboPathSeg,
+    Point, Vec2,
+};
+use druid::{Data, Lens};
+
+use crate::design_space::DPoint;
+use crate::path::{Path, PathPoint, PathSeg, PointType};
+use crate::selection::Selection;
+
+/// How many times a fit is improved by reparameterizing before we give up
+/// and split the points.
+const MAX_ITERATIONS: usize = 4;
+/// A bound on how many times the points are split.
+const MAX_DEPTH: usize = 12;
+/// Where the tangent turns by more than this many radians, there is a corner.
+const CORNER_ANGLE: f64 = 0.2;
+/// Where a freehand trail turns by more than this many radians, there is a
+/// corner. This is much larger than `CORNER_ANGLE`, because the direction of
+/// a trail can only be estimated.
+const TRAIL_CORNER_ANGLE: f64 = 1.2;
+/// Vectors shorter than this have no direction.
+const EPSILON: f64 = 1e-9;
+
+/// The arguments to the 'simplify' command.
+#[derive(Debug, Clone, Copy, Data, Lens)]
+pub struct SimplifySettings {
+    /// The maximum distance, in design units, between the new curves and
+    /// the old ones.
+    pub tolerance: f64,
+}
+
+impl Default for SimplifySettings {
+    fn default() -> Self {
+        SimplifySettings { tolerance: 2.0 }
+    }
+}
+
+/// Fit cubics to `points`, so that no point is further than `tolerance`
+/// from the curves.
+///
+/// `start_tangent` and `end_tangent` are the directions the curves should
+/// have at the first and last points, in the direction of travel.
+pub(crate) fn fit_cubics(
+    points: &[Point],
+    start_tangent: Vec2,
+    end_tangent: Vec2,
+    tolerance: f64,
+) -> Vec<CubicBez> {
+    let mut result = Vec::new();
+    if points.len() >= 2 {
+        fit_recursive(
+            points,
+            unit(start_tangent),
+            unit(end_tangent),
+            tolerance.max(EPSILON),
+            0,
+            &mut result,
+        );
+    }
+    result
+}
+
+fn fit_recursive(
+    points: &[Point],
+    start_tangent: Vec2,
+    end_tangent: Vec2,
+    tolerance: f64,
+    depth: usize,
+    result: &mut Vec<CubicBez>,
+) {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    if points.len() == 2 {
+        let handle_len = first.distance(last) / 3.0;
+        result.push(CubicBez::new(
+            first,
+            first + start_tangent * handle_len,
+            last - end_tangent * handle_len,
+            last,
+        ));
+        return;
+    }
+
+    let mut params = chord_length_params(points);
+    let mut cubic = generate_cubic(points, &params, start_tangent, end_tangent);
+    let (error, mut split) = max_error(points, &params, cubic);
+    if error <= tolerance {
+        result.push(cubic);
+        return;
+    }
+
+    // if we are close, improving the parameters is usually enough.
+    if error <= tolerance * 4.0 {
+        for _ in 0..MAX_ITERATIONS {
+            reparameterize(points, &mut params, cubic);
+            cubic = generate_cubic(points, &params, start_tangent, end_tangent);
+            let (error, idx) = max_error(points, &params, cubic);
+            if error <= tolerance {
+                result.push(cubic);
+                return;
+            }
+            split = idx;
+        }
+    }
+
+    if depth >= MAX_DEPTH {
+        result.push(cubic);
+        return;
+    }
+
+    let center_tangent = unit(points[split + 1] - points[split - 1]);
+    fit_recursive(
+        &points[..=split],
+        start_tangent,
+        center_tangent,
+        tolerance,
+        depth + 1,
+        result,
+    );
+    fit_recursive(
+        &points[split..],
+        center_tangent,
+        end_tangent,
+        tolerance,
+        depth + 1,
+        result,
+    );
+}
+
+/// Find the cubic with the given end points and end tangents that best fits
+/// `points`, at `params`, with least squares.
+fn generate_cubic(
+    points: &[Point],
+    params: &[f64],
+    start_tangent: Vec2,
+    end_tangent: Vec2,
+) -> CubicBez {
+    let first = points[0];
+    let last = points[points.len() - 1];
+    let mut c = [[0.0; 2]; 2];
+    let mut x = [0.0; 2];
+    for (pt, &u) in points.iter().zip(params) {
+        let mt = 1.0 - u;
+        let a0 = start_tangent * (3.0 * mt * mt * u);
+        let a1 = -end_tangent * (3.0 * mt * u * u);
+        c[0][0] += a0.dot(a0);
+        c[0][1] += a0.dot(a1);
+        c[1][1] += a1.dot(a1);
+        let on_chord = first.to_vec2() * (mt * mt * mt + 3.0 * mt * mt * u)
+            + last.to_vec2() * (3.0 * mt * u * u + u * u * u);
+        let diff = pt.to_vec2() - on_chord;
+        x[0] += a0.dot(diff);
+        x[1] += a1.dot(diff);
+    }
+    c[1][0] = c[0][1];
+
+    let det = c[0][0] * c[1][1] - c[0][1] * c[1][0];
+    let chord = first.distance(last);
+    let (alpha_start, alpha_end) = if det.abs() > EPSILON {
+        (
+            (x[0] * c[1][1] - x[1] * c[0][1]) / det,
+            (c[0][0] * x[1] - c[1][0] * x[0]) / det,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+
+    // a negative or tiny handle means the fit failed; fall back to handles
+    // a third of the way along the chord.
+    let min_len = chord * 1e-6;
+    let (alpha_start, alpha_end) = if alpha_start < min_len || alpha_end < min_len {
+        (chord / 3.0, chord / 3.0)
+    } else {
+        (alpha_start, alpha_end)
+    };
+    CubicBez::new(
+        first,
+        first + start_tangent * alpha_start,
+        last - end_tangent * alpha_end,
+        last,
+    )
+}
+
+/// Improve the parameters of each point with a step of Newton's method.
+fn reparameterize(points: &[Point], params: &mut [f64], cubic: CubicBez) {
+    let deriv = cubic.deriv();
+    let deriv2 = deriv.deriv();
+    for (pt, u) in points.iter().zip(params.iter_mut()) {
+        let diff = cubic.eval(*u) - *pt;
+        let d1 = deriv.eval(*u).to_vec2();
+        let d2 = deriv2.eval(*u).to_vec2();
+        let denominator = d1.dot(d1) + diff.dot(d2);
+        if denominator.abs() > EPSILON {
+            *u = (*u - diff.dot(d1) / denominator).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// The largest distance between a point and the cubic at its parameter, and
+/// the index of that point.
+fn max_error(points: &[Point], params: &[f64], cubic: CubicBez) -> (f64, usize) {
+    let mut max = 0.0;
+    let mut split = points.len() / 2;
+    for (i, (pt, &u)) in points.iter().zip(params).enumerate() {
+        let dist = cubic.eval(u).distance(*pt);
+        if dist > max {
+            max = dist;
+            split = i;
+        }
+    }
+    // we split at the point, so it cannot be one of the ends.
+    (max, split.max(1).min(points.len() - 2))
+}
+
+/// Parameters for the points proportional to the distance along them.
+fn chord_length_params(points: &[Point]) -> Vec<f64> {
+    let mut params = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    params.push(0.0);
+    for pair in points.windows(2) {
+        total += pair[0].distance(pair[1]);
+        params.push(total);
+    }
+    if total > 0.0 {
+        params.iter_mut().for_each(|u| *u /= total);
+    }
+    params
+}
+
+/// Fit a path to a freehand trail of points.
+///
+/// `tolerance` is the maximum distance between the trail and the path, and
+/// `window` is the distance over which the direction of the trail is
+/// measured when looking for corners; both are in design units. If the trail
+/// ends within `window` of its start, the path is closed.
+pub(crate) fn fit_trail(points: &[Point], tolerance: f64, window: f64) -> Option<Path> {
+    // points that are very close together only add noise.
+    let mut trail: Vec<Point> = Vec::with_capacity(points.len());
+    for pt in points {
+        if trail
+            .last()
+            .map(|last| last.distance(*pt) >= tolerance / 2.0)
+            != Some(false)
+        {
+            trail.push(*pt);
+        }
+    }
+    if trail.len() < 2 {
+        return None;
+    }
+
+    let closed = trail.len() > 3 && trail[0].distance(trail[trail.len() - 1]) < window;
+    if closed {
+        let last = trail.len() - 1;
+        trail[last] = trail[0];
+    }
+    let mut corners = find_corners(&trail, window);
+    // a closed path should start at a corner, if it has one.
+    if let (true, Some(&first_corner)) = (closed, corners.first()) {
+        trail.pop();
+        trail.rotate_left(first_corner);
+        trail.push(trail[0]);
+        corners = find_corners(&trail, window);
+    }
+
+    let mut breaks = vec![0];
+    breaks.extend(corners.iter().copied());
+    breaks.push(trail.len() - 1);
+    breaks.dedup();
+
+    // a closed trail that starts smoothly needs the same tangent at each end.
+    let smooth_start = if closed && corners.is_empty() {
+        let before = trail[..trail.len() - 1]
+            .iter()
+            .rev()
+            .find(|pt| pt.distance(trail[0]) >= window);
+        let after = trail.iter().find(|pt| pt.distance(trail[0]) >= window);
+        match (before, after) {
+            (Some(before), Some(after)) => Some(*after - *before),
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let mut bez = BezPath::new();
+    bez.move_to(trail[0]);
+    for (i, range) in breaks.windows(2).enumerate() {
+        let piece = &trail[range[0]..=range[1]];
+        let start = match smooth_start {
+            Some(tangent) if i == 0 => tangent,
+            _ => direction_away(piece[0], piece.iter(), window),
+        };
+        let end = match smooth_start {
+            Some(tangent) if i == breaks.len() - 2 => tangent,
+            _ => -direction_away(piece[piece.len() - 1], piece.iter().rev(), window),
+        };
+        for cubic in fit_cubics(piece, start, end, tolerance) {
+            bez.curve_to(cubic.p1, cubic.p2, cubic.p3);
+        }
+    }
+    if closed {
+        bez.close_path();
+    }
+    Path::from_bezpath(bez).ok()
+}
+
+/// Find the points where a freehand trail turns sharply, returning their
+/// indices.
+///
+/// The turn at each point is measured between the points about `window`
+/// away on either side, so that small jitters are ignored. Points closer
+/// than `window` to the ends of the trail are never corners.
+pub(crate) fn find_corners(points: &[Point], window: f64) -> Vec<usize> {
+    let angles: Vec<f64> = (0..points.len())
+        .map(|i| {
+            let pt = points[i];
+            let before = points[..i].iter().rev().find(|p| p.distance(pt) >= window);
+            let after = points[i + 1..].iter().find(|p| p.distance(pt) >= window);
+            match (before, after) {
+                (Some(before), Some(after)) => {
+                    let incoming = pt - *before;
+                    let outgoing = *after - pt;
+                    incoming.cross(outgoing).atan2(incoming.dot(outgoing)).abs()
+                }
+                _ => 0.0,
+            }
+        })
+        .collect();
+
+    // a turn shows up at several points; the corner is the sharpest of them.
+    let mut corners: Vec<usize> = Vec::new();
+    for (i, angle) in angles.iter().enumerate() {
+        let is_sharpest = points
+            .iter()
+            .zip(&angles)
+            .filter(|(pt, _)| pt.distance(points[i]) < window)
+            .all(|(_, other)| other <= angle);
+        let is_new = corners
+            .last()
+            .map(|prev| points[*prev].distance(points[i]) >= window)
+            .unwrap_or(true);
+        if *angle > TRAIL_CORNER_ANGLE && is_sharpest && is_new {
+            corners.push(i);
+        }
+    }
+    corners
+}
+
+/// The direction from `start` to the first of `points` at least `window`
+/// away from it, or to the last of them, if none are.
+fn direction_away<'a>(start: Point, points: impl Iterator<Item = &'a Point>, window: f64) -> Vec2 {
+    let mut towards = start;
+    for pt in points {
+        towards = *pt;
+        if pt.distance(start) >= window {
+            break;
+        }
+    }
+    towards - start
+}
+
+/// Refit the runs of segments in `path` whose start and end points are both
+/// selected, with as few cubics as will fit within the tolerance.
+///
+/// Points where the tangent is discontinuous are kept. Returns `None` if
+/// nothing changed.
+pub(crate) fn simplify_path(
+    path: &Path,
+    selection: &Selection,
+    settings: SimplifySettings,
+) -> Option<Path> {
+    let segs: Vec<PathSeg> = path.iter_segments().collect();
+    let n_segs = segs.len();
+    let is_selected =
+        |seg: &PathSeg| selection.contains(&seg.start_id()) && selection.contains(&seg.end_id());
+
+    // for a closed path, we start at an unselected segment, if there is one,
+    // so that no run wraps around the end.
+    let offset = if path.is_closed() {
+        segs.iter().position(|seg| !is_selected(seg)).unwrap_or(0)
+    } else {
+        0
+    };
+    let segs: Vec<PathSeg> = (0..n_segs).map(|i| segs[(i + offset) % n_segs]).collect();
+
+    let mut points = Vec::new();
+    if !path.is_closed() {
+        points.push(segs[0].start());
+    }
+    let mut changed = false;
+    let mut i = 0;
+    while i < n_segs {
+        if !is_selected(&segs[i]) {
+            points.extend(segs[i].points().skip(1));
+            i += 1;
+            continue;
+        }
+        let run_len = segs[i..].iter().take_while(|seg| is_selected(seg)).count();
+        for piece in split_at_corners(&segs[i..i + run_len]) {
+            match refit(path.id(), piece, settings.tolerance) {
+                Some(new_points) => {
+                    points.extend(new_points);
+                    changed = true;
+                }
+                None => points.extend(piece.iter().flat_map(|seg| seg.points().skip(1))),
+            }
+        }
+        i += run_len;
+    }
+
+    if !changed {
+        return None;
+    }
+
+    // keep the original start point of a closed path, if it is still there.
+    if path.is_closed() {
+        let start_id = path.start_point().id;
+        if let Some(idx) = points.iter().position(|pt| pt.id == start_id) {
+            points.rotate_left(idx + 1);
+        }
+    }
+    Some(Path::from_raw_parts(
+        path.id(),
+        points,
+        None,
+        path.is_closed(),
+    ))
+}
+
+/// Split a run of segments where the tangent is discontinuous.
+fn split_at_corners(segs: &[PathSeg]) -> Vec<&[PathSeg]> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for i in 1..segs.len() {
+        let incoming = end_tangent(segs[i - 1].to_kurbo());
+        let outgoing = start_tangent(segs[i].to_kurbo());
+        let angle = incoming.cross(outgoing).atan2(incoming.dot(outgoing));
+        if angle.abs() > CORNER_ANGLE {
+            pieces.push(&segs[start..i]);
+            start = i;
+        }
+    }
+    pieces.push(&segs[start..]);
+    pieces
+}
+
+/// Fit new cubics to a run of smoothly connected segments, returning the
+/// points that replace all but the first point of the run. Returns `None`
+/// if the fit does not have fewer segments.
+fn refit(path_id: usize, segs: &[PathSeg], tolerance: f64) -> Option<Vec<PathPoint>> {
+    if segs.len() < 2 {
+        return None;
+    }
+    let samples = sample_points(segs);
+    let first = segs[0].to_kurbo();
+    let last = segs[segs.len() - 1].to_kurbo();
+    let cubics = fit_cubics(&samples, start_tangent(first), end_tangent(last), tolerance);
+    if cubics.is_empty() || cubics.len() >= segs.len() {
+        return None;
+    }
+
+    let mut points = Vec::new();
+    for (i, cubic) in cubics.iter().enumerate() {
+        points.push(PathPoint::off_curve(path_id, DPoint::from_raw(cubic.p1)));
+        points.push(PathPoint::off_curve(path_id, DPoint::from_raw(cubic.p2)));
+        if i == cubics.len() - 1 {
+            points.push(segs[segs.len() - 1].end());
+        } else {
+            let mut point = PathPoint::on_curve(path_id, DPoint::from_raw(cubic.p3));
+            point.typ = PointType::OnCurveSmooth;
+            points.push(point);
+        }
+    }
+    Some(points)
+}
+
+/// Points along a run of segments, close enough together to fit to.
+fn sample_points(segs: &[PathSeg]) -> Vec<Point> {
+    let mut points = vec![segs[0].to_kurbo().start()];
+    for seg in segs {
+        let seg = seg.to_kurbo();
+        let n_samples = (seg.arclen(0.1) / 4.0).ceil().clamp(8.0, 64.0) as usize;
+        points.extend((1..=n_samples).map(|i| seg.eval(i as f64 / n_samples as f64)));
+    }
+    points
+}
+
+fn start_tangent(seg: KurboPathSeg) -> Vec2 {
+    match seg {
+        KurboPathSeg::Cubic(cubic) if cubic.p1 != cubic.p0 => unit(cubic.p1 - cubic.p0),
+        KurboPathSeg::Cubic(cubic) if cubic.p2 != cubic.p0 => unit(cubic.p2 - cubic.p0),
+        _ => unit(seg.end() - seg.start()),
+    }
+}
+
+fn end_tangent(seg: KurboPathSeg) -> Vec2 {
+    match seg {
+        KurboPathSeg::Cubic(cubic) if cubic.p3 != cubic.p2 => unit(cubic.p3 - cubic.p2),
+        KurboPathSeg::Cubic(cubic) if cubic.p3 != cubic.p1 => unit(cubic.p3 - cubic.p1),
+        _ => unit(seg.end() - seg.start()),
+    }
+}
+
+fn unit(vec: Vec2) -> Vec2 {
+    if vec.hypot() > EPSILON {
+        vec.normalize()
+    } else {
+        Vec2::new(1.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::kurbo::{BezPath, ParamCurveNearest};
+
+    #[test]
+    fn fit_arc() {
+        // a quarter of a circle, which a single cubic fits closely.
+        let points: Vec<Point> = (0..=20)
+            .map(|i| {
+                let angle = std::f64::consts::FRAC_PI_2 * i as f64 / 20.0;
+                Point::new(100.0 * angle.cos(), 100.0 * angle.sin())
+            })
+            .collect();
+        let cubics = fit_cubics(&points, Vec2::new(0.0, 1.0), Vec2::new(-1.0, 0.0), 0.5);
+        assert_eq!(cubics.len(), 1);
+        for pt in &points {
+            assert!(cubics[0].nearest(*pt, 0.01).1.sqrt() < 0.5);
+        }
+    }
+
+    #[test]
+    fn simplify_split_curve() {
+        // a curve that has been split into many pieces, and an outside corner.
+        let curve = CubicBez::new((0., 0.), (0., 100.), (100., 100.), (100., 0.));
+        let mut bez = BezPath::new();
+        bez.move_to(curve.p0);
+        for i in 0..6 {
+            let piece = curve.subsegment(i as f64 / 6.0..(i + 1) as f64 / 6.0);
+            bez.curve_to(piece.p1, piece.p2, piece.p3);
+        }
+        bez.close_path();
+        let path = Path::from_bezpath(bez).unwrap();
+        let selection = path.points().iter().map(|pt| pt.id).collect();
+
+        let simple = simplify_path(&path, &selection, SimplifySettings::default()).unwrap();
+        let on_curves: Vec<_> = simple
+            .points()
+            .iter()
+            .filter(|pt| pt.is_on_curve())
+            .collect();
+        assert!(on_curves.len() <= 3, "{:?}", on_curves);
+        // the corners are kept.
+        assert!(on_curves.iter().any(|pt| pt.point == DPoint::new(0., 0.)));
+        assert!(on_curves.iter().any(|pt| pt.point == DPoint::new(100., 0.)));
+    }
+
+    #[test]
+    fn fit_trail_with_corner() {
+        // down the y axis, and then along the x axis.
+        let trail: Vec<Point> = (0..=50)
+            .map(|i| Point::new(0., 100. - i as f64 * 2.))
+            .chain((1..=50).map(|i| Point::new(i as f64 * 2., 0.)))
+            .collect();
+        assert_eq!(find_corners(&trail, 10.0), vec![50]);
+
+        let path = fit_trail(&trail, 1.0, 10.0).unwrap();
+        assert!(!path.is_closed());
+        let on_curves: Vec<_> = path.points().iter().filter(|pt| pt.is_on_curve()).collect();
+        assert_eq!(on_curves.len(), 3);
+        assert_eq!(on_curves[1].point, DPoint::new(0., 0.));
+        assert_eq!(on_curves[1].typ, PointType::OnCurve);
+    }
+
+    #[test]
+    fn fit_closed_trail() {
+        let trail: Vec<Point> = (0..=60)
+            .map(|i| {
+                let angle = std::f64::consts::PI * 2.0 * i as f64 / 60.0;
+                Point::new(100.0 * angle.cos(), 100.0 * angle.sin())
+            })
+            .collect();
+        let path = fit_trail(&trail, 1.0, 10.0).unwrap();
+        assert!(path.is_closed());
+        assert!(path
+            .points()
+            .iter()
+            .filter(|pt| pt.is_on_curve())
+            .all(|pt| pt.typ == PointType::OnCurveSmooth));
+    }
+}
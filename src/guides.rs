@@ -70,6 +70,26 @@ impl Guide {
         }
     }
 
+    /// Returns `true` if this guide passes through the polygon with the
+    /// vertices `polygon`, in screen space.
+    pub fn crosses_screen_polygon(&self, vport: ViewPort, polygon: &[Point]) -> bool {
+        let (origin, dir) = match self.guide {
+            GuideLine::Horiz(p) => (p.to_screen(vport), Vec2::new(1.0, 0.0)),
+            GuideLine::Vertical(p) => (p.to_screen(vport), Vec2::new(0.0, 1.0)),
+            GuideLine::Angle { p1, p2 } => {
+                let p1 = p1.to_screen(vport);
+                (p1, p2.to_screen(vport) - p1)
+            }
+        };
+        // the guide is infinite, so it crosses the polygon if there are
+        // vertices on both sides of it.
+        let sides = polygon.iter().map(|pt| dir.cross(*pt - origin));
+        let (min, max) = sides.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), side| {
+            (min.min(side), max.max(side))
+        });
+        min <= 0.0 && max >= 0.0
+    }
+
     pub fn nudge(&mut self, nudge: DVec2) {
         match self.guide {
             GuideLine::Horiz(ref mut p) => p.y += nudge.y,
@@ -125,3 +145,32 @@ impl Guide {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guide_crosses_polygon() {
+        let vport = ViewPort::default();
+        let square = [
+            Point::new(0., 0.),
+            Point::new(10., 0.),
+            Point::new(10., 10.),
+            Point::new(0., 10.),
+        ];
+        let inside = DPoint::from_screen(Point::new(5., 5.), vport);
+        let outside = DPoint::from_screen(Point::new(20., 20.), vport);
+        assert!(Guide::horiz(inside).crosses_screen_polygon(vport, &square));
+        assert!(Guide::vertical(inside).crosses_screen_polygon(vport, &square));
+        assert!(!Guide::horiz(outside).crosses_screen_polygon(vport, &square));
+        assert!(!Guide::vertical(outside).crosses_screen_polygon(vport, &square));
+
+        // a diagonal guide that only passes near the square
+        let p1 = DPoint::from_screen(Point::new(15., 0.), vport);
+        let p2 = DPoint::from_screen(Point::new(30., 15.), vport);
+        assert!(!Guide::angle(p1, p2).crosses_screen_polygon(vport, &square));
+        let p2 = DPoint::from_screen(Point::new(0., 15.), vport);
+        assert!(Guide::angle(p1, p2).crosses_screen_polygon(vport, &square));
+    }
+}
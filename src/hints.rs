@@ -0,0 +1,253 @@
+//! Alignment hints, shown while a point is dragged.
+//!
+//! A hint appears when the dragged point lines up horizontally or vertically
+//! with another point, or when it is as far from its neighbor in such a line
+//! as that neighbor is from the next one. Points near an alignment are
+//! pulled onto it.
+//!
+//! The other points are indexed once, when the drag begins, by sorting them
+//! on each axis; each query is then a couple of binary searches.
+
+use std::cmp::Ordering;
+
+use druid::kurbo::{Line, Point};
+
+/// The points that a dragged point may line up with, sorted on each axis.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AlignmentIndex {
+    /// Sorted by x, then y.
+    by_x: Vec<Point>,
+    /// Sorted by y, then x.
+    by_y: Vec<Point>,
+}
+
+/// The result of a query: the adjusted point, and lines to draw, in design
+/// space.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Hints {
+    pub point: Point,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Clone, Copy)]
+enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    /// The coordinate along this axis.
+    fn major(self, pt: Point) -> f64 {
+        match self {
+            Axis::X => pt.x,
+            Axis::Y => pt.y,
+        }
+    }
+
+    /// The other coordinate.
+    fn minor(self, pt: Point) -> f64 {
+        match self {
+            Axis::X => pt.y,
+            Axis::Y => pt.x,
+        }
+    }
+
+    fn set_major(self, pt: &mut Point, val: f64) {
+        match self {
+            Axis::X => pt.x = val,
+            Axis::Y => pt.y = val,
+        }
+    }
+}
+
+impl AlignmentIndex {
+    pub(crate) fn new(points: impl IntoIterator<Item = Point>) -> Self {
+        let mut by_x: Vec<Point> = points.into_iter().collect();
+        let mut by_y = by_x.clone();
+        by_x.sort_by(|a, b| (a.x, a.y).partial_cmp(&(b.x, b.y)).unwrap());
+        by_y.sort_by(|a, b| (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap());
+        AlignmentIndex { by_x, by_y }
+    }
+
+    fn sorted(&self, axis: Axis) -> &[Point] {
+        match axis {
+            Axis::X => &self.by_x,
+            Axis::Y => &self.by_y,
+        }
+    }
+
+    /// The points whose coordinate on `axis` is exactly `val`, sorted on the
+    /// other axis.
+    fn line_at(&self, axis: Axis, val: f64) -> &[Point] {
+        let sorted = self.sorted(axis);
+        let start = partition_point(sorted, |pt| axis.major(*pt) < val);
+        let end = partition_point(sorted, |pt| axis.major(*pt) <= val);
+        &sorted[start..end]
+    }
+
+    /// The coordinate on `axis` nearest to `val`, within `tolerance`.
+    fn nearest(&self, axis: Axis, val: f64, tolerance: f64) -> Option<f64> {
+        let sorted = self.sorted(axis);
+        let idx = partition_point(sorted, |pt| axis.major(*pt) < val);
+        let below = idx.checked_sub(1).map(|i| axis.major(sorted[i]));
+        let above = sorted.get(idx).map(|pt| axis.major(*pt));
+        below
+            .into_iter()
+            .chain(above)
+            .filter(|v| (v - val).abs() <= tolerance)
+            .min_by(|a, b| (a - val).abs().partial_cmp(&(b - val).abs()).unwrap())
+    }
+
+    /// Find the alignments of `point`, moving it onto any that are within
+    /// `tolerance`.
+    pub(crate) fn query(&self, point: Point, tolerance: f64) -> Hints {
+        let axes = [Axis::X, Axis::Y];
+        let mut point = point;
+        let mut aligned = [false, false];
+        for (i, axis) in axes.iter().enumerate() {
+            if let Some(val) = self.nearest(*axis, axis.major(point), tolerance) {
+                axis.set_major(&mut point, val);
+                aligned[i] = true;
+            }
+        }
+
+        // equal spacing, within a line of points that we are aligned with
+        // on only one axis: if we share an x, we are spaced along y.
+        let mut gaps = Vec::new();
+        for (i, axis) in axes.iter().enumerate() {
+            if !aligned[i] || aligned[1 - i] {
+                continue;
+            }
+            let row = self.line_at(*axis, axis.major(point));
+            let spacing_axis = axes[1 - i];
+            if let Some((val, gap1, gap2)) =
+                equal_spacing(row, point, *axis, spacing_axis, tolerance)
+            {
+                spacing_axis.set_major(&mut point, val);
+                gaps.push(gap1);
+                gaps.push(gap2);
+            }
+        }
+
+        let mut lines = Vec::new();
+        for (i, axis) in axes.iter().enumerate() {
+            if !aligned[i] {
+                continue;
+            }
+            let others = self.line_at(*axis, axis.major(point));
+            let (first, last) = (others[0], others[others.len() - 1]);
+            let start = if axis.minor(first) < axis.minor(point) {
+                first
+            } else {
+                point
+            };
+            let end = if axis.minor(last) > axis.minor(point) {
+                last
+            } else {
+                point
+            };
+            lines.push(Line::new(start, end));
+        }
+        lines.extend(gaps);
+
+        Hints { point, lines }
+    }
+}
+
+/// If `point` is nearly as far from its nearest neighbor in `row` as that
+/// neighbor is from the next, returns the coordinate on `spacing` that makes
+/// the gaps equal, and the two gaps.
+fn equal_spacing(
+    row: &[Point],
+    point: Point,
+    row_axis: Axis,
+    spacing: Axis,
+    tolerance: f64,
+) -> Option<(f64, Line, Line)> {
+    let val = spacing.major(point);
+    let idx = partition_point(row, |pt| row_axis.minor(*pt) < val);
+    // neighbors before `point`, nearest first; then those after.
+    let before = if idx >= 2 {
+        Some((row[idx - 1], row[idx - 2]))
+    } else {
+        None
+    };
+    let after = row
+        .get(idx)
+        .filter(|pt| row_axis.minor(**pt) > val)
+        .and_then(|near| row.get(idx + 1).map(|far| (*near, *far)));
+
+    for (near, far) in before.into_iter().chain(after) {
+        let gap = spacing.major(near) - spacing.major(far);
+        if gap == 0.0 {
+            continue;
+        }
+        let target = spacing.major(near) + gap;
+        if (target - val).abs() <= tolerance {
+            let mut snapped = point;
+            spacing.set_major(&mut snapped, target);
+            return Some((target, Line::new(far, near), Line::new(near, snapped)));
+        }
+    }
+    None
+}
+
+/// The index of the first item in `items` for which `pred` is false,
+/// assuming it is true for all items before that.
+fn partition_point(items: &[Point], pred: impl Fn(&Point) -> bool) -> usize {
+    items
+        .binary_search_by(|pt| {
+            if pred(pt) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_or_else(|idx| idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_on_both_axes() {
+        let index = AlignmentIndex::new(vec![
+            Point::new(100.0, 0.0),
+            Point::new(100.0, 300.0),
+            Point::new(400.0, 200.0),
+        ]);
+        let hints = index.query(Point::new(102.0, 197.0), 4.0);
+        assert_eq!(hints.point, Point::new(100.0, 200.0));
+        assert_eq!(
+            hints.lines,
+            vec![
+                Line::new((100.0, 0.0), (100.0, 300.0)),
+                Line::new((100.0, 200.0), (400.0, 200.0)),
+            ]
+        );
+
+        let hints = index.query(Point::new(250.0, 150.0), 4.0);
+        assert_eq!(hints.point, Point::new(250.0, 150.0));
+        assert!(hints.lines.is_empty());
+    }
+
+    #[test]
+    fn equal_spacing_in_a_row() {
+        let index = AlignmentIndex::new(vec![Point::new(0.0, 50.0), Point::new(80.0, 50.0)]);
+        let hints = index.query(Point::new(158.0, 51.0), 4.0);
+        assert_eq!(hints.point, Point::new(160.0, 50.0));
+        assert_eq!(
+            hints.lines,
+            vec![
+                Line::new((0.0, 50.0), (160.0, 50.0)),
+                Line::new((0.0, 50.0), (80.0, 50.0)),
+                Line::new((80.0, 50.0), (160.0, 50.0)),
+            ]
+        );
+
+        // and on the other side
+        let hints = index.query(Point::new(-81.0, 50.0), 4.0);
+        assert_eq!(hints.point, Point::new(-80.0, 50.0));
+    }
+}
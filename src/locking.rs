@@ -0,0 +1,157 @@
+//! Storing which contours and components are locked or hidden.
+//!
+//! Norad has no place for these flags on contours or components, so we
+//! keep them in the glyph lib, as lists of the indices of the affected
+//! contours and components.
+
+use crate::component::Component;
+use crate::path::Path;
+
+/// The key in the glyph lib under which the flags are stored.
+pub(crate) const LIB_KEY: &str = "org.linebender.runebender.locks";
+
+const LOCKED_CONTOURS: &str = "lockedContours";
+const HIDDEN_CONTOURS: &str = "hiddenContours";
+const LOCKED_COMPONENTS: &str = "lockedComponents";
+const HIDDEN_COMPONENTS: &str = "hiddenComponents";
+
+/// Returns `true` if any of `paths` or `components` is locked or hidden.
+pub(crate) fn has_flags(paths: &[Path], components: &[Component]) -> bool {
+    paths
+        .iter()
+        .any(|path| path.is_locked() || path.is_hidden())
+        || components.iter().any(|comp| comp.locked || comp.hidden)
+}
+
+/// Sets the flags of `paths` and `components` from those stored in a glyph lib.
+pub(crate) fn flags_from_lib(
+    lib: &plist::Dictionary,
+    paths: &mut [Path],
+    components: &mut [Component],
+) {
+    let dict = match lib.get(LIB_KEY).and_then(plist::Value::as_dictionary) {
+        Some(dict) => dict,
+        None => return,
+    };
+    for idx in indices(dict, LOCKED_CONTOURS) {
+        if let Some(path) = paths.get_mut(idx) {
+            path.set_locked(true);
+        }
+    }
+    for idx in indices(dict, HIDDEN_CONTOURS) {
+        if let Some(path) = paths.get_mut(idx) {
+            path.set_hidden(true);
+        }
+    }
+    for idx in indices(dict, LOCKED_COMPONENTS) {
+        if let Some(comp) = components.get_mut(idx) {
+            comp.locked = true;
+        }
+    }
+    for idx in indices(dict, HIDDEN_COMPONENTS) {
+        if let Some(comp) = components.get_mut(idx) {
+            comp.hidden = true;
+        }
+    }
+}
+
+/// Stores the flags of `paths` and `components` in a glyph lib, replacing
+/// any that were there before.
+pub(crate) fn flags_to_lib(lib: &mut plist::Dictionary, paths: &[Path], components: &[Component]) {
+    if !has_flags(paths, components) {
+        lib.remove(LIB_KEY);
+        return;
+    }
+    let mut dict = plist::Dictionary::new();
+    let mut insert = |key: &str, flags: Vec<bool>| {
+        let entries: Vec<_> = flags
+            .into_iter()
+            .enumerate()
+            .filter(|(_, flag)| *flag)
+            .map(|(idx, _)| plist::Value::from(idx as i64))
+            .collect();
+        if !entries.is_empty() {
+            dict.insert(key.into(), plist::Value::Array(entries));
+        }
+    };
+    insert(LOCKED_CONTOURS, paths.iter().map(Path::is_locked).collect());
+    insert(HIDDEN_CONTOURS, paths.iter().map(Path::is_hidden).collect());
+    insert(
+        LOCKED_COMPONENTS,
+        components.iter().map(|comp| comp.locked).collect(),
+    );
+    insert(
+        HIDDEN_COMPONENTS,
+        components.iter().map(|comp| comp.hidden).collect(),
+    );
+    lib.insert(LIB_KEY.into(), plist::Value::Dictionary(dict));
+}
+
+fn indices(dict: &plist::Dictionary, key: &str) -> Vec<usize> {
+    dict.get(key)
+        .and_then(plist::Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(plist::Value::as_signed_integer)
+                .filter(|idx| *idx >= 0)
+                .map(|idx| idx as usize)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::design_space::DPoint;
+    use druid::kurbo::Affine;
+
+    fn component(base: &str) -> Component {
+        Component {
+            base: base.into(),
+            transform: Affine::default(),
+            id: crate::path::EntityId::new_for_component(),
+            locked: false,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn round_trip_lib() {
+        let mut paths: Vec<_> = (0..3)
+            .map(|i| Path::new(DPoint::new(i as f64, 0.)))
+            .collect();
+        paths[1].set_locked(true);
+        paths[2].set_hidden(true);
+        let mut components = vec![component("a"), component("b")];
+        components[0].hidden = true;
+        components[0].locked = true;
+
+        let mut lib = plist::Dictionary::new();
+        flags_to_lib(&mut lib, &paths, &components);
+
+        let mut new_paths: Vec<_> = (0..3)
+            .map(|i| Path::new(DPoint::new(i as f64, 0.)))
+            .collect();
+        let mut new_components = vec![component("a"), component("b")];
+        flags_from_lib(&lib, &mut new_paths, &mut new_components);
+        let path_flags: Vec<_> = new_paths
+            .iter()
+            .map(|path| (path.is_locked(), path.is_hidden()))
+            .collect();
+        assert_eq!(
+            path_flags,
+            vec![(false, false), (true, false), (false, true)]
+        );
+        assert!(new_components[0].locked && new_components[0].hidden);
+        assert!(!new_components[1].locked && !new_components[1].hidden);
+
+        for path in &mut new_paths {
+            path.set_locked(false);
+            path.set_hidden(false);
+        }
+        flags_to_lib(&mut lib, &new_paths, &[]);
+        assert!(lib.get(LIB_KEY).is_none());
+    }
+}
@@ -1,6 +1,7 @@
 //! A font editor.
 
 #![allow(clippy::rc_buffer)]
+#![cfg_attr(all(test, feature = "nightly"), feature(test))]
 
 #[macro_use]
 extern crate serde_derive;
@@ -8,26 +9,40 @@ extern crate serde_derive;
 #[macro_use]
 extern crate lopdf;
 
+mod align;
 mod app_delegate;
 mod bez_cache;
+mod boolean;
 mod clipboard;
 mod component;
 mod consts;
+mod corner;
+mod curvature;
 mod data;
 mod design_space;
 mod draw;
 mod edit_session;
+mod fit;
 mod glyph_names;
 mod guides;
+mod hints;
+mod locking;
+mod measurement;
 mod menus;
 mod mouse;
+mod offset;
 mod path;
 mod plist;
 mod quadrant;
 mod selection;
+mod session_diff;
+mod shapes;
+mod snap;
+mod spline;
 mod theme;
 mod tools;
 mod undo;
+mod undo_file;
 mod util;
 pub mod widgets;
 
@@ -0,0 +1,297 @@
+//! Measurements that stay on the canvas.
+//!
+//! A pinned measurement is a line labeled with the distances between the
+//! outlines it crosses, like the one drawn by the measure tool. A stem probe
+//! is a horizontal or vertical line across the whole glyph, labeled with the
+//! thickness of each stem it crosses. Both are recomputed from the current
+//! outlines whenever they are drawn, and are saved in the glyph's lib.
+
+use druid::kurbo::{BezPath, Line, Point, Rect, Shape};
+use druid::Data;
+
+use crate::design_space::DPoint;
+use crate::path::Path;
+
+/// The key in the glyph lib under which measurements are stored.
+pub(crate) const LIB_KEY: &str = "org.linebender.runebender.measurements";
+
+/// How far, in design units, a stem probe extends past the outlines.
+const PROBE_OVERHANG: f64 = 50.0;
+
+/// Don't report segments smaller than this.
+const FUZZY_TOLERANCE: f64 = 0.1;
+
+/// A measurement that is drawn until it is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub enum Measurement {
+    /// A line between two points.
+    Line { start: DPoint, end: DPoint },
+    /// A horizontal (if `horizontal` is set) or vertical line across the
+    /// glyph, at `pos` on the other axis.
+    StemProbe { horizontal: bool, pos: f64 },
+}
+
+impl Measurement {
+    /// The line to measure along, in design space, given the current paths.
+    ///
+    /// Returns `None` for a stem probe when there are no paths to cross.
+    pub(crate) fn line(&self, paths: &[Path]) -> Option<Line> {
+        match *self {
+            Measurement::Line { start, end } => Some(Line::new(start.to_raw(), end.to_raw())),
+            Measurement::StemProbe { horizontal, pos } => {
+                let bounds = paths
+                    .iter()
+                    .map(|path| path.bezier().bounding_box())
+                    .fold(None, |acc: Option<Rect>, rect| {
+                        Some(acc.map(|acc| acc.union(rect)).unwrap_or(rect))
+                    })?
+                    .inflate(PROBE_OVERHANG, PROBE_OVERHANG);
+                if horizontal {
+                    Some(Line::new((bounds.x0, pos), (bounds.x1, pos)))
+                } else {
+                    Some(Line::new((pos, bounds.y0), (pos, bounds.y1)))
+                }
+            }
+        }
+    }
+
+    /// The parts of `line` to label, as ranges of `t` along it.
+    ///
+    /// For a line this is every span between crossings of the outlines; for
+    /// a stem probe it is only those spans that are inside the outlines.
+    pub(crate) fn spans(&self, paths: &[Path], line: Line) -> Vec<(f64, f64)> {
+        let ts = intersections(paths, line);
+        let spans = ts.windows(2).map(|pair| (pair[0], pair[1]));
+        match self {
+            Measurement::Line { .. } => spans.collect(),
+            Measurement::StemProbe { .. } => {
+                let mut bez = BezPath::new();
+                for path in paths.iter().filter(|path| path.is_closed()) {
+                    path.append_to_bezier(&mut bez);
+                }
+                spans
+                    .filter(|(t0, t1)| bez.winding(line.p0.lerp(line.p1, 0.5 * (t0 + t1))) != 0)
+                    .collect()
+            }
+        }
+    }
+
+    fn to_plist(self) -> plist::Value {
+        let mut dict = plist::Dictionary::new();
+        match self {
+            Measurement::Line { start, end } => {
+                dict.insert("type".into(), "line".into());
+                dict.insert("x0".into(), start.x.into());
+                dict.insert("y0".into(), start.y.into());
+                dict.insert("x1".into(), end.x.into());
+                dict.insert("y1".into(), end.y.into());
+            }
+            Measurement::StemProbe { horizontal, pos } => {
+                let axis = if horizontal { "horizontal" } else { "vertical" };
+                dict.insert("type".into(), "stems".into());
+                dict.insert("axis".into(), axis.into());
+                dict.insert("position".into(), pos.into());
+            }
+        }
+        plist::Value::Dictionary(dict)
+    }
+
+    fn from_plist(value: &plist::Value) -> Option<Measurement> {
+        let dict = value.as_dictionary()?;
+        let get = |key: &str| dict.get(key).and_then(finite_number);
+        match dict.get("type")?.as_string()? {
+            "line" => Some(Measurement::Line {
+                start: DPoint::new(get("x0")?.round(), get("y0")?.round()),
+                end: DPoint::new(get("x1")?.round(), get("y1")?.round()),
+            }),
+            "stems" => {
+                let horizontal = match dict.get("axis")?.as_string()? {
+                    "horizontal" => true,
+                    "vertical" => false,
+                    _ => return None,
+                };
+                let pos = get("position")?;
+                Some(Measurement::StemProbe { horizontal, pos })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A finite number in a lib entry, which may be written as a real or an integer.
+fn finite_number(value: &plist::Value) -> Option<f64> {
+    value
+        .as_real()
+        .or_else(|| value.as_signed_integer().map(|int| int as f64))
+        .filter(|num| num.is_finite())
+}
+
+/// The values of `t` at which `line` crosses any of `paths`, sorted, with
+/// crossings that are very close together merged.
+///
+/// The ends of the line, `0.0` and `1.0`, are always included.
+#[allow(clippy::float_cmp)]
+pub(crate) fn intersections(paths: &[Path], line: Line) -> Vec<f64> {
+    // We scale the intersections to fixed point to make them easier to sort.
+    const T_SCALE: f64 = (1u64 << 63) as f64;
+    let mut intersections = vec![0, T_SCALE as u64];
+    for path in paths {
+        for seg in path.iter_segments() {
+            let kurbo_seg = seg.to_kurbo();
+            for intersection in kurbo_seg.intersect_line(line) {
+                let t_fixed = (intersection.line_t.max(0.0).min(1.0) * T_SCALE) as u64;
+                intersections.push(t_fixed);
+            }
+        }
+    }
+    intersections.sort_unstable();
+
+    // Fuzzy deduplication
+    let thresh = FUZZY_TOLERANCE / (line.p1 - line.p0).hypot();
+    let mut result = Vec::with_capacity(intersections.len());
+    let mut t_cluster_start = -1.0;
+    let mut t_last = -1.0;
+    for t_fixed in intersections {
+        let t = t_fixed as f64 / T_SCALE;
+        if t - t_last > thresh {
+            t_cluster_start = t;
+            result.push(t);
+        } else {
+            let cluster_t = if t_cluster_start == 0.0 {
+                0.0
+            } else if t == 1.0 {
+                1.0
+            } else {
+                0.5 * (t_cluster_start + t)
+            };
+            *result.last_mut().unwrap() = cluster_t;
+        }
+        t_last = t;
+    }
+    result
+}
+
+/// The midpoint of the span from `t0` to `t1` along `line`, and its length.
+pub(crate) fn span_label(line: Line, t0: f64, t1: f64) -> (Point, f64) {
+    let center = line.p0.lerp(line.p1, 0.5 * (t0 + t1));
+    (center, (line.p1 - line.p0).hypot() * (t1 - t0))
+}
+
+/// Reads the measurements stored in a glyph lib.
+pub(crate) fn measurements_from_lib(lib: &plist::Dictionary) -> Vec<Measurement> {
+    lib.get(LIB_KEY)
+        .and_then(plist::Value::as_array)
+        .map(|entries| entries.iter().filter_map(Measurement::from_plist).collect())
+        .unwrap_or_default()
+}
+
+/// Stores measurements in a glyph lib, replacing any that were there before.
+pub(crate) fn measurements_to_lib(lib: &mut plist::Dictionary, measurements: &[Measurement]) {
+    if measurements.is_empty() {
+        lib.remove(LIB_KEY);
+    } else {
+        let entries = measurements.iter().map(|m| m.to_plist()).collect();
+        lib.insert(LIB_KEY.into(), plist::Value::Array(entries));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An 'H'-like pair of stems, 20 and 30 units thick, with a crossbar.
+    fn stems() -> Vec<Path> {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((20., 0.));
+        bez.line_to((20., 40.));
+        bez.line_to((70., 40.));
+        bez.line_to((70., 0.));
+        bez.line_to((100., 0.));
+        bez.line_to((100., 100.));
+        bez.line_to((70., 100.));
+        bez.line_to((70., 60.));
+        bez.line_to((20., 60.));
+        bez.line_to((20., 100.));
+        bez.line_to((0., 100.));
+        bez.close_path();
+        vec![Path::from_bezpath(bez).unwrap()]
+    }
+
+    fn span_lengths(measurement: Measurement, paths: &[Path]) -> Vec<f64> {
+        let line = measurement.line(paths).unwrap();
+        measurement
+            .spans(paths, line)
+            .into_iter()
+            .map(|(t0, t1)| span_label(line, t0, t1).1.round())
+            .collect()
+    }
+
+    #[test]
+    fn stem_probes() {
+        let paths = stems();
+        let probe = Measurement::StemProbe {
+            horizontal: true,
+            pos: 80.,
+        };
+        assert_eq!(span_lengths(probe, &paths), vec![20., 30.]);
+        let probe = Measurement::StemProbe {
+            horizontal: false,
+            pos: 50.,
+        };
+        assert_eq!(span_lengths(probe, &paths), vec![20.]);
+
+        // a line also measures the gaps
+        let line = Measurement::Line {
+            start: DPoint::new(-10., 80.),
+            end: DPoint::new(110., 80.),
+        };
+        assert_eq!(span_lengths(line, &paths), vec![10., 20., 50., 30., 10.]);
+    }
+
+    #[test]
+    fn round_trip_lib() {
+        let measurements = vec![
+            Measurement::Line {
+                start: DPoint::new(0., 10.),
+                end: DPoint::new(100., 10.),
+            },
+            Measurement::StemProbe {
+                horizontal: false,
+                pos: 250.,
+            },
+        ];
+        let mut lib = plist::Dictionary::new();
+        measurements_to_lib(&mut lib, &measurements);
+        assert_eq!(measurements_from_lib(&lib), measurements);
+        measurements_to_lib(&mut lib, &[]);
+        assert!(lib.get(LIB_KEY).is_none());
+    }
+
+    #[test]
+    fn lib_numbers() {
+        let entry = |x0: plist::Value| {
+            let mut dict = plist::Dictionary::new();
+            dict.insert("type".into(), "line".into());
+            dict.insert("x0".into(), x0);
+            dict.insert("y0".into(), plist::Value::from(10_i64));
+            dict.insert("x1".into(), plist::Value::from(100.4));
+            dict.insert("y1".into(), plist::Value::from(10_i64));
+            plist::Value::Dictionary(dict)
+        };
+        let entries = vec![
+            entry(plist::Value::from(0_i64)),
+            entry(plist::Value::from(f64::NAN)),
+            entry(plist::Value::from(f64::INFINITY)),
+        ];
+        let mut lib = plist::Dictionary::new();
+        lib.insert(LIB_KEY.into(), plist::Value::Array(entries));
+        assert_eq!(
+            measurements_from_lib(&lib),
+            vec![Measurement::Line {
+                start: DPoint::new(0., 10.),
+                end: DPoint::new(100., 10.),
+            }]
+        );
+    }
+}
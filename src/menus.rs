@@ -6,17 +6,36 @@ use druid::{
     Data, FileDialogOptions, FileSpec, KbKey, LocalizedString, MenuDesc, MenuItem, Point, SysMods,
 };
 
+use crate::boolean::BooleanOp;
 use crate::consts;
 use crate::data::{AppState, EditorState};
+use crate::selection::SelectSimilar;
 
 pub const UFO_FILE_TYPE: FileSpec = FileSpec::new("Font Object", &["ufo"]);
 
 /// Context menu's inner menu must have type T == the root app state.
 pub fn make_context_menu(data: &EditorState, pos: Point) -> MenuDesc<AppState> {
-    let mut menu = MenuDesc::empty().append(MenuItem::new(
-        LocalizedString::new("menu-item-add-guide").with_placeholder("Add Guide"),
-        consts::cmd::ADD_GUIDE.with(pos),
-    ));
+    let mut menu = MenuDesc::empty()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-add-guide").with_placeholder("Add Guide"),
+            consts::cmd::ADD_GUIDE.with(pos),
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-add-horizontal-stem-probe")
+                .with_placeholder("Add Horizontal Stem Probe"),
+            consts::cmd::ADD_STEM_PROBE.with(consts::cmd::StemProbeCmdArgs {
+                horizontal: true,
+                pos,
+            }),
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-add-vertical-stem-probe")
+                .with_placeholder("Add Vertical Stem Probe"),
+            consts::cmd::ADD_STEM_PROBE.with(consts::cmd::StemProbeCmdArgs {
+                horizontal: false,
+                pos,
+            }),
+        ));
 
     // only show 'toggle guide' if a guide is selected
     if data.session.selection.len() == 1 && data.session.selection.iter().all(|s| s.is_guide()) {
@@ -42,7 +61,7 @@ pub(crate) fn make_menu(data: &AppState) -> MenuDesc<AppState> {
 
     menu.append(file_menu(data))
         .append(edit_menu())
-        .append(view_menu())
+        .append(view_menu(data))
         .append(glyph_menu(data))
         .append(paths_menu())
         .append(tools_menu())
@@ -81,7 +100,34 @@ fn file_menu(data: &AppState) -> MenuDesc<AppState> {
             .hotkey(SysMods::Cmd, "s"),
         );
     }
+    let persist_undo = data.workspace.persist_undo_history;
+    let remove_overlaps = data.workspace.remove_overlaps_on_export;
     menu.append_separator()
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-export").with_placeholder("Export"),
+                consts::cmd::EXPORT,
+            )
+            .disabled_if(|| !has_path),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-remove-overlaps-on-export")
+                    .with_placeholder("Remove Overlaps on Export"),
+                consts::cmd::TOGGLE_REMOVE_OVERLAPS_ON_EXPORT,
+            )
+            .selected_if(|| remove_overlaps),
+        )
+        .append_separator()
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-save-undo-history")
+                    .with_placeholder("Save Undo History"),
+                consts::cmd::TOGGLE_PERSIST_UNDO_HISTORY,
+            )
+            .selected_if(|| persist_undo),
+        )
+        .append_separator()
         .append(platform_menus::mac::file::page_setup().disabled())
         .append(platform_menus::mac::file::print().disabled())
 }
@@ -113,9 +159,66 @@ fn edit_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::AltCmd, "A"),
         )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-invert-selection")
+                    .with_placeholder("Invert Selection"),
+                consts::cmd::INVERT_SELECTION,
+            )
+            .hotkey(SysMods::CmdShift, "I"),
+        )
+        .append_separator()
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-select-contour").with_placeholder("Select Contour"),
+                consts::cmd::SELECT_CONTOURS,
+            )
+            .hotkey(SysMods::Cmd, "l"),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-grow-selection").with_placeholder("Grow Selection"),
+                consts::cmd::GROW_SELECTION,
+            )
+            .hotkey(SysMods::Cmd, "]"),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-shrink-selection")
+                    .with_placeholder("Shrink Selection"),
+                consts::cmd::SHRINK_SELECTION,
+            )
+            .hotkey(SysMods::Cmd, "["),
+        )
+        .append_separator()
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-select-off-curve")
+                    .with_placeholder("Select Off-Curve Points"),
+                consts::cmd::SELECT_SIMILAR.with(SelectSimilar::OffCurve),
+            )
+            .hotkey(SysMods::AltCmd, "O"),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-select-smooth")
+                    .with_placeholder("Select Smooth Points"),
+                consts::cmd::SELECT_SIMILAR.with(SelectSimilar::Smooth),
+            )
+            .hotkey(SysMods::AltCmd, "S"),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-select-same-y")
+                    .with_placeholder("Select Points at Same Height"),
+                consts::cmd::SELECT_SIMILAR.with(SelectSimilar::SameY),
+            )
+            .hotkey(SysMods::AltCmd, "Y"),
+        )
 }
 
-fn view_menu<T: Data>() -> MenuDesc<T> {
+fn view_menu(data: &AppState) -> MenuDesc<AppState> {
+    let show_comb = data.workspace.comb_settings.visible;
     MenuDesc::new(LocalizedString::new("menu-view-menu").with_placeholder("View"))
         .append(
             MenuItem::new(
@@ -138,6 +241,30 @@ fn view_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::Cmd, "0"),
         )
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-snapping").with_placeholder("Snapping..."),
+            consts::cmd::SHOW_SNAP_PANEL,
+        ))
+        .append_separator()
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-show-curvature-comb")
+                    .with_placeholder("Show Curvature Comb"),
+                consts::cmd::TOGGLE_CURVATURE_COMB,
+            )
+            .selected_if(|| show_comb),
+        )
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-curvature-comb").with_placeholder("Curvature Comb..."),
+            consts::cmd::SHOW_COMB_PANEL,
+        ))
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-clear-measurements")
+                .with_placeholder("Clear Measurements"),
+            consts::cmd::CLEAR_MEASUREMENTS,
+        ))
 }
 
 fn glyph_menu(data: &AppState) -> MenuDesc<AppState> {
@@ -157,6 +284,14 @@ fn glyph_menu(data: &AppState) -> MenuDesc<AppState> {
             .hotkey(SysMods::Cmd, KbKey::Backspace)
             .disabled_if(|| data.workspace.selected.is_none()),
         )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-remove-overlap-in-glyph")
+                    .with_placeholder("Remove Overlap"),
+                consts::cmd::REMOVE_OVERLAP_IN_SELECTED_GLYPH,
+            )
+            .disabled_if(|| data.workspace.selected.is_none()),
+        )
         .append(
             MenuItem::new(
                 LocalizedString::new("menu-item-add-component").with_placeholder("Add Component"),
@@ -174,6 +309,19 @@ fn paths_menu<T: Data>() -> MenuDesc<T> {
             consts::cmd::REVERSE_CONTOURS,
             // TODO: hotkey on mac should be ctrl-alt-cmd R, but what about non-mac?
         ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-correct-directions")
+                .with_placeholder("Correct Path Directions"),
+            consts::cmd::CORRECT_DIRECTIONS,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-set-start-point").with_placeholder("Set Start Point"),
+            consts::cmd::SET_START_POINT,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-break-contour").with_placeholder("Break Contour"),
+            consts::cmd::BREAK_CONTOUR,
+        ))
         .append(
             MenuItem::new(
                 LocalizedString::new("menu-item-align-selection")
@@ -182,6 +330,90 @@ fn paths_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::CmdShift, "A"),
         )
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-align").with_placeholder("Align..."),
+            consts::cmd::SHOW_ALIGN_PANEL,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-flip-horizontal").with_placeholder("Flip Horizontally"),
+            consts::cmd::FLIP_HORIZONTAL,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-flip-vertical").with_placeholder("Flip Vertically"),
+            consts::cmd::FLIP_VERTICAL,
+        ))
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-union").with_placeholder("Union"),
+            consts::cmd::BOOLEAN_OP.with(BooleanOp::Union),
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-subtract").with_placeholder("Subtract"),
+            consts::cmd::BOOLEAN_OP.with(BooleanOp::Subtract),
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-intersect").with_placeholder("Intersect"),
+            consts::cmd::BOOLEAN_OP.with(BooleanOp::Intersect),
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-exclude").with_placeholder("Exclude"),
+            consts::cmd::BOOLEAN_OP.with(BooleanOp::Exclude),
+        ))
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-remove-overlap").with_placeholder("Remove Overlap"),
+                consts::cmd::REMOVE_OVERLAP,
+            )
+            .hotkey(SysMods::CmdShift, "O"),
+        )
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-add-extremes").with_placeholder("Add Extremes"),
+            consts::cmd::ADD_EXTREMES,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-harmonize").with_placeholder("Harmonize"),
+            consts::cmd::HARMONIZE,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-remove-redundant-points")
+                .with_placeholder("Remove Redundant Points"),
+            consts::cmd::REMOVE_REDUNDANT_POINTS,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-simplify").with_placeholder("Simplify..."),
+            consts::cmd::SHOW_SIMPLIFY_PANEL,
+        ))
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-offset-path").with_placeholder("Offset Path..."),
+            consts::cmd::SHOW_OFFSET_PATH_PANEL,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-expand-stroke").with_placeholder("Expand Stroke..."),
+            consts::cmd::SHOW_EXPAND_STROKE_PANEL,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-corners").with_placeholder("Corners..."),
+            consts::cmd::SHOW_CORNER_PANEL,
+        ))
+        .append_separator()
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-lock-selection").with_placeholder("Lock Selection"),
+            consts::cmd::LOCK_SELECTION,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-unlock-all").with_placeholder("Unlock All"),
+            consts::cmd::UNLOCK_ALL,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-hide-selection").with_placeholder("Hide Selection"),
+            consts::cmd::HIDE_SELECTION,
+        ))
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-show-all").with_placeholder("Show All"),
+            consts::cmd::SHOW_ALL,
+        ))
 }
 
 fn tools_menu<T: Data>() -> MenuDesc<T> {
@@ -200,6 +432,20 @@ fn tools_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::None, "p"),
         )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-pencil-tool").with_placeholder("Pencil"),
+                consts::cmd::SET_TOOL.with("Pencil"),
+            )
+            .hotkey(SysMods::Shift, "p"),
+        )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-spline-tool").with_placeholder("Spline"),
+                consts::cmd::SET_TOOL.with("Spline"),
+            )
+            .hotkey(SysMods::None, "b"),
+        )
         .append(
             MenuItem::new(
                 LocalizedString::new("menu-item-preview-tool").with_placeholder("Preview"),
@@ -221,6 +467,17 @@ fn tools_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::Shift, "u"),
         )
+        .append(
+            MenuItem::new(
+                LocalizedString::new("menu-item-polygon-tool").with_placeholder("Polygon"),
+                consts::cmd::SET_TOOL.with("Polygon"),
+            )
+            .hotkey(SysMods::None, "y"),
+        )
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-shape-options").with_placeholder("Shape Options..."),
+            consts::cmd::SHOW_SHAPE_OPTIONS_PANEL,
+        ))
         .append(
             MenuItem::new(
                 LocalizedString::new("menu-item-knife-tool").with_placeholder("Knife"),
@@ -228,6 +485,10 @@ fn tools_menu<T: Data>() -> MenuDesc<T> {
             )
             .hotkey(SysMods::None, "e"),
         )
+        .append(MenuItem::new(
+            LocalizedString::new("menu-item-knife-options").with_placeholder("Knife Options..."),
+            consts::cmd::SHOW_KNIFE_PANEL,
+        ))
         .append(
             MenuItem::new(
                 LocalizedString::new("menu-item-measure-tool").with_placeholder("Measure"),
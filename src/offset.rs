@@ -0,0 +1,713 @@
+//! Offsetting paths, and expanding strokes into outlines.
+//!
+//! Each segment is offset on its own: lines exactly, and cubics by fitting a
+//! cubic to the true offset curve, subdividing until the fit is within
+//! `TOLERANCE`. Consecutive segments are then connected with the requested
+//! join.
+//!
+//! At inner corners the offset segments overlap; we connect them by way of
+//! the original corner point, which leaves a small loop that winds the wrong
+//! way. The same happens where a curve is offset by more than its radius.
+//! When offsetting, these loops are removed by keeping only the regions with
+//! a positive winding number; when expanding a stroke they are inside the
+//! stroke, and are kept.
+
+use druid::kurbo::{
+    BezPath, CubicBez, Line, ParamCurve, ParamCurveDeriv, ParamCurveNearest, PathEl, PathSeg,
+    Point, Shape, Vec2,
+};
+use druid::{Data, Lens};
+
+use crate::boolean::{self, BooleanOp};
+use crate::path::Path;
+
+/// The maximum distance, in design units, between an offset segment and the
+/// true offset curve. Points closer together than this are merged.
+const TOLERANCE: f64 = 0.5;
+/// A bound on how many times a single cubic is subdivided.
+const MAX_DEPTH: usize = 8;
+/// The longest a miter may be, as a multiple of the offset distance, before
+/// it is replaced by a bevel.
+const MITER_LIMIT: f64 = 4.0;
+/// Vectors shorter than this have no direction.
+const EPSILON: f64 = 1e-9;
+
+/// The shape used to connect offset segments at a corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Join {
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// The shape used at the ends of an expanded stroke.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum Cap {
+    Butt,
+    Round,
+    Square,
+}
+
+/// The arguments to the 'offset path' command.
+#[derive(Debug, Clone, Copy, Data, Lens)]
+pub struct OffsetSettings {
+    /// The distance to move the outline; positive values move it away from
+    /// the filled area.
+    pub distance: f64,
+    pub join: Join,
+}
+
+/// The arguments to the 'expand stroke' command.
+#[derive(Debug, Clone, Copy, Data, Lens)]
+pub struct StrokeSettings {
+    pub width: f64,
+    pub cap: Cap,
+    pub join: Join,
+}
+
+impl Default for OffsetSettings {
+    fn default() -> Self {
+        OffsetSettings {
+            distance: 10.0,
+            join: Join::Miter,
+        }
+    }
+}
+
+impl Default for StrokeSettings {
+    fn default() -> Self {
+        StrokeSettings {
+            width: 20.0,
+            cap: Cap::Butt,
+            join: Join::Miter,
+        }
+    }
+}
+
+/// Offset a closed path, returning the new outline.
+///
+/// Contours are assumed to have the usual direction, so that a positive
+/// distance makes outer contours larger and counters smaller. The result may
+/// contain more than one path, or none, if the outline disappears.
+pub(crate) fn offset_path(path: &Path, settings: OffsetSettings) -> Vec<Path> {
+    let segs = segments(path);
+    if segs.is_empty() || !path.is_closed() {
+        return Vec::new();
+    }
+
+    // only regions with positive winding survive the cleanup, so we work
+    // with a counter-clockwise contour. Reversing the contour also swaps its
+    // sides, so the distance is negated as well.
+    let clockwise = path.bezier().area() < 0.0;
+    let (segs, distance) = if clockwise {
+        (reversed(&segs), -settings.distance)
+    } else {
+        (segs, settings.distance)
+    };
+
+    let mut outline = Outline::new(distance, settings.join);
+    outline.add_side(&segs);
+    outline.add_join(*segs.last().unwrap(), segs[0]);
+    outline.close();
+
+    let mut result = clean_up(boolean::resolve_positive(&outline.into_paths()));
+    if clockwise {
+        result.iter_mut().for_each(Path::reverse_contour);
+    }
+    result
+}
+
+/// Expand an open path into a closed outline of a stroke of the given width.
+///
+/// Where the stroke is wider than the curves of the path are tight, there
+/// may be gaps on the inside of the curve.
+pub(crate) fn expand_stroke(path: &Path, settings: StrokeSettings) -> Vec<Path> {
+    let segs = segments(path);
+    let half_width = settings.width.abs() / 2.0;
+    if segs.is_empty() || path.is_closed() || half_width < TOLERANCE {
+        return Vec::new();
+    }
+
+    // the right side of the reversed path is the left side of the original,
+    // so we go out along one side and back along the other. Unlike with an
+    // offset, every loop in this outline is within the stroke, so it is
+    // cleaned up with the nonzero rule.
+    let back = reversed(&segs);
+    let mut outline = Outline::new(half_width, settings.join);
+    outline.add_side(&segs);
+    outline.add_cap(*segs.last().unwrap(), settings.cap);
+    outline.add_side(&back);
+    outline.add_cap(*back.last().unwrap(), settings.cap);
+    outline.close();
+    clean_up(boolean::boolean_op(
+        &outline.into_paths(),
+        &[],
+        BooleanOp::Union,
+    ))
+}
+
+/// Remove the points left on straight lines where overlaps were removed.
+fn clean_up(paths: Vec<Path>) -> Vec<Path> {
+    paths.iter().filter_map(merge_lines).collect()
+}
+
+/// Merge consecutive lines in a closed path that have the same direction.
+fn merge_lines(path: &Path) -> Option<Path> {
+    let same_direction = |a: Line, b: Line| {
+        let (a, b) = ((a.p1 - a.p0).normalize(), (b.p1 - b.p0).normalize());
+        a.cross(b).abs() < EPSILON && a.dot(b) > 0.0
+    };
+
+    let mut segs: Vec<PathSeg> = Vec::new();
+    for seg in path.bezier().segments() {
+        match (segs.last_mut(), seg) {
+            (Some(PathSeg::Line(prev)), PathSeg::Line(line)) if same_direction(*prev, line) => {
+                prev.p1 = line.p1;
+            }
+            _ => segs.push(seg),
+        }
+    }
+    if let (Some(PathSeg::Line(first)), Some(PathSeg::Line(last))) = (segs.first(), segs.last()) {
+        if segs.len() > 2 && same_direction(*last, *first) {
+            segs[0] = PathSeg::Line(Line::new(last.p0, first.p1));
+            segs.pop();
+        }
+    }
+
+    let mut bez = BezPath::new();
+    bez.move_to(segs.first()?.start());
+    for seg in segs {
+        match seg {
+            PathSeg::Line(line) => bez.line_to(line.p1),
+            PathSeg::Cubic(cubic) => bez.curve_to(cubic.p1, cubic.p2, cubic.p3),
+            PathSeg::Quad(quad) => bez.quad_to(quad.p1, quad.p2),
+        }
+    }
+    bez.close_path();
+    Path::from_bezpath(bez).ok()
+}
+
+/// Builds the contours of an offset outline.
+struct Outline {
+    /// The offset, towards the right-hand side of the segments.
+    distance: f64,
+    join: Join,
+    contours: Vec<BezPath>,
+    current: Vec<PathEl>,
+}
+
+impl Outline {
+    fn new(distance: f64, join: Join) -> Self {
+        Outline {
+            distance,
+            join,
+            contours: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    fn into_paths(self) -> Vec<Path> {
+        self.contours
+            .into_iter()
+            .filter_map(|bez| Path::from_bezpath(bez).ok())
+            .collect()
+    }
+
+    /// Add the offset of each segment, joined at the corners.
+    fn add_side(&mut self, segs: &[PathSeg]) {
+        for (i, seg) in segs.iter().enumerate() {
+            if i > 0 {
+                self.add_join(segs[i - 1], *seg);
+            }
+            match *seg {
+                PathSeg::Line(line) => {
+                    let offset = right_normal(line.p1 - line.p0) * self.distance;
+                    self.push(PathSeg::Line(Line::new(line.p0 + offset, line.p1 + offset)));
+                }
+                PathSeg::Cubic(cubic) => {
+                    for piece in split_at_cusps(cubic, self.distance) {
+                        self.add_offset_cubic(piece, 0);
+                    }
+                }
+                PathSeg::Quad(_) => unreachable!("segments are converted to cubics"),
+            }
+        }
+    }
+
+    fn add_offset_cubic(&mut self, cubic: CubicBez, depth: usize) {
+        let approx = fit_offset(cubic, self.distance);
+        if depth < MAX_DEPTH && !is_close_to_offset(approx, cubic, self.distance) {
+            self.add_offset_cubic(cubic.subsegment(0.0..0.5), depth + 1);
+            self.add_offset_cubic(cubic.subsegment(0.5..1.0), depth + 1);
+        } else {
+            self.push(PathSeg::Cubic(approx));
+        }
+    }
+
+    /// Connect the offsets of two consecutive segments.
+    fn add_join(&mut self, prev: PathSeg, next: PathSeg) {
+        let corner = next.start();
+        let dir_in = end_tangent(prev);
+        let dir_out = start_tangent(next);
+        let normal_in = right_normal(dir_in);
+        let normal_out = right_normal(dir_out);
+        let from = corner + normal_in * self.distance;
+        let to = corner + normal_out * self.distance;
+        if (to - from).hypot() < TOLERANCE {
+            return;
+        }
+
+        // the offset side is on the outside of the turn; a complete reversal
+        // is treated as an outside corner.
+        let turn = dir_in.cross(dir_out);
+        let is_outer =
+            turn * self.distance > 0.0 || (turn.abs() < EPSILON && dir_in.dot(dir_out) < 0.0);
+        if !is_outer {
+            self.line_to(corner);
+            self.line_to(to);
+            return;
+        }
+
+        match self.join {
+            Join::Bevel => self.line_to(to),
+            Join::Miter => {
+                let sum = normal_in + normal_out;
+                if sum.hypot() > 2.0 / MITER_LIMIT {
+                    let miter = corner + sum * (2.0 * self.distance / sum.hypot2());
+                    self.line_to(miter);
+                }
+                self.line_to(to);
+            }
+            Join::Round => {
+                let angle = normal_in.cross(normal_out).atan2(normal_in.dot(normal_out));
+                let angle = if turn.abs() < EPSILON {
+                    std::f64::consts::PI.copysign(self.distance)
+                } else {
+                    angle
+                };
+                self.add_arc(corner, from - corner, angle);
+            }
+        }
+    }
+
+    /// Add a cap at the end of `seg`, connecting this side to the other.
+    fn add_cap(&mut self, seg: PathSeg, cap: Cap) {
+        let end = seg.end();
+        let dir = end_tangent(seg);
+        let offset = right_normal(dir) * self.distance;
+        match cap {
+            Cap::Butt => (),
+            Cap::Square => {
+                let extension = dir * self.distance;
+                self.line_to(end + offset + extension);
+                self.line_to(end - offset + extension);
+            }
+            Cap::Round => self.add_arc(end, offset, std::f64::consts::PI),
+        }
+        self.line_to(end - offset);
+    }
+
+    /// Add a circular arc around `center`, starting at `center + start` and
+    /// turning through `angle` radians (counter-clockwise if positive.)
+    fn add_arc(&mut self, center: Point, start: Vec2, angle: f64) {
+        let n_pieces = (angle.abs() / std::f64::consts::FRAC_PI_2).ceil().max(1.0);
+        let step = angle / n_pieces;
+        let handle_len = 4.0 / 3.0 * (step / 4.0).tan();
+        let mut radius = start;
+        for _ in 0..n_pieces as usize {
+            let next = rotate(radius, step);
+            let p0 = center + radius;
+            let p3 = center + next;
+            let p1 = p0 + perpendicular(radius) * handle_len;
+            let p2 = p3 - perpendicular(next) * handle_len;
+            self.push(PathSeg::Cubic(CubicBez::new(p0, p1, p2, p3)));
+            radius = next;
+        }
+    }
+
+    fn last_point(&self) -> Option<Point> {
+        self.current.last().map(|el| end_point(*el))
+    }
+
+    /// Add a line to `pt`, extending the previous line if it has the same
+    /// direction, so that we don't add unnecessary points.
+    fn line_to(&mut self, pt: Point) {
+        let last = match self.last_point() {
+            Some(last) if (pt - last).hypot() < TOLERANCE => return,
+            Some(last) => last,
+            None => {
+                self.current.push(PathEl::MoveTo(pt));
+                return;
+            }
+        };
+        if let [.., prev, PathEl::LineTo(_)] = self.current.as_slice() {
+            let prev_dir = (last - end_point(*prev)).normalize();
+            let dir = (pt - last).normalize();
+            if prev_dir.cross(dir).abs() < EPSILON && prev_dir.dot(dir) > 0.0 {
+                self.current.pop();
+            }
+        }
+        self.current.push(PathEl::LineTo(pt));
+    }
+
+    fn push(&mut self, seg: PathSeg) {
+        self.line_to(seg.start());
+        match seg {
+            PathSeg::Line(line) => self.line_to(line.p1),
+            PathSeg::Cubic(cubic) => self
+                .current
+                .push(PathEl::CurveTo(cubic.p1, cubic.p2, cubic.p3)),
+            PathSeg::Quad(_) => unreachable!("offsets are always cubics"),
+        }
+    }
+
+    fn close(&mut self) {
+        if !self.current.is_empty() {
+            self.current.push(PathEl::ClosePath);
+            let contour = std::mem::take(&mut self.current);
+            self.contours.push(contour.into_iter().collect());
+        }
+    }
+}
+
+fn end_point(el: PathEl) -> Point {
+    match el {
+        PathEl::MoveTo(pt) | PathEl::LineTo(pt) => pt,
+        PathEl::QuadTo(_, pt) | PathEl::CurveTo(_, _, pt) => pt,
+        PathEl::ClosePath => unreachable!("closed contours are not extended"),
+    }
+}
+
+/// The segments of `path`, as lines and cubics, skipping any that have no
+/// length.
+fn segments(path: &Path) -> Vec<PathSeg> {
+    path.bezier()
+        .segments()
+        .map(|seg| match seg {
+            PathSeg::Quad(quad) => PathSeg::Cubic(CubicBez::new(
+                quad.p0,
+                quad.p0.lerp(quad.p1, 2.0 / 3.0),
+                quad.p2.lerp(quad.p1, 2.0 / 3.0),
+                quad.p2,
+            )),
+            other => other,
+        })
+        .filter(|seg| start_direction(*seg).is_some())
+        .collect()
+}
+
+fn reversed(segs: &[PathSeg]) -> Vec<PathSeg> {
+    segs.iter()
+        .rev()
+        .map(|seg| match *seg {
+            PathSeg::Line(line) => PathSeg::Line(Line::new(line.p1, line.p0)),
+            PathSeg::Cubic(c) => PathSeg::Cubic(CubicBez::new(c.p3, c.p2, c.p1, c.p0)),
+            PathSeg::Quad(_) => unreachable!("segments are converted to cubics"),
+        })
+        .collect()
+}
+
+/// Split `cubic` wherever its offset has a cusp.
+///
+/// Each piece has an offset that runs in a single direction, which we can
+/// fit without introducing loops.
+fn split_at_cusps(cubic: CubicBez, distance: f64) -> Vec<CubicBez> {
+    const SAMPLES: usize = 32;
+    let mut splits = Vec::new();
+    let mut prev_t = 0.0;
+    let mut prev_speed = offset_speed(cubic, distance, 0.0);
+    for i in 1..=SAMPLES {
+        let t = i as f64 / SAMPLES as f64;
+        let speed = offset_speed(cubic, distance, t);
+        if speed.signum() != prev_speed.signum() {
+            // bisect to find the cusp
+            let (mut lo, mut hi) = (prev_t, t);
+            for _ in 0..20 {
+                let mid = (lo + hi) / 2.0;
+                if offset_speed(cubic, distance, mid).signum() == prev_speed.signum() {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            splits.push((lo + hi) / 2.0);
+        }
+        prev_t = t;
+        prev_speed = speed;
+    }
+
+    let mut start = 0.0;
+    let mut pieces = Vec::new();
+    for t in splits.into_iter().chain(Some(1.0)) {
+        pieces.push(cubic.subsegment(start..t));
+        start = t;
+    }
+    pieces
+}
+
+/// The approximate offset of `cubic`: a cubic with the correct end points
+/// and tangents, which also passes through the offset of the midpoint.
+fn fit_offset(cubic: CubicBez, distance: f64) -> CubicBez {
+    let tangent0 = start_tangent(PathSeg::Cubic(cubic));
+    let tangent1 = end_tangent(PathSeg::Cubic(cubic));
+    let p0 = cubic.p0 + right_normal(tangent0) * distance;
+    let p3 = cubic.p3 + right_normal(tangent1) * distance;
+    let mid = offset_point(cubic, distance, 0.5);
+    // where the offset is larger than the radius of curvature, the offset
+    // curve runs backwards.
+    let dir0 = tangent0 * offset_speed(cubic, distance, 0.0).signum();
+    let dir1 = tangent1 * offset_speed(cubic, distance, 1.0).signum();
+
+    // the midpoint of a cubic is (p0 + 3p1 + 3p2 + p3) / 8; with p1 and p2
+    // on the end tangents, solve for the handle lengths.
+    let u = dir0 * 3.0;
+    let v = dir1 * -3.0;
+    let target = mid.to_vec2() * 8.0 - (p0.to_vec2() + p3.to_vec2()) * 4.0;
+    let det = u.cross(v);
+    let chord = (p3 - p0).hypot();
+    let solved = if det.abs() > EPSILON {
+        Some((target.cross(v) / det, u.cross(target) / det))
+    } else {
+        None
+    };
+
+    let (len0, len1) = match solved {
+        Some((a, b)) if a >= 0.0 && b >= 0.0 && a.max(b) <= 2.0 * chord => (a, b),
+        // fall back to scaling the original handles with the chord
+        _ => {
+            let orig_chord = (cubic.p3 - cubic.p0).hypot();
+            let scale = if orig_chord > EPSILON {
+                chord / orig_chord
+            } else {
+                1.0
+            };
+            (
+                (cubic.p1 - cubic.p0).hypot() * scale,
+                (cubic.p3 - cubic.p2).hypot() * scale,
+            )
+        }
+    };
+    CubicBez::new(p0, p0 + dir0 * len0, p3 - dir1 * len1, p3)
+}
+
+/// Returns `true` if `approx` is within `TOLERANCE` of the offset of `cubic`.
+fn is_close_to_offset(approx: CubicBez, cubic: CubicBez, distance: f64) -> bool {
+    const SAMPLES: [f64; 6] = [0.1, 0.25, 0.4, 0.6, 0.75, 0.9];
+    SAMPLES.iter().all(|t| {
+        let target = offset_point(cubic, distance, *t);
+        approx.nearest(target, 1e-3).1 <= TOLERANCE * TOLERANCE
+    })
+}
+
+/// The speed of the offset curve at `t`, relative to the speed of `cubic`.
+///
+/// This is negative where the offset runs in the opposite direction.
+fn offset_speed(cubic: CubicBez, distance: f64, t: f64) -> f64 {
+    // the curvature is undefined where a handle is retracted, so look
+    // slightly further along.
+    let t = t.clamp(1e-3, 1.0 - 1e-3);
+    let deriv = cubic.deriv();
+    let d1 = deriv.eval(t).to_vec2();
+    let d2 = deriv.deriv().eval(t).to_vec2();
+    let speed = d1.hypot();
+    if speed < EPSILON {
+        return 1.0;
+    }
+    let curvature = d1.cross(d2) / (speed * speed * speed);
+    1.0 + distance * curvature
+}
+
+/// The point at `distance` to the right of `cubic` at `t`.
+fn offset_point(cubic: CubicBez, distance: f64, t: f64) -> Point {
+    let deriv = cubic.deriv().eval(t).to_vec2();
+    let dir = if deriv.hypot() > EPSILON {
+        deriv.normalize()
+    } else if t < 0.5 {
+        start_tangent(PathSeg::Cubic(cubic))
+    } else {
+        end_tangent(PathSeg::Cubic(cubic))
+    };
+    cubic.eval(t) + right_normal(dir) * distance
+}
+
+/// The direction at the start of a segment, if it has one.
+fn start_direction(seg: PathSeg) -> Option<Vec2> {
+    let candidates = match seg {
+        PathSeg::Line(line) => [line.p1, line.p1, line.p1],
+        PathSeg::Cubic(c) => [c.p1, c.p2, c.p3],
+        PathSeg::Quad(q) => [q.p1, q.p2, q.p2],
+    };
+    let start = seg.start();
+    candidates
+        .iter()
+        .map(|pt| *pt - start)
+        .find(|v| v.hypot() > EPSILON)
+        .map(Vec2::normalize)
+}
+
+/// The unit tangent at the start of a segment.
+fn start_tangent(seg: PathSeg) -> Vec2 {
+    start_direction(seg).unwrap_or(Vec2::new(1.0, 0.0))
+}
+
+/// The unit tangent at the end of a segment.
+fn end_tangent(seg: PathSeg) -> Vec2 {
+    let reversed = match seg {
+        PathSeg::Line(line) => PathSeg::Line(Line::new(line.p1, line.p0)),
+        PathSeg::Cubic(c) => PathSeg::Cubic(CubicBez::new(c.p3, c.p2, c.p1, c.p0)),
+        PathSeg::Quad(q) => PathSeg::Cubic(CubicBez::new(q.p2, q.p1, q.p1, q.p0)),
+    };
+    -start_tangent(reversed)
+}
+
+/// The unit vector pointing to the right of `dir`, in design space (where
+/// y points up.)
+fn right_normal(dir: Vec2) -> Vec2 {
+    let dir = if dir.hypot() > EPSILON {
+        dir.normalize()
+    } else {
+        Vec2::new(1.0, 0.0)
+    };
+    Vec2::new(dir.y, -dir.x)
+}
+
+/// `vec` rotated a quarter turn counter-clockwise.
+fn perpendicular(vec: Vec2) -> Vec2 {
+    Vec2::new(-vec.y, vec.x)
+}
+
+fn rotate(vec: Vec2, angle: f64) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    Vec2::new(vec.x * cos - vec.y * sin, vec.x * sin + vec.y * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A counter-clockwise rectangle.
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Path {
+        let mut bez = BezPath::new();
+        bez.move_to((x0, y0));
+        bez.line_to((x1, y0));
+        bez.line_to((x1, y1));
+        bez.line_to((x0, y1));
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    /// A counter-clockwise circle made of four cubics.
+    fn circle(r: f64) -> Path {
+        let k = 0.5523 * r;
+        let mut bez = BezPath::new();
+        bez.move_to((r, 0.));
+        bez.curve_to((r, k), (k, r), (0., r));
+        bez.curve_to((-k, r), (-r, k), (-r, 0.));
+        bez.curve_to((-r, -k), (-k, -r), (0., -r));
+        bez.curve_to((k, -r), (r, -k), (r, 0.));
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    fn area(paths: &[Path]) -> f64 {
+        paths.iter().map(|p| p.bezier().area()).sum()
+    }
+
+    fn offset(distance: f64, join: Join) -> OffsetSettings {
+        OffsetSettings { distance, join }
+    }
+
+    #[test]
+    fn offset_rect_joins() {
+        let square = rect(0., 0., 100., 100.);
+        let miter = offset_path(&square, offset(10., Join::Miter));
+        assert_eq!(miter.len(), 1);
+        assert!((area(&miter) - 120. * 120.).abs() < 1e-6);
+
+        let bevel = offset_path(&square, offset(10., Join::Bevel));
+        assert!((area(&bevel) - (120. * 120. - 4. * 50.)).abs() < 1e-6);
+
+        // the corners are quarter circles, with rounded control points
+        let round = offset_path(&square, offset(10., Join::Round));
+        let expected = 100. * 100. + 4. * 100. * 10. + std::f64::consts::PI * 100.;
+        assert!((area(&round) - expected).abs() < 0.01 * expected);
+
+        let inset = offset_path(&square, offset(-10., Join::Round));
+        assert_eq!(inset.len(), 1);
+        assert!((area(&inset) - 80. * 80.).abs() < 1e-6);
+
+        assert!(offset_path(&square, offset(-60., Join::Miter)).is_empty());
+    }
+
+    #[test]
+    fn offset_circle() {
+        let bigger = offset_path(&circle(100.), offset(20., Join::Miter));
+        assert_eq!(bigger.len(), 1);
+        let expected = std::f64::consts::PI * 120. * 120.;
+        assert!((area(&bigger) - expected).abs() < 0.005 * expected);
+        let on_curve = bigger[0]
+            .points()
+            .iter()
+            .filter(|p| p.is_on_curve())
+            .count();
+        assert!(on_curve <= 8, "too many points: {}", on_curve);
+
+        let smaller = offset_path(&circle(100.), offset(-20., Join::Miter));
+        let expected = std::f64::consts::PI * 80. * 80.;
+        assert!((area(&smaller) - expected).abs() < 0.005 * expected);
+    }
+
+    #[test]
+    fn offset_counter() {
+        // a clockwise contour gets smaller with a positive offset
+        let mut counter = rect(0., 0., 100., 100.);
+        counter.reverse_contour();
+        let result = offset_path(&counter, offset(10., Join::Miter));
+        assert_eq!(result.len(), 1);
+        assert!((area(&result) + 80. * 80.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn expand_line() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((100., 0.));
+        let line = Path::from_bezpath(bez).unwrap();
+        let stroke = |cap| StrokeSettings {
+            width: 20.,
+            cap,
+            join: Join::Miter,
+        };
+
+        let butt = expand_stroke(&line, stroke(Cap::Butt));
+        assert_eq!(butt.len(), 1);
+        assert!(butt[0].is_closed());
+        assert!((area(&butt) - 2000.).abs() < 1e-6);
+
+        let square = expand_stroke(&line, stroke(Cap::Square));
+        assert!((area(&square) - 2400.).abs() < 1e-6);
+
+        let round = expand_stroke(&line, stroke(Cap::Round));
+        let expected = 2000. + std::f64::consts::PI * 100.;
+        assert!((area(&round) - expected).abs() < 0.01 * expected);
+    }
+
+    #[test]
+    fn expand_corner() {
+        // an 'L' with a sharp corner; the inside of the corner overlaps
+        let mut bez = BezPath::new();
+        bez.move_to((0., 100.));
+        bez.line_to((0., 0.));
+        bez.line_to((100., 0.));
+        let path = Path::from_bezpath(bez).unwrap();
+        let settings = StrokeSettings {
+            width: 20.,
+            cap: Cap::Butt,
+            join: Join::Miter,
+        };
+        let result = expand_stroke(&path, settings);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].points().len(), 6);
+        assert!((area(&result) - (110. * 20. + 90. * 20.)).abs() < 1e-6);
+    }
+}
@@ -4,15 +4,17 @@ use std::sync::Arc;
 
 use super::design_space::{DPoint, DVec2, ViewPort};
 use druid::kurbo::{
-    Affine, BezPath, CubicBez, Line, ParamCurve, ParamCurveNearest, PathEl,
-    PathSeg as KurboPathSeg, Point, Vec2,
+    Affine, BezPath, CubicBez, Line, ParamCurve, ParamCurveArclen, ParamCurveExtrema,
+    ParamCurveNearest, PathEl, PathSeg as KurboPathSeg, Point, Shape, Vec2,
 };
 use druid::Data;
 
 use crate::selection::Selection;
+use crate::spline::Spline;
 
 const RESERVED_ID_COUNT: usize = 5;
 const GUIDE_TYPE_ID: usize = 1;
+const COMPONENT_TYPE_ID: usize = 2;
 
 /// We give paths & points unique integer identifiers.
 pub fn next_id() -> usize {
@@ -38,6 +40,9 @@ pub struct EntityId {
 pub enum PointType {
     OnCurve,
     OnCurveSmooth,
+    /// A smooth point between a line and a curve; its handle always points
+    /// along the line.
+    OnCurveTangent,
     OffCurve,
 }
 
@@ -68,6 +73,13 @@ pub struct Path {
     points: Arc<Vec<PathPoint>>,
     trailing: Option<DPoint>,
     closed: bool,
+    /// The spline this path was solved from, if it was drawn with the spline
+    /// tool. This is discarded if the points are edited directly.
+    spline: Option<Arc<Spline>>,
+    /// Locked paths are drawn, but can't be selected.
+    locked: bool,
+    /// Hidden paths are neither drawn nor selectable, but are still saved.
+    hidden: bool,
 }
 
 /// Questionable.
@@ -108,6 +120,15 @@ impl EntityId {
         self.parent == GUIDE_TYPE_ID
     }
 
+    #[inline]
+    pub fn new_for_component() -> Self {
+        EntityId::new_with_parent(COMPONENT_TYPE_ID)
+    }
+
+    pub fn is_component(self) -> bool {
+        self.parent == COMPONENT_TYPE_ID
+    }
+
     pub(crate) fn parent_eq(self, other: EntityId) -> bool {
         self.parent == other.parent
     }
@@ -121,7 +142,7 @@ impl EntityId {
 impl PointType {
     pub fn is_on_curve(self) -> bool {
         match self {
-            PointType::OnCurve | PointType::OnCurveSmooth => true,
+            PointType::OnCurve | PointType::OnCurveSmooth | PointType::OnCurveTangent => true,
             PointType::OffCurve => false,
         }
     }
@@ -178,6 +199,9 @@ impl Path {
             points: Arc::new(vec![start]),
             closed: false,
             trailing: None,
+            spline: None,
+            locked: false,
+            hidden: false,
         }
     }
 
@@ -202,12 +226,16 @@ impl Path {
 
             points.rotate_left(rotate_distance);
         }
+        mark_tangent_points(&mut points, closed);
 
         Path {
             id,
             points: Arc::new(points),
             trailing,
             closed,
+            spline: None,
+            locked: false,
+            hidden: false,
         }
     }
 
@@ -278,6 +306,60 @@ impl Path {
         Ok(Self::from_raw_parts(path_id, points, None, closed))
     }
 
+    /// Create a path from a spline, solving for its control points.
+    pub(crate) fn from_spline(spline: Spline) -> Path {
+        let mut path = Path::new(spline.points()[0].point);
+        path.set_spline(spline);
+        path
+    }
+
+    /// Replace the points of this path with those solved from `spline`.
+    pub(crate) fn set_spline(&mut self, spline: Spline) {
+        let solved =
+            Path::from_bezpath(spline.to_bezier()).expect("spline bezier starts with moveto");
+        let mut points = solved.points.as_ref().clone();
+        for pt in &mut points {
+            pt.id = EntityId::new_with_parent(self.id);
+        }
+        self.points = Arc::new(points);
+        self.closed = solved.closed;
+        self.trailing = None;
+        self.spline = Some(Arc::new(spline));
+    }
+
+    /// Attach the spline these points were solved from, without solving it
+    /// again. This is for restoring a path whose points are already known.
+    pub(crate) fn attach_spline(&mut self, spline: Spline) {
+        self.spline = Some(Arc::new(spline));
+    }
+
+    /// The spline this path was solved from, if any.
+    pub(crate) fn spline(&self) -> Option<&Spline> {
+        self.spline.as_deref()
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    pub(crate) fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    pub(crate) fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    pub(crate) fn set_hidden(&mut self, hidden: bool) {
+        self.hidden = hidden;
+    }
+
+    /// Whether this path can be selected and edited: that is, whether it is
+    /// neither locked nor hidden.
+    pub(crate) fn is_editable(&self) -> bool {
+        !self.locked && !self.hidden
+    }
+
     pub fn from_norad(src: &norad::glyph::Contour) -> Path {
         use norad::glyph::PointType as NoradPType;
         assert!(
@@ -326,18 +408,13 @@ impl Path {
         let mut prev_off_curve = self.points.last().map(|p| p.typ) == Some(PointType::OffCurve);
         for p in self.points.iter() {
             let typ = match p.typ {
-                PointType::OnCurve | PointType::OnCurveSmooth
-                    if points.is_empty() && !self.closed =>
-                {
-                    NoradPType::Move
-                }
                 PointType::OffCurve => NoradPType::OffCurve,
-                PointType::OnCurve | PointType::OnCurveSmooth if prev_off_curve => {
-                    NoradPType::Curve
-                }
+                _ if points.is_empty() && !self.closed => NoradPType::Move,
+                _ if prev_off_curve => NoradPType::Curve,
                 _ => NoradPType::Line,
             };
-            let smooth = p.typ == PointType::OnCurveSmooth;
+            // UFO has no tangent type; a smooth point next to a line is one.
+            let smooth = matches!(p.typ, PointType::OnCurveSmooth | PointType::OnCurveTangent);
             let x = p.point.x as f32;
             let y = p.point.y as f32;
             points.push(ContourPoint {
@@ -380,9 +457,18 @@ impl Path {
     }
 
     fn points_mut(&mut self) -> &mut Vec<PathPoint> {
+        self.spline = None;
         Arc::make_mut(&mut self.points)
     }
 
+    /// Replace the point at `idx`.
+    ///
+    /// This is used to apply undo changes, and does not update any
+    /// neighbouring points.
+    pub(crate) fn replace_point(&mut self, idx: usize, point: PathPoint) {
+        self.points_mut()[idx] = point;
+    }
+
     pub fn iter_segments(&self) -> impl Iterator<Item = PathSeg> {
         let prev_pt = *self.start_point();
         let idx = if self.closed { 0 } else { 1 };
@@ -431,7 +517,7 @@ impl Path {
         }
     }
 
-    fn end_point(&self) -> &PathPoint {
+    pub(crate) fn end_point(&self) -> &PathPoint {
         assert!(!self.points.is_empty(), "empty path is not constructable");
         let idx = if self.closed {
             self.points.len().saturating_sub(2)
@@ -603,11 +689,32 @@ impl Path {
         self.transform_points(points, scale_xform, anchor);
     }
 
+    /// Apply an arbitrary transform to the given points, around `anchor`.
+    ///
+    /// If the transform mirrors and the whole path is transformed, the path
+    /// is reversed, so that it keeps its direction.
+    pub(crate) fn apply_transform(&mut self, points: &[EntityId], affine: Affine, anchor: DPoint) {
+        self.transform_points(points, affine, anchor);
+        let whole_path = self.points.iter().all(|pt| points.contains(&pt.id));
+        if affine.determinant() < 0.0 && whole_path {
+            self.reverse_contour();
+        }
+    }
+
     pub(crate) fn nudge_points(&mut self, points: &[EntityId], v: DVec2) {
         let affine = Affine::translate(v.to_raw());
         self.transform_points(points, affine, DPoint::ZERO);
     }
 
+    /// The ids of the points that `nudge_points` or `apply_transform` move
+    /// for these `points`; this includes the handles of on-curve points.
+    pub(crate) fn points_moved_with(&self, points: &[EntityId]) -> Vec<EntityId> {
+        self.points_for_points(points)
+            .into_iter()
+            .map(|idx| self.points[idx].id)
+            .collect()
+    }
+
     pub(crate) fn nudge_all_points(&mut self, v: DVec2) {
         let affine = Affine::translate(v.to_raw());
         for idx in 0..self.points.len() {
@@ -637,6 +744,20 @@ impl Path {
                 }
             }
         }
+
+        // put the handles of tangent points back on their lines, unless the
+        // whole junction moved together.
+        for idx in 0..self.points.len() {
+            if let Some((line_end, handle)) = self.tangent_line_and_handle(idx) {
+                let moved = |i: usize| to_xform.contains(&i);
+                if moved(idx) && moved(line_end) && moved(handle) {
+                    continue;
+                }
+                if moved(idx) || moved(line_end) || moved(handle) {
+                    self.constrain_tangent(idx, moved(handle) && !moved(idx));
+                }
+            }
+        }
     }
 
     fn transform_point(&mut self, idx: usize, affine: Affine, anchor: DPoint) {
@@ -725,6 +846,50 @@ impl Path {
         self.points_mut()[bcp2].point = new_pos;
     }
 
+    /// For a tangent point, returns the index of the on-curve point at the
+    /// other end of its line, and of its handle.
+    fn tangent_line_and_handle(&self, idx: usize) -> Option<(usize, usize)> {
+        if self.points[idx].typ != PointType::OnCurveTangent {
+            return None;
+        }
+        let prev = self.prev_idx(idx);
+        let next = self.next_idx(idx);
+        match (
+            self.points[prev].is_on_curve(),
+            self.points[next].is_on_curve(),
+        ) {
+            (true, false) => Some((prev, next)),
+            (false, true) => Some((next, prev)),
+            _ => None,
+        }
+    }
+
+    /// Move the handle of the tangent point at `idx` onto the continuation of
+    /// its line.
+    ///
+    /// If `project` is `true` the handle moves to the nearest point on the
+    /// line, as when the handle itself is dragged; otherwise it keeps its
+    /// length, as when the line has moved.
+    fn constrain_tangent(&mut self, idx: usize, project: bool) {
+        let (line_end, handle) = match self.tangent_line_and_handle(idx) {
+            Some(pair) => pair,
+            None => return,
+        };
+        let on_curve = self.points[idx].point;
+        let dir = (on_curve - self.points[line_end].point).to_raw();
+        if dir.hypot() == 0.0 {
+            return;
+        }
+        let dir = dir.normalize();
+        let offset = (self.points[handle].point - on_curve).to_raw();
+        let handle_len = if project {
+            offset.dot(dir).max(0.0)
+        } else {
+            offset.hypot()
+        };
+        self.points_mut()[handle].point = on_curve + DVec2::from_raw(dir * handle_len);
+    }
+
     pub fn update_handle(&mut self, point: EntityId, mut dpt: DPoint, is_locked: bool) {
         if let Some(bcp1) = self.idx_for_point(point) {
             if let Some((on_curve, bcp2)) = self.tangent_handle_opt(bcp1) {
@@ -735,8 +900,47 @@ impl Path {
                 if let Some(bcp2) = bcp2 {
                     self.adjust_handle_angle(bcp1, on_curve, bcp2);
                 }
+                self.constrain_tangent(on_curve, true);
+            }
+        }
+    }
+
+    /// Slide the selected smooth points so that the curvature is the same on
+    /// both sides of them (G2 continuity).
+    ///
+    /// The handles stay where they are, so each point keeps its tangent while
+    /// the lengths of its two handles change. Points where the curve
+    /// inflects are left alone. Returns `true` if anything moved.
+    pub(crate) fn harmonize(&mut self, points: &[EntityId]) -> bool {
+        let mut changed = false;
+        for id in points {
+            let idx = match self.idx_for_point(*id) {
+                Some(idx) if self.points[idx].typ == PointType::OnCurveSmooth => idx,
+                _ => continue,
+            };
+            let prev = self.prev_idx(idx);
+            let next = self.next_idx(idx);
+            let neighbors = [self.prev_idx(prev), prev, next, self.next_idx(next)];
+            if neighbors.iter().any(|i| self.points[*i].is_on_curve()) {
+                continue;
+            }
+            let [in_outer, in_handle, out_handle, out_outer] = neighbors;
+            let raw = |i: usize| self.points[i].point.to_raw();
+            let harmonized = harmonized_point(
+                raw(in_outer),
+                raw(in_handle),
+                raw(out_handle),
+                raw(out_outer),
+            );
+            if let Some(pt) = harmonized {
+                let pt = DPoint::from_raw(pt);
+                if pt != self.points[idx].point {
+                    self.points_mut()[idx].point = pt;
+                    changed = true;
+                }
             }
         }
+        changed
     }
 
     pub fn debug_print_points(&self) {
@@ -824,11 +1028,19 @@ impl Path {
             };
             let next_idx = (idx + 1) % self.points.len();
 
-            if self.points[idx].typ == PointType::OnCurveSmooth
-                && self.points[prev_idx].is_on_curve()
-                && self.points[next_idx].is_on_curve()
-            {
+            let has_handles = (
+                !self.points[prev_idx].is_on_curve(),
+                !self.points[next_idx].is_on_curve(),
+            );
+            let typ = self.points[idx].typ;
+            if typ == PointType::OnCurveSmooth && has_handles == (false, false) {
                 self.points_mut()[idx].typ = PointType::OnCurve;
+            } else if typ == PointType::OnCurveTangent {
+                self.points_mut()[idx].typ = match has_handles {
+                    (false, false) => PointType::OnCurve,
+                    (true, true) => PointType::OnCurveSmooth,
+                    _ => PointType::OnCurveTangent,
+                };
             }
         }
 
@@ -859,16 +1071,20 @@ impl Path {
         len > 2 && !self.points[len - 2].is_on_curve()
     }
 
+    /// Toggle an on-curve point between corner and smooth; a point with a
+    /// handle on only one side becomes a tangent point.
     pub fn toggle_on_curve_point_type(&mut self, id: EntityId) {
         let idx = self.idx_for_point(id).unwrap();
-        let has_ctrl = !self.points[self.prev_idx(idx)].is_on_curve()
-            || !self.points[self.next_idx(idx)].is_on_curve();
+        let prev_ctrl = !self.points[self.prev_idx(idx)].is_on_curve();
+        let next_ctrl = !self.points[self.next_idx(idx)].is_on_curve();
         let point = &mut self.points_mut()[idx];
         point.typ = match point.typ {
-            PointType::OnCurve if has_ctrl => PointType::OnCurveSmooth,
-            PointType::OnCurveSmooth => PointType::OnCurve,
+            PointType::OnCurve if prev_ctrl && next_ctrl => PointType::OnCurveSmooth,
+            PointType::OnCurve if prev_ctrl || next_ctrl => PointType::OnCurveTangent,
+            PointType::OnCurveSmooth | PointType::OnCurveTangent => PointType::OnCurve,
             other => other,
-        }
+        };
+        self.constrain_tangent(idx, true);
     }
 
     /// If the user drags after mousedown, we convert the last point to a curve.
@@ -947,6 +1163,125 @@ impl Path {
         self.points_mut()[..last].reverse();
     }
 
+    /// Make the on-curve `point` the start of this closed path.
+    ///
+    /// Returns `false` if the path is open, or `point` is not one of its
+    /// on-curve points.
+    pub(crate) fn set_start_point(&mut self, point: EntityId) -> bool {
+        if !self.closed {
+            return false;
+        }
+        let idx = match self
+            .points
+            .iter()
+            .position(|pt| pt.id == point && pt.is_on_curve())
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        // the start of a closed path is its last point
+        self.points_mut().rotate_left(idx + 1);
+        true
+    }
+
+    /// Returns `true` if this closed path runs clockwise.
+    pub(crate) fn is_clockwise(&self) -> bool {
+        self.bezier().area() < 0.0
+    }
+
+    /// Returns `true` if this closed path surrounds all of `other`.
+    ///
+    /// Every on-curve point of `other` has to be inside this path, and its
+    /// bounds inside ours; contours that merely overlap don't count.
+    pub(crate) fn encloses(&self, other: &Path) -> bool {
+        if !self.is_closed() {
+            return false;
+        }
+        let bez = self.bezier();
+        let bounds = bez.bounding_box();
+        let other_bounds = other.bezier().bounding_box();
+        bounds.union(other_bounds) == bounds
+            && other
+                .points()
+                .iter()
+                .filter(|pt| pt.is_on_curve())
+                .all(|pt| bez.winding(pt.point.to_raw()) != 0)
+    }
+
+    /// Returns `true` if `point` is the first or last point of an open path.
+    pub(crate) fn is_endpoint(&self, point: EntityId) -> bool {
+        !self.closed && (self.start_point().id == point || self.end_point().id == point)
+    }
+
+    /// Append the open path `other` to the end of this open path, with a new
+    /// segment from our last point to its first.
+    ///
+    /// If we have a trailing handle, the new segment is a curve.
+    pub(crate) fn append_path(&mut self, other: &Path) {
+        assert!(!self.closed && !other.closed);
+        let id = self.id;
+        if let Some(trailing) = self.trailing.take() {
+            let p2 = self
+                .end_point()
+                .point
+                .lerp(other.start_point().point, 2.0 / 3.0);
+            self.points_mut().push(PathPoint::off_curve(id, trailing));
+            self.points_mut().push(PathPoint::off_curve(id, p2));
+        }
+        let points = other.points.iter().map(|pt| {
+            let mut pt = *pt;
+            pt.id.set_parent(id);
+            pt
+        });
+        self.points_mut().extend(points);
+    }
+
+    /// Cut the path at each of the on-curve `points`, returning the pieces,
+    /// which are all open.
+    ///
+    /// A closed path is first opened at one of the points, so that it starts
+    /// there and ends at a copy of it. The first piece keeps this path's id;
+    /// the others get new ids. Returns `None` if there is nowhere to cut.
+    pub(crate) fn break_at(&self, points: &[EntityId]) -> Option<Vec<Path>> {
+        let is_cut = |pt: &PathPoint| pt.is_on_curve() && points.contains(&pt.id);
+        let mut all_points = self.points.to_vec();
+        if self.closed {
+            let idx = all_points.iter().position(is_cut)?;
+            all_points.rotate_left(idx);
+            let mut end = all_points[0];
+            end.id = EntityId::new_with_parent(self.id);
+            all_points.push(end);
+        }
+        let last = all_points.len() - 1;
+        let cuts: Vec<usize> = (1..last).filter(|i| is_cut(&all_points[*i])).collect();
+        if !self.closed && cuts.is_empty() {
+            return None;
+        }
+
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        for end in cuts.into_iter().chain(std::iter::once(last)) {
+            let mut piece = all_points[start..=end].to_vec();
+            let path_id = if pieces.is_empty() {
+                self.id
+            } else {
+                next_id()
+            };
+            for pt in piece.iter_mut() {
+                pt.id.set_parent(path_id);
+            }
+            if start > 0 {
+                // the cut point is shared with the previous piece
+                piece[0].id = EntityId::new_with_parent(path_id);
+            }
+            piece[0].typ = PointType::OnCurve;
+            piece.last_mut().unwrap().typ = PointType::OnCurve;
+            pieces.push(Path::from_raw_parts(path_id, piece, None, false));
+            start = end;
+        }
+        Some(pieces)
+    }
+
     #[inline]
     fn prev_idx(&self, idx: usize) -> usize {
         if idx == 0 {
@@ -984,6 +1319,53 @@ impl Path {
         self.points[idx]
     }
 
+    /// The indices of the points on either side of the point at `idx`.
+    ///
+    /// The ends of an open path only have one neighbour.
+    fn neighbour_idxs(&self, idx: usize) -> (Option<usize>, Option<usize>) {
+        let prev = if self.closed || idx > 0 {
+            Some(self.prev_idx(idx))
+        } else {
+            None
+        };
+        let next = if self.closed || idx + 1 < self.points.len() {
+            Some(self.next_idx(idx))
+        } else {
+            None
+        };
+        (prev, next)
+    }
+
+    /// The ids of the points in `sel`, along with those of their neighbours.
+    pub(crate) fn grown_selection(&self, sel: &Selection) -> Vec<EntityId> {
+        let selected: Vec<_> = self.points.iter().map(|pt| sel.contains(&pt.id)).collect();
+        (0..self.points.len())
+            .filter(|idx| {
+                let (prev, next) = self.neighbour_idxs(*idx);
+                selected[*idx]
+                    || prev.map(|i| selected[i]).unwrap_or(false)
+                    || next.map(|i| selected[i]).unwrap_or(false)
+            })
+            .map(|idx| self.points[idx].id)
+            .collect()
+    }
+
+    /// The ids of the points in `sel` whose neighbours are also in `sel`.
+    ///
+    /// The ends of an open path are always removed.
+    pub(crate) fn shrunk_selection(&self, sel: &Selection) -> Vec<EntityId> {
+        let selected: Vec<_> = self.points.iter().map(|pt| sel.contains(&pt.id)).collect();
+        (0..self.points.len())
+            .filter(|idx| {
+                let (prev, next) = self.neighbour_idxs(*idx);
+                selected[*idx]
+                    && prev.map(|i| selected[i]).unwrap_or(false)
+                    && next.map(|i| selected[i]).unwrap_or(false)
+            })
+            .map(|idx| self.points[idx].id)
+            .collect()
+    }
+
     pub(crate) fn split_segment_at_point(&mut self, seg: PathSeg, t: f64) {
         let (existing_control_pts, points_to_insert) = match seg {
             PathSeg::Line(..) => (0, 1),
@@ -1028,10 +1410,170 @@ impl Path {
             [p1, p2].iter().map(|p| PathPoint::off_curve(path, *p)),
         );
     }
+
+    /// Split each curve at its horizontal and vertical extrema, so that
+    /// there is an on-curve point at each of them.
+    pub(crate) fn add_extremes(&mut self) {
+        let curves: Vec<PathSeg> = self
+            .iter_segments()
+            .filter(|seg| matches!(seg, PathSeg::Cubic(..)))
+            .collect();
+        for seg in curves {
+            let kurbo_seg = seg.to_kurbo();
+            let (start, end) = (kurbo_seg.start(), kurbo_seg.end());
+            let mut extrema: Vec<f64> = kurbo_seg
+                .extrema()
+                .into_iter()
+                .filter(|t| {
+                    // a degenerate curve can give us NaNs, which we can't sort.
+                    let pt = kurbo_seg.eval(*t);
+                    t.is_finite() && pt.distance(start) >= 1.0 && pt.distance(end) >= 1.0
+                })
+                .collect();
+            extrema.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            extrema.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+
+            // we split from the end, so that the segment we are splitting
+            // always starts at the original start point; `remaining` is the
+            // portion of the original segment that is still left.
+            let mut remaining = 1.0;
+            for t in extrema.into_iter().rev() {
+                let seg = self
+                    .iter_segments()
+                    .find(|s| s.start_id() == seg.start_id())
+                    .unwrap();
+                self.split_segment_at_point(seg, t / remaining);
+                remaining = t;
+            }
+        }
+    }
+
+    /// Remove on-curve points that do not change the shape of the path by
+    /// more than `tolerance`: points in the middle of a straight line, and
+    /// points between two curves that can be replaced by a single curve.
+    ///
+    /// Returns the ids of the removed points.
+    pub(crate) fn remove_redundant_points(&mut self, tolerance: f64) -> Vec<EntityId> {
+        let mut removed = Vec::new();
+        // each removal changes the segments, so we start over after each.
+        while let Some(removal) = self.find_redundant_point(tolerance) {
+            let closed = self.closed;
+            let points = self.points_mut();
+            for (id, point) in removal.moved.iter().copied() {
+                if let Some(pt) = points.iter_mut().find(|pt| pt.id == id) {
+                    pt.point = point;
+                }
+            }
+            points.retain(|pt| !removal.removed.contains(&pt.id));
+            // if we removed the start of a closed path, the last point should
+            // still be on-curve.
+            if closed && !points.last().unwrap().is_on_curve() {
+                let rotate_distance = points.iter().position(|p| p.is_on_curve()).unwrap() + 1;
+                points.rotate_left(rotate_distance);
+            }
+            removed.extend(removal.removed);
+        }
+        removed
+    }
+
+    fn find_redundant_point(&self, tolerance: f64) -> Option<Removal> {
+        let segs: Vec<PathSeg> = self.iter_segments().collect();
+        // a closed path needs at least two segments.
+        let min_segs = if self.closed { 3 } else { 2 };
+        if segs.len() < min_segs {
+            return None;
+        }
+        let n_junctions = if self.closed {
+            segs.len()
+        } else {
+            segs.len() - 1
+        };
+
+        (0..n_junctions).find_map(|i| {
+            let (prev, next) = (segs[i], segs[(i + 1) % segs.len()]);
+            match (prev, next) {
+                (PathSeg::Line(p0, p1), PathSeg::Line(_, p2)) => {
+                    let line = Line::new(p0.point.to_raw(), p2.point.to_raw());
+                    let (t, dist_sq) = line.nearest(p1.point.to_raw(), 0.1);
+                    let is_between = t > 0.0 && t < 1.0;
+                    if dist_sq.sqrt() <= tolerance && (is_between || p0.point == p1.point) {
+                        Some(Removal {
+                            removed: vec![p1.id],
+                            moved: Vec::new(),
+                        })
+                    } else {
+                        None
+                    }
+                }
+                (PathSeg::Cubic(_, p1, p2, p3), PathSeg::Cubic(_, p4, p5, _)) => {
+                    let originals = [prev.to_kurbo(), next.to_kurbo()];
+                    let merged = merge_cubics(originals[0], originals[1])?;
+                    let merged_seg = KurboPathSeg::Cubic(merged);
+                    let dist_to_originals = |pt: Point| {
+                        originals
+                            .iter()
+                            .map(|seg| seg.nearest(pt, 0.1).1)
+                            .fold(f64::MAX, f64::min)
+                            .sqrt()
+                    };
+                    let is_close = originals.iter().all(|seg| {
+                        sample_points(*seg)
+                            .all(|pt| merged_seg.nearest(pt, 0.1).1.sqrt() <= tolerance)
+                    }) && sample_points(merged_seg)
+                        .all(|pt| dist_to_originals(pt) <= tolerance);
+                    if !is_close {
+                        return None;
+                    }
+                    Some(Removal {
+                        removed: vec![p2.id, p3.id, p4.id],
+                        moved: vec![
+                            (p1.id, DPoint::from_raw(merged.p1)),
+                            (p5.id, DPoint::from_raw(merged.p2)),
+                        ],
+                    })
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// The points to remove, and the handles to move, to remove a redundant
+/// point from a path.
+struct Removal {
+    removed: Vec<EntityId>,
+    moved: Vec<(EntityId, DPoint)>,
 }
 
-/// Walk the points in a list and mark those that look like tangent points
-/// as being tangent points (OnCurveSmooth).
+/// Guess the single cubic that `first` and `second` were split from.
+///
+/// The split is assumed to be where the arc lengths of the two curves
+/// suggest; the handles keep their directions, and are scaled accordingly.
+fn merge_cubics(first: KurboPathSeg, second: KurboPathSeg) -> Option<CubicBez> {
+    let (first, second) = match (first, second) {
+        (KurboPathSeg::Cubic(first), KurboPathSeg::Cubic(second)) => (first, second),
+        _ => return None,
+    };
+    let len_first = first.arclen(0.1);
+    let len_second = second.arclen(0.1);
+    let t = len_first / (len_first + len_second);
+    if !(t > 0.0 && t < 1.0) {
+        return None;
+    }
+    let p1 = first.p0 + (first.p1 - first.p0) / t;
+    let p2 = second.p3 + (second.p2 - second.p3) / (1.0 - t);
+    Some(CubicBez::new(first.p0, p1, p2, second.p3))
+}
+
+/// Points along the inside of `seg`, for checking how close it is to
+/// another curve.
+fn sample_points(seg: KurboPathSeg) -> impl Iterator<Item = Point> {
+    (1..8).map(move |i| seg.eval(i as f64 / 8.0))
+}
+
+/// Walk the points in a list and mark those whose handles are in line as
+/// being smooth (OnCurveSmooth), or tangent (OnCurveTangent) if they have
+/// a line on one side.
 pub(crate) fn mark_tangent_handles(points: &mut [PathPoint]) {
     let len = points.len();
 
@@ -1058,6 +1600,12 @@ pub(crate) fn mark_tangent_handles(points: &mut [PathPoint]) {
                 if delta_angle <= 0.018 {
                     pt.typ = PointType::OnCurveSmooth;
                 }
+            } else if prev.is_on_curve() != next.is_on_curve() {
+                let prev_angle = (prev.point.to_raw() - pt.point.to_raw()).atan2();
+                let next_angle = (pt.point.to_raw() - next.point.to_raw()).atan2();
+                if (prev_angle - next_angle).abs() <= 0.018 {
+                    pt.typ = PointType::OnCurveTangent;
+                }
             }
         }
         //pt.id.parent = parent_id;
@@ -1066,6 +1614,68 @@ pub(crate) fn mark_tangent_handles(points: &mut [PathPoint]) {
     }
 }
 
+/// Mark smooth points with a line on one side as tangent points.
+///
+/// Sources such as UFO only know whether a point is smooth, so we work out
+/// which smooth points are tangents from their neighbours.
+fn mark_tangent_points(points: &mut [PathPoint], closed: bool) {
+    let len = points.len();
+    for idx in 0..len {
+        let is_end = !closed && (idx == 0 || idx == len - 1);
+        if points[idx].typ != PointType::OnCurveSmooth || is_end {
+            continue;
+        }
+        let prev = points[(idx + len - 1) % len];
+        let next = points[(idx + 1) % len];
+        if prev.is_on_curve() != next.is_on_curve() {
+            points[idx].typ = PointType::OnCurveTangent;
+        }
+    }
+}
+
+/// The number of other closed paths in `paths` that enclose the closed path
+/// at `idx`.
+///
+/// Outer contours have an even depth, and counters an odd one; this is what
+/// determines which way each should run. Contours that only overlap don't
+/// enclose each other.
+pub(crate) fn nesting_depth(paths: &[Path], idx: usize) -> usize {
+    paths
+        .iter()
+        .enumerate()
+        .filter(|(i, path)| *i != idx && path.encloses(&paths[idx]))
+        .count()
+}
+
+/// The position on the line between a smooth point's two handles at which
+/// the curves on either side have the same curvature.
+///
+/// The curvature at the end of a cubic is proportional to the distance of
+/// its far handle from the tangent, divided by the square of the length of
+/// its near handle; we split the line so that these match. Returns `None` if
+/// the curve inflects at the point.
+fn harmonized_point(
+    in_outer: Point,
+    in_handle: Point,
+    out_handle: Point,
+    out_outer: Point,
+) -> Option<Point> {
+    let chord = out_handle - in_handle;
+    let len = chord.hypot();
+    if len == 0.0 {
+        return None;
+    }
+    let dir = chord / len;
+    let in_dist = dir.cross(in_outer - in_handle);
+    let out_dist = dir.cross(out_outer - in_handle);
+    if in_dist * out_dist <= 0.0 {
+        return None;
+    }
+    let in_ratio = in_dist.abs().sqrt();
+    let out_ratio = out_dist.abs().sqrt();
+    Some(in_handle + chord * (in_ratio / (in_ratio + out_ratio)))
+}
+
 /// Lock the smallest axis of `point` (from `prev`) to that axis on `prev`.
 fn axis_locked_point(point: DPoint, prev: DPoint) -> DPoint {
     let dxy = prev - point;
@@ -1241,6 +1851,7 @@ impl std::fmt::Debug for PathSeg {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::curvature::curvature;
     use druid::kurbo::{Rect, Shape};
 
     #[test]
@@ -1300,4 +1911,260 @@ mod tests {
         assert_eq!(iter.next(), Some(Line::new((0., 0.), (20., 0.)).into()));
         assert_eq!(iter.next(), Some(Line::new((20., 0.), (10., 10.)).into()));
     }
+
+    #[test]
+    fn add_and_remove_extremes() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.curve_to((0., 100.), (100., 100.), (100., 0.));
+        bez.curve_to((100., -100.), (0., -100.), (0., 0.));
+        bez.close_path();
+        let mut path = Path::from_bezpath(bez).unwrap();
+        let on_curve_count = |path: &Path| path.points().iter().filter(|p| p.is_on_curve()).count();
+
+        path.add_extremes();
+        assert_eq!(on_curve_count(&path), 4);
+        assert!(path
+            .points()
+            .iter()
+            .any(|p| p.point == DPoint::new(50., 75.) && p.typ == PointType::OnCurveSmooth));
+
+        let removed = path.remove_redundant_points(1.0);
+        assert_eq!(removed.len(), 6);
+        assert_eq!(on_curve_count(&path), 2);
+        assert!(path
+            .points()
+            .iter()
+            .any(|p| p.point == DPoint::new(0., 100.) && !p.is_on_curve()));
+
+        // a curve with no length has no extrema to add
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((100., 0.));
+        bez.curve_to((100., 0.), (100., 0.), (100., 0.));
+        bez.line_to((100., 100.));
+        bez.close_path();
+        let mut path = Path::from_bezpath(bez).unwrap();
+        let before = path.points().len();
+        path.add_extremes();
+        assert_eq!(path.points().len(), before);
+    }
+
+    #[test]
+    fn remove_collinear_points() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((50., 0.));
+        bez.line_to((100., 0.));
+        bez.line_to((100., 100.));
+        bez.line_to((0., 100.));
+        bez.close_path();
+        let mut path = Path::from_bezpath(bez).unwrap();
+        assert_eq!(path.remove_redundant_points(1.0).len(), 1);
+        assert_eq!(path.points().len(), 4);
+        assert!(path.remove_redundant_points(1.0).is_empty());
+    }
+
+    #[test]
+    fn mirror_keeps_direction() {
+        let rect = Rect::new(0., 0., 10., 20.);
+        let mut path = Path::from_bezpath(rect.to_path(0.1)).unwrap();
+        let area = path.bezier().area();
+        let ids: Vec<_> = path.points().iter().map(|pt| pt.id).collect();
+        let flip = Affine::scale_non_uniform(-1.0, 1.0);
+        path.apply_transform(&ids, flip, DPoint::new(10., 0.));
+        assert_eq!(path.bezier().bounding_box(), Rect::new(10., 0., 20., 20.));
+        assert_eq!(path.bezier().area(), area);
+
+        // a partial selection is not reversed
+        let first = path.points()[0];
+        path.apply_transform(&[first.id], flip, DPoint::new(10., 0.));
+        assert_eq!(path.points()[0].id, first.id);
+        assert_eq!(path.points()[0].point.x, 20.0 - first.point.x);
+    }
+
+    #[test]
+    fn tangent_handle_follows_line() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((100., 0.));
+        bez.curve_to((150., 0.), (200., 50.), (200., 100.));
+        let mut path = Path::from_bezpath(bez).unwrap();
+        let find = |path: &Path, x, y| {
+            *path
+                .points()
+                .iter()
+                .find(|p| p.point == DPoint::new(x, y))
+                .unwrap()
+        };
+        let tangent = find(&path, 100., 0.);
+        assert_eq!(tangent.typ, PointType::OnCurveTangent);
+
+        // dragging the handle off the line pulls it back on
+        let handle = find(&path, 150., 0.);
+        path.nudge_points(&[handle.id], DVec2::from_raw((0., 20.)));
+        assert_eq!(path.points()[2].point, DPoint::new(150., 0.));
+
+        // moving the other end of the line swings the handle around
+        let start = find(&path, 0., 0.);
+        path.nudge_points(&[start.id], DVec2::from_raw((0., -100.)));
+        assert_eq!(path.points()[2].point, DPoint::new(135., 35.));
+    }
+
+    #[test]
+    fn harmonize_matches_curvature() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.curve_to((0., 100.), (100., 200.), (200., 200.));
+        bez.curve_to((400., 200.), (500., 0.), (600., 0.));
+        let mut path = Path::from_bezpath(bez).unwrap();
+        let ids: Vec<_> = path.points().iter().map(|p| p.id).collect();
+        assert_eq!(path.points()[3].typ, PointType::OnCurveSmooth);
+
+        assert!(path.harmonize(&ids));
+        assert_eq!(path.points()[3].point, DPoint::new(224., 200.));
+        let cubics: Vec<_> = path.bezier().segments().map(|seg| seg.to_cubic()).collect();
+        let k_in = curvature(cubics[0], 1.0).unwrap();
+        let k_out = curvature(cubics[1], 0.0).unwrap();
+        assert!((k_in - k_out).abs() / k_in.abs() < 0.02);
+
+        // already harmonized
+        assert!(!path.harmonize(&ids));
+    }
+
+    #[test]
+    fn set_start_point() {
+        let rect = Rect::new(0., 0., 10., 10.);
+        let mut path = Path::from_bezpath(rect.to_path(0.1)).unwrap();
+        let area = path.bezier().area();
+        let corner = path.points()[1];
+        assert!(path.set_start_point(corner.id));
+        assert_eq!(path.start_point().id, corner.id);
+        assert_eq!(path.iter_segments().next().unwrap().start_id(), corner.id);
+        assert_eq!(path.bezier().area(), area);
+    }
+
+    #[test]
+    fn nested_contours() {
+        let outer = Path::from_bezpath(Rect::new(0., 0., 100., 100.).to_path(0.1)).unwrap();
+        let counter = Path::from_bezpath(Rect::new(25., 25., 75., 75.).to_path(0.1)).unwrap();
+        let dot = Path::from_bezpath(Rect::new(45., 45., 55., 55.).to_path(0.1)).unwrap();
+        let paths = vec![outer, counter, dot];
+        let depths: Vec<_> = (0..3).map(|i| nesting_depth(&paths, i)).collect();
+        assert_eq!(depths, vec![0, 1, 2]);
+        assert!(paths.iter().all(|p| !p.is_clockwise()));
+    }
+
+    #[test]
+    fn overlapping_contours_are_not_nested() {
+        // two overlapping squares, each starting inside the other
+        let mut first = Path::from_bezpath(Rect::new(0., 0., 100., 100.).to_path(0.1)).unwrap();
+        let second = Path::from_bezpath(Rect::new(50., 50., 150., 150.).to_path(0.1)).unwrap();
+        let corner = first
+            .points()
+            .iter()
+            .find(|pt| pt.point == DPoint::new(100., 100.));
+        assert!(first.set_start_point(corner.unwrap().id));
+        let paths = vec![first, second];
+
+        let depths: Vec<_> = (0..2).map(|i| nesting_depth(&paths, i)).collect();
+        assert_eq!(depths, vec![0, 0]);
+    }
+
+    #[test]
+    fn break_closed_and_open() {
+        let rect = Rect::new(0., 0., 10., 10.);
+        let path = Path::from_bezpath(rect.to_path(0.1)).unwrap();
+        let corner = path.points()[1];
+        let pieces = path.break_at(&[corner.id]).unwrap();
+        assert_eq!(pieces.len(), 1);
+        let opened = &pieces[0];
+        assert!(!opened.is_closed());
+        assert_eq!(opened.points().len(), 5);
+        assert_eq!(opened.start_point().id, corner.id);
+        assert_eq!(opened.end_point().point, corner.point);
+        assert_eq!(opened.bezier().area().abs(), 100.);
+
+        // cutting the open path again splits it in two
+        let mid = opened.points()[2];
+        let pieces = opened.break_at(&[mid.id]).unwrap();
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].id(), opened.id());
+        assert_eq!(pieces[0].end_point().id, mid.id);
+        assert_eq!(pieces[1].start_point().point, mid.point);
+        assert_eq!(pieces[1].points().len(), 3);
+        assert!(pieces[1]
+            .points()
+            .iter()
+            .all(|pt| pt.id.parent == pieces[1].id()));
+
+        // the ends of an open path are not cut points
+        assert!(pieces[1].break_at(&[pieces[1].start_point().id]).is_none());
+    }
+
+    #[test]
+    fn append_open_paths() {
+        let mut first = Path::new(DPoint::new(0., 0.));
+        first.append_point(DPoint::new(10., 0.));
+        let mut second = Path::new(DPoint::new(20., 0.));
+        second.append_point(DPoint::new(30., 0.));
+        first.append_path(&second);
+        assert_eq!(first.points().len(), 4);
+        assert!(first.points().iter().all(|pt| pt.id.parent == first.id()));
+        assert_eq!(first.end_point().point, DPoint::new(30., 0.));
+        assert!(first.is_endpoint(first.points()[3].id));
+        assert!(!first.is_endpoint(first.points()[1].id));
+    }
+
+    #[test]
+    fn grow_and_shrink_selection() {
+        let mut path = Path::new(DPoint::new(0., 0.));
+        for i in 1..5 {
+            path.append_point(DPoint::new(i as f64 * 10., 0.));
+        }
+        let ids: Vec<_> = path.points().iter().map(|pt| pt.id).collect();
+
+        let sel: Selection = std::iter::once(ids[0]).collect();
+        let grown = path.grown_selection(&sel);
+        assert_eq!(grown, &ids[..2]);
+        let grown = path.grown_selection(&grown.into_iter().collect());
+        assert_eq!(grown, &ids[..3]);
+
+        // the ends of an open path are always shrunk away
+        let all: Selection = ids.iter().copied().collect();
+        assert_eq!(path.shrunk_selection(&all), &ids[1..4]);
+
+        // a closed path wraps around
+        path.close();
+        let sel: Selection = std::iter::once(ids[0]).collect();
+        assert_eq!(path.grown_selection(&sel).len(), 3);
+        assert_eq!(path.shrunk_selection(&all).len(), ids.len());
+    }
+
+    #[test]
+    fn handles_move_with_their_points() {
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.curve_to((0., 100.), (100., 100.), (100., 0.));
+        bez.curve_to((100., -100.), (0., -100.), (0., 0.));
+        bez.close_path();
+        let path = Path::from_bezpath(bez).unwrap();
+        let id_at = |x: f64, y: f64| {
+            path.points()
+                .iter()
+                .find(|pt| pt.point == DPoint::new(x, y))
+                .unwrap()
+                .id
+        };
+
+        let mut moved = path.points_moved_with(&[id_at(100., 0.)]);
+        moved.sort();
+        let mut expected = vec![id_at(100., 0.), id_at(100., 100.), id_at(100., -100.)];
+        expected.sort();
+        assert_eq!(moved, expected);
+        assert_eq!(
+            path.points_moved_with(&[id_at(0., 100.)]),
+            vec![id_at(0., 100.)]
+        );
+    }
 }
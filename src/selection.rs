@@ -5,6 +5,17 @@ use druid::Data;
 
 use crate::path::EntityId;
 
+/// The kinds of point selected by the 'select similar' commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectSimilar {
+    /// All off-curve points.
+    OffCurve,
+    /// All smooth on-curve points, including tangents.
+    Smooth,
+    /// All points at the same height as a selected point.
+    SameY,
+}
+
 /// A sorted set of selected items.
 #[derive(Debug, Clone, Data)]
 pub struct Selection {
@@ -0,0 +1,593 @@
+//! Compact, reversible changes to an `EditSession`, for undo.
+//!
+//! Most edits touch a small number of points; instead of storing a copy of
+//! the session for each undo group we record only what changed, keyed by
+//! the ids of the changed paths and points.
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use druid::Data;
+use norad::glyph::Advance;
+
+use crate::component::Component;
+use crate::edit_session::EditSession;
+use crate::guides::Guide;
+use crate::measurement::Measurement;
+use crate::path::{Path, PathPoint};
+use crate::selection::Selection;
+use crate::undo::Undoable;
+
+/// The changes between two `EditSession`s.
+#[derive(Debug)]
+pub(crate) struct SessionDiff {
+    paths: Option<PathsDiff>,
+    components: Option<Change<Arc<Vec<Component>>>>,
+    guides: Option<Change<Arc<Vec<Guide>>>>,
+    measurements: Option<Change<Arc<Vec<Measurement>>>>,
+    selection: Option<Change<Selection>>,
+    advance: Option<Change<Option<Advance>>>,
+}
+
+/// A value before and after some edit.
+#[derive(Debug, Clone)]
+struct Change<T> {
+    before: T,
+    after: T,
+}
+
+#[derive(Debug)]
+struct PathsDiff {
+    /// The ids of all paths, in order, if paths were added, removed or reordered.
+    order: Option<Change<Vec<usize>>>,
+    changes: Vec<PathChange>,
+}
+
+#[derive(Debug)]
+enum PathChange {
+    /// Some points in the path were moved or changed type; the number and
+    /// identity of the points is unchanged.
+    Points {
+        path_id: usize,
+        points: Vec<(usize, Change<PathPoint>)>,
+    },
+    /// A path was added, removed, or had points added or removed.
+    Path {
+        path_id: usize,
+        change: Change<Option<Path>>,
+    },
+}
+
+impl<T> Change<T> {
+    fn get(&self, forward: bool) -> &T {
+        if forward {
+            &self.after
+        } else {
+            &self.before
+        }
+    }
+}
+
+impl SessionDiff {
+    fn new(old: &EditSession, new: &EditSession) -> Self {
+        let paths = if old.paths.same(&new.paths) {
+            None
+        } else {
+            Some(PathsDiff::new(&old.paths, &new.paths))
+        };
+
+        let components = change_if_different(&old.components, &new.components);
+        let guides = change_if_different(&old.guides, &new.guides);
+        let measurements = change_if_different(&old.measurements, &new.measurements);
+        let selection = change_if_different(&old.selection, &new.selection);
+        let advance = if !advance_eq(&old.glyph.advance, &new.glyph.advance) {
+            Some(Change {
+                before: old.glyph.advance.clone(),
+                after: new.glyph.advance.clone(),
+            })
+        } else {
+            None
+        };
+
+        SessionDiff {
+            paths,
+            components,
+            guides,
+            measurements,
+            selection,
+            advance,
+        }
+    }
+
+    fn apply(&self, session: &mut EditSession, forward: bool) {
+        if let Some(paths) = &self.paths {
+            paths.apply(Arc::make_mut(&mut session.paths), forward);
+        }
+        if let Some(components) = &self.components {
+            session.components = components.get(forward).clone();
+        }
+        if let Some(guides) = &self.guides {
+            session.guides = guides.get(forward).clone();
+        }
+        if let Some(measurements) = &self.measurements {
+            session.measurements = measurements.get(forward).clone();
+        }
+        if let Some(selection) = &self.selection {
+            session.selection = selection.get(forward).clone();
+        }
+        if let Some(advance) = &self.advance {
+            Arc::make_mut(&mut session.glyph).advance = advance.get(forward).clone();
+        }
+        if self.paths.is_some() || self.components.is_some() {
+            session.rebuild_glyph();
+        }
+    }
+
+    /// An estimate of the memory retained by this diff.
+    fn size(&self) -> usize {
+        let paths = self.paths.as_ref().map(PathsDiff::size).unwrap_or(0);
+        let components = self
+            .components
+            .as_ref()
+            .map(|c| (c.before.len() + c.after.len()) * size_of::<Component>())
+            .unwrap_or(0);
+        let guides = self
+            .guides
+            .as_ref()
+            .map(|c| (c.before.len() + c.after.len()) * size_of::<Guide>())
+            .unwrap_or(0);
+        let measurements = self
+            .measurements
+            .as_ref()
+            .map(|c| (c.before.len() + c.after.len()) * size_of::<Measurement>())
+            .unwrap_or(0);
+        let selection = self
+            .selection
+            .as_ref()
+            .map(|c| (c.before.len() + c.after.len()) * size_of::<crate::path::EntityId>())
+            .unwrap_or(0);
+        size_of::<Self>() + paths + components + guides + measurements + selection
+    }
+}
+
+impl PathsDiff {
+    fn new(old: &[Path], new: &[Path]) -> Self {
+        let old_ids: Vec<_> = old.iter().map(Path::id).collect();
+        let new_ids: Vec<_> = new.iter().map(Path::id).collect();
+        let order = if old_ids != new_ids {
+            Some(Change {
+                before: old_ids,
+                after: new_ids,
+            })
+        } else {
+            None
+        };
+
+        let old_paths: HashMap<_, _> = old.iter().map(|path| (path.id(), path)).collect();
+        let mut changes = Vec::new();
+        for path in new {
+            match old_paths.get(&path.id()) {
+                Some(old_path) if old_path.same(path) => (),
+                Some(old_path) => changes.push(PathChange::new(old_path, path)),
+                None => changes.push(PathChange::Path {
+                    path_id: path.id(),
+                    change: Change {
+                        before: None,
+                        after: Some(path.clone()),
+                    },
+                }),
+            }
+        }
+
+        let new_paths: HashMap<_, _> = new.iter().map(|path| (path.id(), path)).collect();
+        for path in old.iter().filter(|p| !new_paths.contains_key(&p.id())) {
+            changes.push(PathChange::Path {
+                path_id: path.id(),
+                change: Change {
+                    before: Some(path.clone()),
+                    after: None,
+                },
+            });
+        }
+
+        PathsDiff { order, changes }
+    }
+
+    fn apply(&self, paths: &mut Vec<Path>, forward: bool) {
+        let order = match &self.order {
+            Some(order) => order.get(forward).clone(),
+            None => paths.iter().map(Path::id).collect(),
+        };
+
+        let mut by_id: HashMap<_, _> = paths.drain(..).map(|path| (path.id(), path)).collect();
+        for change in &self.changes {
+            match change {
+                PathChange::Points { path_id, points } => {
+                    let path = by_id.get_mut(path_id).expect("missing path in undo");
+                    for (idx, point) in points {
+                        path.replace_point(*idx, *point.get(forward));
+                    }
+                }
+                PathChange::Path { path_id, change } => match change.get(forward) {
+                    Some(path) => {
+                        by_id.insert(*path_id, path.clone());
+                    }
+                    None => {
+                        by_id.remove(path_id);
+                    }
+                },
+            }
+        }
+
+        paths.extend(order.iter().filter_map(|id| by_id.remove(id)));
+        debug_assert!(by_id.is_empty(), "paths missing from undo order");
+    }
+
+    fn size(&self) -> usize {
+        let order = self
+            .order
+            .as_ref()
+            .map(|o| (o.before.len() + o.after.len()) * size_of::<usize>())
+            .unwrap_or(0);
+        order + self.changes.iter().map(PathChange::size).sum::<usize>()
+    }
+}
+
+impl PathChange {
+    fn new(old: &Path, new: &Path) -> Self {
+        // editing the points of a spline path discards its spline, which
+        // replacing points on undo would not restore.
+        let same_spline = match (old.spline(), new.spline()) {
+            (Some(one), Some(two)) => std::ptr::eq(one, two),
+            (None, None) => true,
+            _ => false,
+        };
+        let only_points_changed = same_spline
+            && old.is_closed() == new.is_closed()
+            && old.trailing() == new.trailing()
+            && old.is_locked() == new.is_locked()
+            && old.is_hidden() == new.is_hidden()
+            && old.points().len() == new.points().len()
+            && old
+                .points()
+                .iter()
+                .zip(new.points())
+                .all(|(one, two)| one.id == two.id);
+
+        if only_points_changed {
+            let points = old
+                .points()
+                .iter()
+                .zip(new.points())
+                .enumerate()
+                .filter(|(_, (one, two))| one != two)
+                .map(|(idx, (one, two))| {
+                    let change = Change {
+                        before: *one,
+                        after: *two,
+                    };
+                    (idx, change)
+                })
+                .collect();
+            PathChange::Points {
+                path_id: new.id(),
+                points,
+            }
+        } else {
+            PathChange::Path {
+                path_id: new.id(),
+                change: Change {
+                    before: Some(old.clone()),
+                    after: Some(new.clone()),
+                },
+            }
+        }
+    }
+
+    fn size(&self) -> usize {
+        let path_size = |path: &Option<Path>| {
+            path.as_ref()
+                .map(|p| size_of::<Path>() + p.points().len() * size_of::<PathPoint>())
+                .unwrap_or(0)
+        };
+        size_of::<Self>()
+            + match self {
+                PathChange::Points { points, .. } => {
+                    points.len() * size_of::<(usize, Change<PathPoint>)>()
+                }
+                PathChange::Path { change, .. } => {
+                    path_size(&change.before) + path_size(&change.after)
+                }
+            }
+    }
+}
+
+fn advance_eq(one: &Option<Advance>, two: &Option<Advance>) -> bool {
+    match (one, two) {
+        (Some(one), Some(two)) => one.width == two.width && one.height == two.height,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn change_if_different<T: Data>(old: &T, new: &T) -> Option<Change<T>> {
+    if old.same(new) {
+        None
+    } else {
+        Some(Change {
+            before: old.clone(),
+            after: new.clone(),
+        })
+    }
+}
+
+impl Undoable for EditSession {
+    type Diff = SessionDiff;
+
+    fn diff(&self, new: &Self) -> SessionDiff {
+        SessionDiff::new(self, new)
+    }
+
+    fn apply_diff(&mut self, diff: &SessionDiff) {
+        diff.apply(self, true)
+    }
+
+    fn revert_diff(&mut self, diff: &SessionDiff) {
+        diff.apply(self, false)
+    }
+
+    fn diff_size(diff: &SessionDiff) -> usize {
+        diff.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Workspace;
+    use crate::design_space::{DPoint, DVec2};
+    use crate::path::EntityId;
+    use crate::spline::{Spline, SplinePoint, SplinePointType};
+    use crate::undo::UndoState;
+    use druid::kurbo::{BezPath, Point};
+
+    /// A session with `n_paths` closed polygons of `n_points` points each.
+    fn make_session(n_paths: usize, n_points: usize) -> EditSession {
+        let mut workspace = Workspace::default();
+        let name = workspace.add_new_glyph();
+        let mut session = EditSession::new(&name, &workspace);
+        let paths = (0..n_paths).map(|i| {
+            let mut bez = BezPath::new();
+            for j in 0..n_points {
+                let angle = j as f64 / n_points as f64 * std::f64::consts::PI * 2.0;
+                let pt = Point::new(
+                    (i as f64 * 100.0 + angle.cos() * 40.0).round(),
+                    (angle.sin() * 40.0).round(),
+                );
+                if j == 0 {
+                    bez.move_to(pt);
+                } else {
+                    bez.line_to(pt);
+                }
+            }
+            bez.close_path();
+            Path::from_bezpath(bez).unwrap()
+        });
+        session.paste_paths(paths.collect());
+        session.selection.clear();
+        session.rebuild_glyph();
+        session
+    }
+
+    /// Select a single point and nudge it, the way the editor does.
+    fn nudge_one_point(session: &mut EditSession, i: usize) {
+        let path = &session.paths[i % session.paths.len()];
+        let point = path.points()[i % path.points().len()].id;
+        session.selection.select_one(point);
+        session.nudge_selection(DVec2::from_raw((1.0, 0.0)));
+        session.rebuild_glyph();
+    }
+
+    fn points_as_tuples(session: &EditSession) -> Vec<(EntityId, f64, f64)> {
+        session
+            .iter_points()
+            .map(|p| (p.id, p.point.x, p.point.y))
+            .collect()
+    }
+
+    #[test]
+    fn undo_redo_round_trip() {
+        let mut session = make_session(4, 20);
+        let mut undo = UndoState::new(session.clone());
+        let mut states = vec![points_as_tuples(&session)];
+
+        for i in 0..10 {
+            nudge_one_point(&mut session, i * 7);
+            undo.add_undo_group(session.clone(), "nudge");
+            states.push(points_as_tuples(&session));
+        }
+
+        // delete a path, which changes the order
+        session.selection.clear();
+        session
+            .selection
+            .extend(session.paths[1].points().iter().map(|p| p.id));
+        session.delete_selection();
+        undo.add_undo_group(session.clone(), "delete");
+        states.push(points_as_tuples(&session));
+
+        for expected in states.iter().rev().skip(1) {
+            let prev = undo.undo().unwrap();
+            assert_eq!(&points_as_tuples(prev), expected);
+        }
+        assert!(undo.undo().is_none());
+
+        for expected in states.iter().skip(1) {
+            let next = undo.redo().unwrap();
+            assert_eq!(&points_as_tuples(next), expected);
+        }
+        assert!(undo.redo().is_none());
+    }
+
+    #[test]
+    fn point_edits_are_compact() {
+        let mut session = make_session(10, 100);
+        let init = session.clone();
+        nudge_one_point(&mut session, 3);
+        let diff = init.diff(&session);
+        assert!(diff.components.is_none());
+        match &diff.paths.as_ref().unwrap().changes[..] {
+            [PathChange::Points { points, .. }] => assert_eq!(points.len(), 1),
+            other => panic!("unexpected changes {:?}", other),
+        }
+    }
+
+    #[test]
+    fn undo_restores_spline() {
+        let mut session = make_session(0, 0);
+        let spline = Spline::new(
+            vec![
+                SplinePoint::new(DPoint::new(0., 0.), SplinePointType::Corner),
+                SplinePoint::new(DPoint::new(50., 80.), SplinePointType::Curve),
+                SplinePoint::new(DPoint::new(100., 0.), SplinePointType::Corner),
+            ],
+            false,
+        );
+        session.paste_paths(vec![Path::from_spline(spline)]);
+        session.selection.clear();
+        let mut undo = UndoState::new(session.clone());
+
+        nudge_one_point(&mut session, 0);
+        assert!(session.paths[0].spline().is_none());
+        undo.add_undo_group(session.clone(), "nudge");
+
+        let prev = undo.undo().unwrap();
+        assert!(prev.paths[0].spline().is_some());
+        let next = undo.redo().unwrap();
+        assert!(next.paths[0].spline().is_none());
+    }
+
+    /// Diffs should use far less memory than storing a snapshot of the
+    /// session for each edit. How long each takes is measured by the
+    /// benchmarks below.
+    #[test]
+    fn diffs_are_smaller_than_snapshots() {
+        const N_EDITS: usize = 200;
+        let mut session = make_session(20, 100);
+
+        // the snapshot approach: one clone of the session per edit
+        let mut snapshots = vec![session.clone()];
+        for i in 0..N_EDITS {
+            nudge_one_point(&mut session, i * 31);
+            snapshots.push(session.clone());
+        }
+        let snapshot_bytes = snapshot_memory(&snapshots);
+
+        let mut session = make_session(20, 100);
+        let mut undo = UndoState::with_memory_budget(usize::MAX, session.clone());
+        for i in 0..N_EDITS {
+            nudge_one_point(&mut session, i * 31);
+            undo.add_undo_group(session.clone(), "nudge");
+        }
+        assert!(undo.memory_usage() * 10 < snapshot_bytes);
+    }
+
+    /// The memory uniquely retained by a set of snapshots, counting shared
+    /// allocations only once.
+    fn snapshot_memory(snapshots: &[EditSession]) -> usize {
+        use std::collections::HashSet;
+        let mut seen = HashSet::new();
+        let mut total = 0;
+        for session in snapshots {
+            total += size_of::<EditSession>();
+            if seen.insert(Arc::as_ptr(&session.paths) as usize) {
+                total += session.paths.len() * size_of::<Path>();
+            }
+            for path in session.paths.iter() {
+                if seen.insert(path.points().as_ptr() as usize) {
+                    total += path.points().len() * size_of::<PathPoint>();
+                }
+            }
+            if seen.insert(Arc::as_ptr(&session.glyph) as usize) {
+                let n_points: usize = session
+                    .glyph
+                    .outline
+                    .as_ref()
+                    .map(|o| o.contours.iter().map(|c| c.points.len()).sum())
+                    .unwrap_or(0);
+                total += n_points * size_of::<norad::glyph::ContourPoint>();
+            }
+        }
+        total
+    }
+
+    /// Benchmarks of editing and undo in a glyph with thousands of points.
+    ///
+    /// These need a nightly compiler: `cargo +nightly bench --features nightly`.
+    #[cfg(feature = "nightly")]
+    mod benches {
+        extern crate test;
+
+        use super::*;
+        use test::Bencher;
+
+        /// 40 contours of 100 points each.
+        fn large_session() -> EditSession {
+            make_session(40, 100)
+        }
+
+        /// An edit, keeping a snapshot of the session as the undo state.
+        #[bench]
+        fn edit_with_snapshot(b: &mut Bencher) {
+            let mut session = large_session();
+            let mut i = 0;
+            b.iter(|| {
+                nudge_one_point(&mut session, i * 31);
+                i += 1;
+                test::black_box(session.clone())
+            });
+        }
+
+        /// An edit, recording the diff from the previous state.
+        #[bench]
+        fn edit_with_diff(b: &mut Bencher) {
+            let mut session = large_session();
+            let mut undo = UndoState::new(session.clone());
+            let mut i = 0;
+            b.iter(|| {
+                nudge_one_point(&mut session, i * 31);
+                i += 1;
+                undo.add_undo_group(session.clone(), "nudge");
+            });
+        }
+
+        /// A drag, which updates the most recent group on every step.
+        #[bench]
+        fn drag_step(b: &mut Bencher) {
+            let mut session = large_session();
+            let mut undo = UndoState::new(session.clone());
+            nudge_one_point(&mut session, 0);
+            undo.add_undo_group(session.clone(), "drag");
+            b.iter(|| {
+                nudge_one_point(&mut session, 0);
+                let current = session.clone();
+                undo.update_current_undo(|state| *state = current.clone());
+            });
+        }
+
+        #[bench]
+        fn undo_redo(b: &mut Bencher) {
+            let mut session = large_session();
+            let mut undo = UndoState::new(session.clone());
+            for i in 0..100 {
+                nudge_one_point(&mut session, i * 31);
+                undo.add_undo_group(session.clone(), "nudge");
+            }
+            b.iter(|| {
+                undo.undo();
+                undo.redo();
+            });
+        }
+    }
+}
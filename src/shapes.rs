@@ -0,0 +1,251 @@
+//! Paths for the primitive shapes drawn by the shape tools: rectangles,
+//! ellipses, polygons and stars.
+//!
+//! All shapes are closed and run counter-clockwise, and their points are
+//! typed as corners, smooth or tangent points as appropriate.
+
+use std::f64::consts::PI;
+
+use druid::kurbo::{BezPath, PathEl, Point, Rect, Shape, Vec2};
+use druid::{Data, Lens};
+
+use crate::design_space::DPoint;
+use crate::path::Path;
+
+/// The cubic handle length, as a fraction of the radius, that best
+/// approximates a quarter circle.
+const ARC_HANDLE_RATIO: f64 = 0.552_284_749_8;
+
+/// The kinds of shape that can be placed with the shape tools.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub enum ShapeKind {
+    Rectangle,
+    Ellipse,
+    Polygon,
+}
+
+/// The options of the shape tools.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct ShapeSettings {
+    /// The radius of the corners of rectangles.
+    pub corner_radius: f64,
+    /// The number of sides of a polygon, or of points of a star.
+    pub sides: usize,
+    /// Whether the polygon tool draws stars.
+    pub star: bool,
+    /// The radius of the inner points of a star, as a fraction of the radius
+    /// of its outer points.
+    pub inner_radius: f64,
+    /// The size of a rectangle or ellipse placed by clicking.
+    pub width: f64,
+    pub height: f64,
+    /// The radius of a polygon placed by clicking.
+    pub radius: f64,
+}
+
+/// A shape to add at a point, sent from the dimensions dialog.
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeArgs {
+    pub kind: ShapeKind,
+    /// The top left corner of a rectangle or ellipse, or the center of a
+    /// polygon.
+    pub origin: DPoint,
+    pub settings: ShapeSettings,
+}
+
+impl Default for ShapeSettings {
+    fn default() -> Self {
+        ShapeSettings {
+            corner_radius: 0.0,
+            sides: 5,
+            star: false,
+            inner_radius: 0.5,
+            width: 100.0,
+            height: 100.0,
+            radius: 50.0,
+        }
+    }
+}
+
+impl ShapeArgs {
+    /// The path for this shape.
+    pub(crate) fn to_path(self) -> Option<Path> {
+        let origin = self.origin.to_raw();
+        let settings = self.settings;
+        // design space is y-up, so the shape extends downwards from the origin
+        let rect = Rect::new(
+            origin.x,
+            origin.y - settings.height,
+            origin.x + settings.width,
+            origin.y,
+        );
+        match self.kind {
+            ShapeKind::Rectangle => rect_path(rect, settings.corner_radius),
+            ShapeKind::Ellipse => ellipse_path(rect),
+            ShapeKind::Polygon => polygon_path(origin, Vec2::new(0.0, settings.radius), &settings),
+        }
+    }
+}
+
+/// A rectangle, with its corners rounded by `radius`.
+///
+/// The radius is limited to half of the shorter side.
+pub(crate) fn rect_path(rect: Rect, radius: f64) -> Option<Path> {
+    let rect = rect.abs();
+    if rect.area() == 0.0 {
+        return None;
+    }
+    let radius = radius.max(0.0).min(rect.width().min(rect.height()) / 2.0);
+    let mut bez = BezPath::new();
+    if radius == 0.0 {
+        bez.move_to((rect.x0, rect.y0));
+        bez.line_to((rect.x1, rect.y0));
+        bez.line_to((rect.x1, rect.y1));
+        bez.line_to((rect.x0, rect.y1));
+        bez.close_path();
+        return Path::from_bezpath(bez).ok();
+    }
+
+    // the center of each corner's arc, and the direction from it to the
+    // start of that arc, going counter-clockwise from the bottom right.
+    let corners = [
+        (
+            Point::new(rect.x1 - radius, rect.y0 + radius),
+            Vec2::new(0.0, -1.0),
+        ),
+        (
+            Point::new(rect.x1 - radius, rect.y1 - radius),
+            Vec2::new(1.0, 0.0),
+        ),
+        (
+            Point::new(rect.x0 + radius, rect.y1 - radius),
+            Vec2::new(0.0, 1.0),
+        ),
+        (
+            Point::new(rect.x0 + radius, rect.y0 + radius),
+            Vec2::new(-1.0, 0.0),
+        ),
+    ];
+    let first = corners[0].0 + corners[0].1 * radius;
+    bez.move_to(first);
+    let mut current = first;
+    for (center, dir) in corners.iter() {
+        let next_dir = Vec2::new(-dir.y, dir.x);
+        let start = *center + *dir * radius;
+        let end = *center + next_dir * radius;
+        // the sides vanish when the radius is half their length
+        if start.distance(current) > 1e-6 {
+            bez.line_to(start);
+        }
+        bez.curve_to(
+            start + next_dir * radius * ARC_HANDLE_RATIO,
+            end + *dir * radius * ARC_HANDLE_RATIO,
+            end,
+        );
+        current = end;
+    }
+    if current.distance(first) > 1e-6 {
+        bez.line_to(first);
+    }
+    bez.close_path();
+    Path::from_bezpath(bez).ok()
+}
+
+/// An ellipse filling `rect`.
+pub(crate) fn ellipse_path(rect: Rect) -> Option<Path> {
+    let rect = rect.abs();
+    if rect.area() == 0.0 {
+        return None;
+    }
+    let ellipse = rect.to_ellipse();
+    Path::from_bezpath(
+        ellipse
+            .path_elements(1.0)
+            .chain(std::iter::once(PathEl::ClosePath)),
+    )
+    .ok()
+}
+
+/// A regular polygon or star around `center`, with its first point at
+/// `center + first`.
+pub(crate) fn polygon_path(center: Point, first: Vec2, settings: &ShapeSettings) -> Option<Path> {
+    let radius = first.hypot();
+    if radius == 0.0 {
+        return None;
+    }
+    let sides = settings.sides.max(3);
+    let rotation = first.atan2();
+    let step = 2.0 * PI / sides as f64;
+    let inner = radius * settings.inner_radius.abs().min(1.0);
+
+    let mut bez = BezPath::new();
+    for i in 0..sides {
+        let angle = rotation + step * i as f64;
+        let pt = center + Vec2::from_angle(angle) * radius;
+        if i == 0 {
+            bez.move_to(pt);
+        } else {
+            bez.line_to(pt);
+        }
+        if settings.star {
+            bez.line_to(center + Vec2::from_angle(angle + step / 2.0) * inner);
+        }
+    }
+    bez.close_path();
+    Path::from_bezpath(bez).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::PointType;
+
+    #[test]
+    fn rounded_rect() {
+        let rect = Rect::new(0., 0., 200., 100.);
+        let path = rect_path(rect, 20.).unwrap();
+        assert_eq!(path.points().len(), 16);
+        assert!(!path.is_clockwise());
+        let n_tangent = path
+            .points()
+            .iter()
+            .filter(|pt| pt.typ == PointType::OnCurveTangent)
+            .count();
+        assert_eq!(n_tangent, 8);
+
+        // a radius of half the height makes the ends semicircles
+        let path = rect_path(rect, 80.).unwrap();
+        assert_eq!(path.points().len(), 14);
+        let n_smooth = path
+            .points()
+            .iter()
+            .filter(|pt| pt.typ == PointType::OnCurveSmooth)
+            .count();
+        assert_eq!(n_smooth, 2);
+        let bbox = path.bezier().bounding_box();
+        assert_eq!(bbox, rect);
+    }
+
+    #[test]
+    fn polygons_and_stars() {
+        let mut settings = ShapeSettings {
+            sides: 6,
+            ..Default::default()
+        };
+        let hexagon = polygon_path(Point::ZERO, Vec2::new(0., 100.), &settings).unwrap();
+        assert_eq!(hexagon.points().len(), 6);
+        assert!(!hexagon.is_clockwise());
+        assert!(hexagon
+            .points()
+            .iter()
+            .all(|pt| pt.typ == PointType::OnCurve));
+
+        settings.sides = 5;
+        settings.star = true;
+        let star = polygon_path(Point::ZERO, Vec2::new(0., 100.), &settings).unwrap();
+        assert_eq!(star.points().len(), 10);
+        assert_eq!(star.start_point().point, DPoint::new(0., 100.));
+        let inner = star.points()[0].point.to_raw().to_vec2().hypot();
+        assert!((inner - 50.).abs() < 1.0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Snapping points to the grid, to other points, to guides and to metrics
+//! while they are dragged.
+//!
+//! Snapping happens in design space. A point snaps to another point if it is
+//! close to it; otherwise each axis snaps separately, to the nearest vertical
+//! or horizontal line (a guide or a metric) or failing that to the grid.
+//! Angled guides are only used when neither axis is near a line.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use druid::kurbo::{Line, Point};
+use druid::{Data, Lens, Modifiers};
+
+/// How close, in screen units, a point must be to something to snap to it.
+pub const SNAP_DISTANCE: f64 = 6.0;
+
+/// Which things points snap to.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct SnapSettings {
+    pub grid: bool,
+    /// The distance between grid lines, in design units.
+    pub grid_spacing: f64,
+    /// Other on-curve points.
+    pub points: bool,
+    pub guides: bool,
+    /// The baseline, x-height, cap height, ascender and descender, and the
+    /// sidebearings.
+    pub metrics: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        SnapSettings {
+            grid: false,
+            grid_spacing: 1.0,
+            points: true,
+            guides: true,
+            metrics: true,
+        }
+    }
+}
+
+/// The snap settings, along with the font's vertical metrics; this is passed
+/// to tools so that they can snap without access to the font.
+#[derive(Debug, Clone, Default, PartialEq, Data)]
+pub struct Snapping {
+    pub settings: SnapSettings,
+    /// The heights of the horizontal metric lines.
+    #[data(same_fn = "PartialEq::eq")]
+    pub heights: Arc<Vec<f64>>,
+}
+
+/// Something a point snapped to, in design space, so that it can be
+/// highlighted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapTarget {
+    Point(Point),
+    /// A vertical line at this x.
+    Vertical(f64),
+    /// A horizontal line at this y.
+    Horizontal(f64),
+    /// An angled guide.
+    Line(Line),
+    /// An intersection in the grid.
+    Grid(Point),
+}
+
+/// The result of snapping a point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snap {
+    pub point: Point,
+    pub targets: Vec<SnapTarget>,
+}
+
+/// Everything a point can snap to, in design space.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SnapCandidates {
+    /// The grid spacing, if snapping to the grid.
+    pub grid: Option<f64>,
+    pub points: Vec<Point>,
+    /// The x positions of vertical lines.
+    pub verticals: Vec<f64>,
+    /// The y positions of horizontal lines.
+    pub horizontals: Vec<f64>,
+    /// Angled lines, which are treated as infinite.
+    pub lines: Vec<Line>,
+}
+
+impl Snapping {
+    pub fn new(settings: SnapSettings, heights: Vec<f64>) -> Self {
+        Snapping {
+            settings,
+            heights: Arc::new(heights),
+        }
+    }
+}
+
+/// Returns `true` if snapping is suspended while these modifiers are held.
+pub fn is_suspended(mods: Modifiers) -> bool {
+    mods.ctrl()
+}
+
+impl SnapCandidates {
+    /// Snap `point` to the nearest candidate within `tolerance`.
+    pub(crate) fn snap(&self, point: Point, tolerance: f64) -> Snap {
+        let nearest_point = self
+            .points
+            .iter()
+            .map(|pt| (*pt, pt.distance(point)))
+            .filter(|(_, dist)| *dist <= tolerance)
+            .min_by(|a, b| compare_distances(a.1, b.1));
+        if let Some((pt, _)) = nearest_point {
+            return Snap {
+                point: pt,
+                targets: vec![SnapTarget::Point(pt)],
+            };
+        }
+
+        let x_line = nearest_value(&self.verticals, point.x, tolerance);
+        let y_line = nearest_value(&self.horizontals, point.y, tolerance);
+        if x_line.is_none() && y_line.is_none() {
+            let nearest_line = self
+                .lines
+                .iter()
+                // a line with no length has no direction to snap along
+                .filter(|line| line.p0 != line.p1)
+                .map(|line| (*line, project(*line, point)))
+                .map(|(line, proj)| (line, proj, proj.distance(point)))
+                .filter(|(_, _, dist)| *dist <= tolerance)
+                .min_by(|a, b| compare_distances(a.2, b.2));
+            if let Some((line, proj, _)) = nearest_line {
+                return Snap {
+                    point: proj,
+                    targets: vec![SnapTarget::Line(line)],
+                };
+            }
+        }
+
+        let mut snapped = point;
+        let mut targets = Vec::new();
+        let mut on_grid = false;
+        match (x_line, self.grid) {
+            (Some(x), _) => {
+                snapped.x = x;
+                targets.push(SnapTarget::Vertical(x));
+            }
+            (None, Some(spacing)) => {
+                snapped.x = (point.x / spacing).round() * spacing;
+                on_grid = true;
+            }
+            (None, None) => (),
+        }
+        match (y_line, self.grid) {
+            (Some(y), _) => {
+                snapped.y = y;
+                targets.push(SnapTarget::Horizontal(y));
+            }
+            (None, Some(spacing)) => {
+                snapped.y = (point.y / spacing).round() * spacing;
+                on_grid = true;
+            }
+            (None, None) => (),
+        }
+        if on_grid {
+            targets.push(SnapTarget::Grid(snapped));
+        }
+        Snap {
+            point: snapped,
+            targets,
+        }
+    }
+}
+
+fn nearest_value(values: &[f64], val: f64, tolerance: f64) -> Option<f64> {
+    values
+        .iter()
+        .copied()
+        .filter(|v| (v - val).abs() <= tolerance)
+        .min_by(|a, b| compare_distances((a - val).abs(), (b - val).abs()))
+}
+
+/// Order two distances, treating NaN (which a degenerate candidate can
+/// produce) as equal to anything, rather than panicking.
+fn compare_distances(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// The nearest point to `point` on the infinite line through `line`, which
+/// must not have zero length.
+fn project(line: Line, point: Point) -> Point {
+    let dir = line.p1 - line.p0;
+    line.p0 + dir * ((point - line.p0).dot(dir) / dir.hypot2())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> SnapCandidates {
+        SnapCandidates {
+            grid: Some(10.0),
+            points: vec![Point::new(100.0, 100.0)],
+            verticals: vec![0.0, 500.0],
+            horizontals: vec![0.0, 480.0],
+            lines: vec![Line::new((0.0, 0.0), (10.0, 10.0))],
+        }
+    }
+
+    #[test]
+    fn points_win() {
+        let snap = candidates().snap(Point::new(103.0, 98.0), 5.0);
+        assert_eq!(snap.point, Point::new(100.0, 100.0));
+        assert_eq!(
+            snap.targets,
+            vec![SnapTarget::Point(Point::new(100.0, 100.0))]
+        );
+    }
+
+    #[test]
+    fn axes_snap_separately() {
+        let snap = candidates().snap(Point::new(497.0, 233.0), 5.0);
+        assert_eq!(snap.point, Point::new(500.0, 230.0));
+        assert_eq!(
+            snap.targets,
+            vec![
+                SnapTarget::Vertical(500.0),
+                SnapTarget::Grid(Point::new(500.0, 230.0))
+            ]
+        );
+
+        let mut no_grid = candidates();
+        no_grid.grid = None;
+        let snap = no_grid.snap(Point::new(333.0, 477.0), 5.0);
+        assert_eq!(snap.point, Point::new(333.0, 480.0));
+        assert_eq!(snap.targets, vec![SnapTarget::Horizontal(480.0)]);
+    }
+
+    #[test]
+    fn angled_guide() {
+        let mut cands = candidates();
+        cands.grid = None;
+        let snap = cands.snap(Point::new(203.0, 199.0), 5.0);
+        assert_eq!(snap.point, Point::new(201.0, 201.0));
+        assert_eq!(snap.targets.len(), 1);
+    }
+
+    #[test]
+    fn degenerate_candidates() {
+        let cands = SnapCandidates {
+            verticals: vec![f64::NAN, 300.0],
+            lines: vec![Line::new((10.0, 10.0), (10.0, 10.0))],
+            ..Default::default()
+        };
+        let snap = cands.snap(Point::new(12.0, 12.0), 5.0);
+        assert_eq!(snap.point, Point::new(12.0, 12.0));
+        assert!(snap.targets.is_empty());
+
+        let snap = cands.snap(Point::new(302.0, 12.0), 5.0);
+        assert_eq!(snap.targets, vec![SnapTarget::Vertical(300.0)]);
+    }
+}
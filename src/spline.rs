@@ -0,0 +1,775 @@
+//! Splines that pass through their points, for the spline tool.
+//!
+//! The user places only on-curve points, and gives each a type; the control
+//! points are then solved for, in the manner of Raph Levien's Spiro. Each
+//! curved segment is a cubic whose handle lengths are given by John Hobby's
+//! 'velocity' function of its tangent angles, and the unknown angles (and,
+//! where there is a spare degree of freedom, the handle lengths) are found
+//! with Newton's method, so that the curvature is continuous through the
+//! points.
+//!
+//! The spline itself is kept with the `Path` it generates, and is saved in
+//! the glyph's lib, so that it can be edited again later.
+
+use std::f64::consts::PI;
+
+use druid::kurbo::{BezPath, Point, Vec2};
+
+use crate::design_space::DPoint;
+
+/// The key in the glyph lib under which splines are stored.
+pub(crate) const LIB_KEY: &str = "org.linebender.runebender.splines";
+
+/// How many Newton iterations before we settle for what we have.
+const MAX_ITERATIONS: usize = 32;
+/// The solution is good enough when the residuals are this small.
+const TOLERANCE: f64 = 1e-9;
+/// The largest change to any angle, in radians, in one iteration.
+const MAX_STEP: f64 = 0.5;
+
+/// The type of a point on a spline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplinePointType {
+    /// The curve may change direction abruptly here. Curves either side of
+    /// a corner have no curvature at the corner, and a segment between two
+    /// corners is straight.
+    Corner,
+    /// The direction and the curvature are continuous.
+    Curve,
+    /// The direction, the curvature, and the rate of change of the
+    /// curvature are continuous.
+    G4,
+    /// The segment before this point is straight, and the curve after it
+    /// leaves in the same direction.
+    LeftTangent,
+    /// The segment after this point is straight, and the curve before it
+    /// arrives in the same direction.
+    RightTangent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplinePoint {
+    pub point: DPoint,
+    pub typ: SplinePointType,
+}
+
+/// A spline, as placed by the user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spline {
+    points: Vec<SplinePoint>,
+    closed: bool,
+}
+
+/// A quantity that is either known in advance or solved for.
+#[derive(Debug, Clone, Copy)]
+enum Var {
+    Fixed(f64),
+    Free(usize),
+}
+
+/// The unknowns at one end of a curved segment: the absolute angle of the
+/// tangent, and the log of a scale applied to the handle length.
+#[derive(Debug, Clone, Copy)]
+struct End {
+    angle: Var,
+    tension: Var,
+}
+
+/// A condition the solution must meet.
+#[derive(Debug, Clone, Copy)]
+enum Constraint {
+    /// No curvature at the start (`false`) or end (`true`) of a segment.
+    Flat(usize, bool),
+    /// The curvature is continuous where one segment meets the next.
+    Curvature(usize, usize),
+    /// The rate of change of the curvature is continuous.
+    CurvatureDeriv(usize, usize),
+}
+
+/// A segment between two adjacent points of a spline.
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Line,
+    Curve { start: End, end: End },
+}
+
+impl SplinePointType {
+    /// Returns the next type, for cycling through the types of a point.
+    pub fn next(self) -> SplinePointType {
+        match self {
+            SplinePointType::Curve => SplinePointType::G4,
+            SplinePointType::G4 => SplinePointType::LeftTangent,
+            SplinePointType::LeftTangent => SplinePointType::RightTangent,
+            SplinePointType::RightTangent => SplinePointType::Corner,
+            SplinePointType::Corner => SplinePointType::Curve,
+        }
+    }
+
+    pub(crate) fn to_str(self) -> &'static str {
+        match self {
+            SplinePointType::Corner => "corner",
+            SplinePointType::Curve => "curve",
+            SplinePointType::G4 => "g4",
+            SplinePointType::LeftTangent => "left",
+            SplinePointType::RightTangent => "right",
+        }
+    }
+
+    pub(crate) fn from_str(s: &str) -> Option<SplinePointType> {
+        match s {
+            "corner" => Some(SplinePointType::Corner),
+            "curve" => Some(SplinePointType::Curve),
+            "g4" => Some(SplinePointType::G4),
+            "left" => Some(SplinePointType::LeftTangent),
+            "right" => Some(SplinePointType::RightTangent),
+            _ => None,
+        }
+    }
+}
+
+impl SplinePoint {
+    pub fn new(point: DPoint, typ: SplinePointType) -> Self {
+        SplinePoint { point, typ }
+    }
+}
+
+impl Spline {
+    pub fn new(points: Vec<SplinePoint>, closed: bool) -> Self {
+        assert!(!points.is_empty(), "spline may not be empty");
+        Spline { points, closed }
+    }
+
+    pub fn points(&self) -> &[SplinePoint] {
+        &self.points
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Adds a point to the end of an open spline. Returns `false` if it is
+    /// in the same place as the last point.
+    pub fn push(&mut self, point: SplinePoint) -> bool {
+        if self.points.last().map(|pt| pt.point) == Some(point.point) {
+            return false;
+        }
+        self.points.push(point);
+        true
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Moves the point at `idx`. Returns `false` if this would put it in the
+    /// same place as one of its neighbours.
+    pub fn move_point(&mut self, idx: usize, point: DPoint) -> bool {
+        let len = self.points.len();
+        let prev = (idx + len - 1) % len;
+        let next = (idx + 1) % len;
+        let collides = (self.closed || idx > 0) && prev != idx && self.points[prev].point == point
+            || (self.closed || idx + 1 < len) && next != idx && self.points[next].point == point;
+        if collides {
+            return false;
+        }
+        self.points[idx].point = point;
+        true
+    }
+
+    pub fn set_point_type(&mut self, idx: usize, typ: SplinePointType) {
+        self.points[idx].typ = typ;
+    }
+
+    /// Solves for the control points, returning the spline as cubic béziers.
+    pub fn to_bezier(&self) -> BezPath {
+        let points: Vec<Point> = self.points.iter().map(|pt| pt.point.to_raw()).collect();
+        let mut bez = BezPath::new();
+        bez.move_to(points[0]);
+        if points.len() == 1 {
+            return bez;
+        }
+
+        let solver = Solver::new(self);
+        let x = solver.solve();
+        for (i, seg) in solver.segments.iter().enumerate() {
+            let (p0, p3) = solver.chord(i);
+            match seg {
+                Segment::Line => bez.line_to(p3),
+                Segment::Curve { start, end } => {
+                    let (p1, p2) = solver.handles(p0, p3, *start, *end, &x);
+                    bez.curve_to(p1, p2, p3);
+                }
+            }
+        }
+        if self.closed {
+            bez.close_path();
+        }
+        bez
+    }
+
+    fn to_plist(&self) -> plist::Value {
+        let points = self
+            .points
+            .iter()
+            .map(|pt| {
+                let mut dict = plist::Dictionary::new();
+                dict.insert("x".into(), pt.point.x.into());
+                dict.insert("y".into(), pt.point.y.into());
+                dict.insert("type".into(), pt.typ.to_str().into());
+                plist::Value::Dictionary(dict)
+            })
+            .collect();
+        let mut dict = plist::Dictionary::new();
+        dict.insert("closed".into(), self.closed.into());
+        dict.insert("points".into(), plist::Value::Array(points));
+        plist::Value::Dictionary(dict)
+    }
+
+    fn from_plist(value: &plist::Value) -> Option<Spline> {
+        let dict = value.as_dictionary()?;
+        let closed = dict.get("closed")?.as_boolean()?;
+        let points = dict
+            .get("points")?
+            .as_array()?
+            .iter()
+            .map(|pt| {
+                let pt = pt.as_dictionary()?;
+                let x = pt.get("x")?.as_real()?.round();
+                let y = pt.get("y")?.as_real()?.round();
+                let typ = SplinePointType::from_str(pt.get("type")?.as_string()?)?;
+                Some(SplinePoint::new(DPoint::new(x, y), typ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if points.is_empty() {
+            return None;
+        }
+        Some(Spline::new(points, closed))
+    }
+}
+
+/// Reads the splines stored in a glyph lib, as pairs of the index of the
+/// contour generated by a spline and the spline.
+pub(crate) fn splines_from_lib(lib: &plist::Dictionary) -> Vec<(usize, Spline)> {
+    let entries = match lib.get(LIB_KEY).and_then(plist::Value::as_array) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let dict = entry.as_dictionary()?;
+            let contour = dict.get("contour")?.as_signed_integer()?;
+            let spline = Spline::from_plist(dict.get("spline")?)?;
+            Some((contour as usize, spline))
+        })
+        .collect()
+}
+
+/// Stores splines in a glyph lib, replacing any that were there before.
+pub(crate) fn splines_to_lib<'a>(
+    lib: &mut plist::Dictionary,
+    splines: impl IntoIterator<Item = (usize, &'a Spline)>,
+) {
+    let entries: Vec<_> = splines
+        .into_iter()
+        .map(|(contour, spline)| {
+            let mut dict = plist::Dictionary::new();
+            dict.insert("contour".into(), (contour as i64).into());
+            dict.insert("spline".into(), spline.to_plist());
+            plist::Value::Dictionary(dict)
+        })
+        .collect();
+    if entries.is_empty() {
+        lib.remove(LIB_KEY);
+    } else {
+        lib.insert(LIB_KEY.into(), plist::Value::Array(entries));
+    }
+}
+
+/// The system of equations for one spline.
+struct Solver {
+    points: Vec<Point>,
+    segments: Vec<Segment>,
+    constraints: Vec<Constraint>,
+    /// Initial values for the unknowns.
+    guess: Vec<f64>,
+    /// A length used to make the residuals independent of the spline's size.
+    scale: f64,
+}
+
+impl Solver {
+    fn new(spline: &Spline) -> Solver {
+        let points: Vec<Point> = spline.points.iter().map(|pt| pt.point.to_raw()).collect();
+        let len = points.len();
+        let closed = spline.closed;
+        let n_segs = if closed { len } else { len - 1 };
+
+        // the ends of an open spline act as corners
+        let types: Vec<_> = spline
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, pt)| match pt.typ {
+                _ if !closed && (i == 0 || i == len - 1) => SplinePointType::Corner,
+                typ => typ,
+            })
+            .collect();
+
+        let is_straight: Vec<bool> = (0..n_segs)
+            .map(|i| {
+                let start = types[i];
+                let end = types[(i + 1) % len];
+                start == SplinePointType::RightTangent
+                    || end == SplinePointType::LeftTangent
+                    || (start == SplinePointType::Corner && end == SplinePointType::Corner)
+            })
+            .collect();
+        let chord_angles: Vec<f64> = (0..n_segs)
+            .map(|i| (points[(i + 1) % len] - points[i]).atan2())
+            .collect();
+
+        let mut solver = Solver {
+            scale: 0.0,
+            points,
+            segments: Vec::with_capacity(n_segs),
+            constraints: Vec::new(),
+            guess: Vec::new(),
+        };
+
+        // the ends at each point: of the segment arriving, and of the
+        // segment leaving.
+        let mut arriving: Vec<Option<End>> = vec![None; len];
+        let mut leaving: Vec<Option<End>> = vec![None; len];
+        for i in 0..len {
+            let seg_in = if closed || i > 0 {
+                Some((i + len - 1) % len)
+            } else {
+                None
+            };
+            let seg_out = if i < n_segs { Some(i) } else { None };
+            let curved_in = seg_in.filter(|&s| !is_straight[s]);
+            let curved_out = seg_out.filter(|&s| !is_straight[s]);
+            let straight_in = seg_in.filter(|&s| is_straight[s]);
+            let straight_out = seg_out.filter(|&s| is_straight[s]);
+            let is_corner = types[i] == SplinePointType::Corner;
+
+            match (curved_in, curved_out) {
+                (None, None) => (),
+                (Some(s_in), Some(s_out)) if !is_corner => {
+                    let guess =
+                        (solver.points[(i + 1) % len] - solver.points[(i + len - 1) % len]).atan2();
+                    let angle = solver.free(guess);
+                    let tension = if types[i] == SplinePointType::G4 {
+                        solver
+                            .constraints
+                            .push(Constraint::CurvatureDeriv(s_in, s_out));
+                        solver.free(0.0)
+                    } else {
+                        Var::Fixed(0.0)
+                    };
+                    solver.constraints.push(Constraint::Curvature(s_in, s_out));
+                    arriving[i] = Some(End { angle, tension });
+                    leaving[i] = Some(End { angle, tension });
+                }
+                (curved_in, curved_out) => {
+                    // a curve that meets a line continues in the line's
+                    // direction, unless this is a corner; either way, one
+                    // thing is free, and the curvature at this end is zero.
+                    let line_angle = match (straight_in, straight_out) {
+                        _ if is_corner => None,
+                        (Some(s), _) | (_, Some(s)) => Some(chord_angles[s]),
+                        (None, None) => None,
+                    };
+                    let mut make_end = |seg: usize, at_end: bool| {
+                        solver.constraints.push(Constraint::Flat(seg, at_end));
+                        match line_angle {
+                            Some(angle) => End {
+                                angle: Var::Fixed(angle),
+                                tension: solver.free(0.0),
+                            },
+                            None => End {
+                                angle: solver.free(chord_angles[seg]),
+                                tension: Var::Fixed(0.0),
+                            },
+                        }
+                    };
+                    arriving[i] = curved_in.map(|s| make_end(s, true));
+                    leaving[i] = curved_out.map(|s| make_end(s, false));
+                }
+            }
+        }
+
+        for (i, &straight) in is_straight.iter().enumerate() {
+            let seg = if straight {
+                Segment::Line
+            } else {
+                Segment::Curve {
+                    start: leaving[i].unwrap(),
+                    end: arriving[(i + 1) % len].unwrap(),
+                }
+            };
+            solver.segments.push(seg);
+        }
+
+        let total: f64 = (0..n_segs)
+            .map(|i| {
+                let (p0, p3) = solver.chord(i);
+                p0.distance(p3)
+            })
+            .sum();
+        solver.scale = total / n_segs as f64;
+        solver
+    }
+
+    fn free(&mut self, guess: f64) -> Var {
+        self.guess.push(guess);
+        Var::Free(self.guess.len() - 1)
+    }
+
+    fn chord(&self, seg: usize) -> (Point, Point) {
+        let len = self.points.len();
+        (self.points[seg], self.points[(seg + 1) % len])
+    }
+
+    /// The control points of a curved segment.
+    fn handles(&self, p0: Point, p3: Point, start: End, end: End, x: &[f64]) -> (Point, Point) {
+        let value = |var: Var| match var {
+            Var::Fixed(val) => val,
+            Var::Free(idx) => x[idx],
+        };
+        let chord = p3 - p0;
+        let chord_angle = chord.atan2();
+        let start_angle = value(start.angle);
+        let end_angle = value(end.angle);
+        let theta = normalize_angle(start_angle - chord_angle);
+        let phi = normalize_angle(chord_angle - end_angle);
+        let len = chord.hypot();
+        let start_len = len * velocity(theta, phi) * value(start.tension).exp();
+        let end_len = len * velocity(phi, theta) * value(end.tension).exp();
+        (
+            p0 + Vec2::from_angle(start_angle) * start_len,
+            p3 - Vec2::from_angle(end_angle) * end_len,
+        )
+    }
+
+    /// The control points of every segment, given values for the unknowns.
+    fn cubics(&self, x: &[f64]) -> Vec<[Point; 4]> {
+        self.segments
+            .iter()
+            .enumerate()
+            .map(|(i, seg)| {
+                let (p0, p3) = self.chord(i);
+                match seg {
+                    Segment::Line => [p0, p0.lerp(p3, 1.0 / 3.0), p0.lerp(p3, 2.0 / 3.0), p3],
+                    Segment::Curve { start, end } => {
+                        let (p1, p2) = self.handles(p0, p3, *start, *end, x);
+                        [p0, p1, p2, p3]
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn residuals(&self, x: &[f64]) -> Vec<f64> {
+        let cubics = self.cubics(x);
+        let scale = self.scale;
+        self.constraints
+            .iter()
+            .map(|constraint| match *constraint {
+                Constraint::Flat(seg, at_end) => curvature_at(&cubics[seg], at_end).0 * scale,
+                Constraint::Curvature(seg_in, seg_out) => {
+                    let k_in = curvature_at(&cubics[seg_in], true).0;
+                    let k_out = curvature_at(&cubics[seg_out], false).0;
+                    (k_in - k_out) * scale
+                }
+                Constraint::CurvatureDeriv(seg_in, seg_out) => {
+                    let dk_in = curvature_at(&cubics[seg_in], true).1;
+                    let dk_out = curvature_at(&cubics[seg_out], false).1;
+                    (dk_in - dk_out) * scale * scale
+                }
+            })
+            .collect()
+    }
+
+    /// Finds values for the unknowns with Newton's method. If it does not
+    /// converge, the best values found are returned.
+    fn solve(&self) -> Vec<f64> {
+        const H: f64 = 1e-7;
+        let mut x = self.guess.clone();
+        let n = x.len();
+        if n == 0 {
+            return x;
+        }
+        let mut residuals = self.residuals(&x);
+        let mut error = norm(&residuals);
+        for _ in 0..MAX_ITERATIONS {
+            if error < TOLERANCE {
+                break;
+            }
+            let mut jacobian = vec![vec![0.0; n]; n];
+            for j in 0..n {
+                let mut x_h = x.clone();
+                x_h[j] += H;
+                let r_h = self.residuals(&x_h);
+                for (row, (r, r_h)) in jacobian.iter_mut().zip(residuals.iter().zip(r_h)) {
+                    row[j] = (r_h - r) / H;
+                }
+            }
+            let rhs: Vec<f64> = residuals.iter().map(|r| -r).collect();
+            let mut step = match solve_linear(jacobian, rhs) {
+                Some(step) => step,
+                None => break,
+            };
+            let largest = step.iter().fold(0.0_f64, |acc, s| acc.max(s.abs()));
+            if largest > MAX_STEP {
+                step.iter_mut().for_each(|s| *s *= MAX_STEP / largest);
+            }
+
+            // backtrack until the step is an improvement
+            let mut improved = false;
+            for _ in 0..8 {
+                let candidate: Vec<f64> = x.iter().zip(&step).map(|(x, s)| x + s).collect();
+                let cand_residuals = self.residuals(&candidate);
+                let cand_error = norm(&cand_residuals);
+                if cand_error < error {
+                    x = candidate;
+                    residuals = cand_residuals;
+                    error = cand_error;
+                    improved = true;
+                    break;
+                }
+                step.iter_mut().for_each(|s| *s *= 0.5);
+            }
+            if !improved {
+                break;
+            }
+        }
+        x
+    }
+}
+
+/// Hobby's 'velocity': the length of a handle relative to the chord, given
+/// the angles between the chord and the tangents at this end and the other.
+fn velocity(theta: f64, phi: f64) -> f64 {
+    let (st, ct) = theta.sin_cos();
+    let (sf, cf) = phi.sin_cos();
+    let num = 2.0 + 2f64.sqrt() * (st - sf / 16.0) * (sf - st / 16.0) * (ct - cf);
+    let denom = 3.0 + 1.5 * (5f64.sqrt() - 1.0) * ct + 1.5 * (3.0 - 5f64.sqrt()) * cf;
+    if denom <= 0.0 {
+        return 4.0;
+    }
+    (num / denom).clamp(0.05, 4.0)
+}
+
+/// Wraps an angle into (-π, π].
+fn normalize_angle(angle: f64) -> f64 {
+    let angle = angle % (2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else if angle <= -PI {
+        angle + 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+/// The signed curvature of a cubic at its start or end, and the rate at
+/// which it changes with respect to arc length.
+fn curvature_at(cubic: &[Point; 4], at_end: bool) -> (f64, f64) {
+    let [p0, p1, p2, p3] = *cubic;
+    let (d1, d2) = if at_end {
+        (
+            (p3 - p2) * 3.0,
+            (p3.to_vec2() - 2.0 * p2.to_vec2() + p1.to_vec2()) * 6.0,
+        )
+    } else {
+        (
+            (p1 - p0) * 3.0,
+            (p2.to_vec2() - 2.0 * p1.to_vec2() + p0.to_vec2()) * 6.0,
+        )
+    };
+    let d3 = (p3.to_vec2() - 3.0 * p2.to_vec2() + 3.0 * p1.to_vec2() - p0.to_vec2()) * 6.0;
+    let speed = d1.hypot();
+    if speed < 1e-12 {
+        return (0.0, 0.0);
+    }
+    let cross = d1.cross(d2);
+    let k = cross / speed.powi(3);
+    let dk_dt = d1.cross(d3) / speed.powi(3) - 3.0 * cross * d1.dot(d2) / speed.powi(5);
+    (k, dk_dt / speed)
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+/// Solves `a·x = b` by Gaussian elimination with partial pivoting.
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap())?;
+        if a[pivot][col].abs() < 1e-14 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            if factor == 0.0 {
+                continue;
+            }
+            let (above, below) = a.split_at_mut(row);
+            for (dst, src) in below[0][col..].iter_mut().zip(&above[col][col..]) {
+                *dst -= factor * src;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::kurbo::{ParamCurve, PathEl, PathSeg};
+
+    fn spline(points: &[(f64, f64, SplinePointType)], closed: bool) -> Spline {
+        let points = points
+            .iter()
+            .map(|&(x, y, typ)| SplinePoint::new(DPoint::new(x, y), typ))
+            .collect();
+        Spline::new(points, closed)
+    }
+
+    #[test]
+    fn four_curve_points_make_a_circle() {
+        use SplinePointType::Curve;
+        let circle = spline(
+            &[
+                (100.0, 0.0, Curve),
+                (0.0, 100.0, Curve),
+                (-100.0, 0.0, Curve),
+                (0.0, -100.0, Curve),
+            ],
+            true,
+        );
+        let bez = circle.to_bezier();
+        let mut n_curves = 0;
+        for seg in bez.segments() {
+            assert!(matches!(seg, PathSeg::Cubic(_)));
+            n_curves += 1;
+            for i in 0..=8 {
+                let radius = seg.eval(i as f64 / 8.0).to_vec2().hypot();
+                assert!((radius - 100.0).abs() < 0.1, "{}", radius);
+            }
+        }
+        assert_eq!(n_curves, 4);
+    }
+
+    #[test]
+    fn open_ends_are_flat() {
+        use SplinePointType::Curve;
+        let arch = spline(
+            &[
+                (0.0, 0.0, Curve),
+                (100.0, 150.0, Curve),
+                (250.0, 120.0, Curve),
+                (300.0, 0.0, Curve),
+            ],
+            false,
+        );
+        let solver = Solver::new(&arch);
+        let cubics = solver.cubics(&solver.solve());
+        assert_eq!(cubics.len(), 3);
+        assert!(curvature_at(&cubics[0], false).0.abs() < 1e-6);
+        assert!(curvature_at(&cubics[2], true).0.abs() < 1e-6);
+        for pair in cubics.windows(2) {
+            let k_in = curvature_at(&pair[0], true).0;
+            let k_out = curvature_at(&pair[1], false).0;
+            assert!((k_in - k_out).abs() < 1e-6, "{} {}", k_in, k_out);
+        }
+    }
+
+    #[test]
+    fn corners_make_lines() {
+        use SplinePointType::Corner;
+        let square = spline(
+            &[
+                (0.0, 0.0, Corner),
+                (100.0, 0.0, Corner),
+                (100.0, 100.0, Corner),
+                (0.0, 100.0, Corner),
+            ],
+            true,
+        );
+        let bez = square.to_bezier();
+        assert!(bez
+            .elements()
+            .iter()
+            .all(|el| !matches!(el, PathEl::CurveTo(..))));
+    }
+
+    #[test]
+    fn tangent_point_continues_line() {
+        use SplinePointType::*;
+        let hook = spline(
+            &[
+                (0.0, 0.0, Corner),
+                (0.0, 200.0, LeftTangent),
+                (100.0, 300.0, Curve),
+                (200.0, 200.0, Corner),
+            ],
+            false,
+        );
+        let bez = hook.to_bezier();
+        let els = bez.elements();
+        assert_eq!(els[1], PathEl::LineTo((0.0, 200.0).into()));
+        match els[2] {
+            // the curve leaves straight up, as the line arrives
+            PathEl::CurveTo(p1, _, _) => {
+                assert!(p1.x.abs() < 1e-6 && p1.y > 200.0, "{:?}", p1);
+            }
+            _ => panic!("expected a curve"),
+        }
+    }
+
+    #[test]
+    fn g4_is_smoother_than_g2() {
+        use SplinePointType::*;
+        let points = |typ| {
+            spline(
+                &[
+                    (0.0, 0.0, Corner),
+                    (100.0, 80.0, Curve),
+                    (200.0, 100.0, typ),
+                    (300.0, 60.0, Curve),
+                    (400.0, -40.0, Corner),
+                ],
+                false,
+            )
+        };
+        let solver = Solver::new(&points(G4));
+        let cubics = solver.cubics(&solver.solve());
+        let dk_in = curvature_at(&cubics[1], true).1;
+        let dk_out = curvature_at(&cubics[2], false).1;
+        assert!((dk_in - dk_out).abs() < 1e-9, "{} {}", dk_in, dk_out);
+    }
+
+    #[test]
+    fn round_trip_lib() {
+        use SplinePointType::*;
+        let one = spline(&[(0.0, 0.0, Corner), (10.0, 20.0, G4)], false);
+        let two = spline(&[(5.0, 5.0, LeftTangent), (10.0, 20.0, RightTangent)], true);
+        let mut lib = plist::Dictionary::new();
+        splines_to_lib(&mut lib, vec![(0, &one), (3, &two)]);
+        assert_eq!(splines_from_lib(&lib), vec![(0, one), (3, two)]);
+        splines_to_lib(&mut lib, vec![]);
+        assert!(lib.get(LIB_KEY).is_none());
+    }
+}
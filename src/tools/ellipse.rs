@@ -2,13 +2,13 @@
 
 // this share a lot of code with the rectangle tool :shrug:
 
-use druid::kurbo::{PathEl, Shape};
-use druid::{Color, Env, EventCtx, KbKey, KeyEvent, PaintCtx, Rect, RenderContext};
+use druid::{Color, Env, EventCtx, KbKey, KeyEvent, MouseEvent, PaintCtx, Rect, RenderContext};
 
+use crate::consts;
 use crate::design_space::DPoint;
 use crate::edit_session::EditSession;
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
-use crate::path::Path;
+use crate::shapes::{self, ShapeKind};
 use crate::tools::{EditType, Tool};
 
 /// The state of the ellipse tool.
@@ -16,41 +16,36 @@ use crate::tools::{EditType, Tool};
 pub struct Ellipse {
     gesture: GestureState,
     shift_locked: bool,
+    from_center: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum GestureState {
     Ready,
-    Begun { start: DPoint, current: DPoint },
+    Down(DPoint),
+    Begun {
+        start: DPoint,
+        current: DPoint,
+    },
+    /// The canvas was clicked without dragging.
+    Clicked(DPoint),
     Finished,
 }
 
 impl Ellipse {
-    fn pts_for_rect(&self) -> Option<(DPoint, DPoint)> {
+    /// The rectangle enclosing the current ellipse, in design space.
+    fn current_rect(&self) -> Option<Rect> {
         if let GestureState::Begun { start, current } = self.gesture {
-            let mut current = current;
-            if self.shift_locked {
-                let mut vec2 = current - start;
-                vec2.y = if vec2.y.signum() > 0.0 {
-                    vec2.x.abs()
-                } else {
-                    vec2.x.abs() * -1.0
-                };
-                current = start + vec2;
-            }
-            Some((start, current))
+            Some(super::drag_rect(
+                start,
+                current,
+                self.shift_locked,
+                self.from_center,
+            ))
         } else {
             None
         }
     }
-
-    fn current_drag_rect(&self, data: &EditSession) -> Option<Rect> {
-        let (start, current) = self.pts_for_rect()?;
-        Some(Rect::from_points(
-            data.viewport.to_screen(start),
-            data.viewport.to_screen(current),
-        ))
-    }
 }
 
 impl Tool for Ellipse {
@@ -58,6 +53,10 @@ impl Tool for Ellipse {
         "Ellipse"
     }
 
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Draw ellipse".into()
+    }
+
     fn key_down(
         &mut self,
         key: &KeyEvent,
@@ -68,6 +67,9 @@ impl Tool for Ellipse {
         if key.key == KbKey::Shift {
             self.shift_locked = true;
             ctx.request_paint();
+        } else if key.key == KbKey::Alt {
+            self.from_center = true;
+            ctx.request_paint();
         }
         None
     }
@@ -82,6 +84,9 @@ impl Tool for Ellipse {
         if key.key == KbKey::Shift {
             self.shift_locked = false;
             ctx.request_paint();
+        } else if key.key == KbKey::Alt {
+            self.from_center = false;
+            ctx.request_paint();
         }
         None
     }
@@ -100,17 +105,25 @@ impl Tool for Ellipse {
             ctx.request_paint();
         }
 
-        if self.gesture == GestureState::Finished {
-            self.gesture = GestureState::Ready;
-            Some(EditType::Normal)
-        } else {
-            None
+        match self.gesture {
+            GestureState::Finished => {
+                self.gesture = GestureState::Ready;
+                Some(EditType::Normal)
+            }
+            GestureState::Clicked(origin) => {
+                self.gesture = GestureState::Ready;
+                ctx.submit_command(
+                    consts::cmd::SHOW_SHAPE_PANEL.with((ShapeKind::Ellipse, origin)),
+                );
+                None
+            }
+            _ => None,
         }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, _env: &Env) {
-        if let Some(rect) = self.current_drag_rect(data) {
-            let ellipse = rect.to_ellipse();
+        if let Some(rect) = self.current_rect() {
+            let ellipse = data.viewport.rect_to_screen(rect).to_ellipse();
             ctx.stroke(ellipse, &Color::grey(0.7), 1.0);
         }
     }
@@ -121,18 +134,27 @@ impl MouseDelegate<EditSession> for Ellipse {
         self.gesture = GestureState::Ready;
     }
 
+    fn left_down(&mut self, event: &MouseEvent, data: &mut EditSession) {
+        if event.count == 1 {
+            self.gesture = GestureState::Down(data.viewport.from_screen(event.pos));
+        }
+    }
+
+    fn left_click(&mut self, _event: &MouseEvent, _data: &mut EditSession) {
+        if let GestureState::Down(pt) = self.gesture {
+            self.gesture = GestureState::Clicked(pt);
+        }
+    }
+
     fn left_drag_ended(&mut self, _drag: Drag, data: &mut EditSession) {
-        if let Some((start, current)) = self.pts_for_rect() {
-            let rect = Rect::from_points(start.to_raw(), current.to_raw());
-            let ellipse = rect.to_ellipse();
-            if let Ok(path) = Path::from_bezpath(
-                ellipse
-                    .path_elements(1.0)
-                    .chain(std::iter::once(PathEl::ClosePath)),
-            ) {
-                data.paste_paths(vec![path]);
-            }
-            self.gesture = GestureState::Finished;
+        if let Some(rect) = self.current_rect() {
+            self.gesture = match shapes::ellipse_path(rect) {
+                Some(path) => {
+                    data.paste_paths(vec![path]);
+                    GestureState::Finished
+                }
+                None => GestureState::Ready,
+            };
         }
     }
 
@@ -141,6 +163,7 @@ impl MouseDelegate<EditSession> for Ellipse {
         let current = data.viewport.from_screen(event.current.pos);
         self.gesture = GestureState::Begun { start, current };
         self.shift_locked = event.current.mods.shift();
+        self.from_center = event.current.mods.alt();
     }
 
     fn left_drag_changed(&mut self, drag: Drag, data: &mut EditSession) {
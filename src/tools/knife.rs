@@ -1,17 +1,52 @@
 //! The knife tool
 
-use druid::kurbo::{Line, LineIntersection, ParamCurve, ParamCurveArclen};
+use druid::kurbo::{
+    BezPath, Line, LineIntersection, ParamCurve, ParamCurveArclen, Rect, Shape, Vec2,
+};
 use druid::piet::StrokeStyle;
-use druid::{Color, Env, EventCtx, KbKey, KeyEvent, MouseEvent, PaintCtx, Point, RenderContext};
+use druid::{
+    Color, Data, Env, EventCtx, KbKey, KeyEvent, Lens, MouseEvent, PaintCtx, Point, RenderContext,
+};
 
+use crate::boolean::{self, BooleanOp};
 use crate::design_space::DPoint;
 use crate::edit_session::EditSession;
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
 use crate::path::{Path, PathPoint, PathSeg};
-use crate::tools::{EditType, Tool};
+use crate::tools::{EditType, Tool, ToolSettings};
 
 const MAX_RECURSE: usize = 16;
 
+/// What the knife does to the paths it crosses.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub enum KnifeMode {
+    /// Cut each path where the line crosses it, joining the cut ends with
+    /// straight segments.
+    Slice,
+    /// Cut the filled shapes in two, as with a stencil, so that the pieces
+    /// on each side are closed; counters are cut along with their outlines.
+    Separate,
+    /// Like `Separate`, but remove a strip along the line.
+    Gap,
+}
+
+/// The knife's options.
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct KnifeSettings {
+    pub mode: KnifeMode,
+    /// The width of the strip removed in `Gap` mode, in design units.
+    pub gap: f64,
+}
+
+impl Default for KnifeSettings {
+    fn default() -> Self {
+        KnifeSettings {
+            mode: KnifeMode::Slice,
+            gap: 10.0,
+        }
+    }
+}
+
 /// The state of the rectangle tool.
 #[derive(Debug, Clone)]
 pub struct Knife {
@@ -21,6 +56,7 @@ pub struct Knife {
     /// during a drag, the places where we intersect a path; we just hold
     /// on to this so we don't always need to reallocate.
     intersections: Vec<DPoint>,
+    settings: KnifeSettings,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -50,6 +86,7 @@ impl Default for Knife {
             shift_locked: false,
             stroke_style,
             intersections: Vec::new(),
+            settings: KnifeSettings::default(),
         }
     }
 }
@@ -99,6 +136,7 @@ impl Knife {
         let iter = data
             .paths
             .iter()
+            .filter(|path| path.is_editable())
             .flat_map(Path::iter_segments)
             .flat_map(|seg| {
                 seg.to_kurbo()
@@ -115,6 +153,14 @@ impl Tool for Knife {
         "Knife"
     }
 
+    fn set_settings(&mut self, settings: &ToolSettings) {
+        self.settings = settings.knife;
+    }
+
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Knife cut".into()
+    }
+
     fn key_down(
         &mut self,
         key: &KeyEvent,
@@ -177,6 +223,16 @@ impl Tool for Knife {
 
             ctx.stroke_styled(line, &Color::BLACK, 1.0, &self.stroke_style);
 
+            // show the edges of the strip that will be removed
+            if self.settings.mode == KnifeMode::Gap {
+                let perp = Vec2::new(-unit_vec.y, unit_vec.x);
+                let offset = perp * (self.settings.gap * data.viewport.zoom / 2.0);
+                for edge_offset in &[offset, -offset] {
+                    let edge = Line::new(line.p0 + *edge_offset, line.p1 + *edge_offset);
+                    ctx.stroke_styled(edge, &Color::grey(0.5), 1.0, &self.stroke_style);
+                }
+            }
+
             for point in &self.intersections {
                 let point = data.viewport.to_screen(*point);
                 let cut_mark_start = point - (unit_vec * 4.0);
@@ -223,7 +279,12 @@ impl MouseDelegate<EditSession> for Knife {
 
         if let Some(line) = self.current_line_in_dspace() {
             if !self.intersections.is_empty() {
-                let new_paths = slice_paths(&data.paths, line);
+                let settings = self.settings;
+                let new_paths = cut_editable(&data.paths, |paths| match settings.mode {
+                    KnifeMode::Slice => slice_paths(paths, line),
+                    KnifeMode::Separate => cut_shapes(paths, line, 0.0),
+                    KnifeMode::Gap => cut_shapes(paths, line, settings.gap),
+                });
                 data.paths = new_paths.into();
             }
         }
@@ -254,6 +315,24 @@ impl Hit {
     }
 }
 
+/// Apply `cut` to those of `paths` that are neither locked nor hidden.
+///
+/// The other paths are kept in place, and the cut paths are put where the
+/// first of the paths passed to `cut` was.
+fn cut_editable(paths: &[Path], cut: impl FnOnce(&[Path]) -> Vec<Path>) -> Vec<Path> {
+    let editable: Vec<Path> = paths.iter().filter(|p| p.is_editable()).cloned().collect();
+    let mut cut_paths = Some(cut(&editable));
+    let mut out = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !path.is_editable() {
+            out.push(path.clone());
+        } else if let Some(cut_paths) = cut_paths.take() {
+            out.extend(cut_paths);
+        }
+    }
+    out
+}
+
 /// What the knife tool does.
 ///
 /// Checks for intersection with all paths, modifying old and adding
@@ -278,6 +357,79 @@ fn slice_paths(paths: &[Path], line: Line) -> Vec<Path> {
     out
 }
 
+/// Cut the filled shapes crossed by `line` into closed pieces on either side
+/// of it, leaving a strip `gap` units wide between them.
+///
+/// The line is extended in both directions, so that every shape it crosses
+/// is cut all the way through. Closed paths nested inside a crossed path,
+/// such as its counters, are treated as part of the same shape. Other paths
+/// are returned unchanged, even if the line passes close to them.
+fn cut_shapes(paths: &[Path], line: Line, gap: f64) -> Vec<Path> {
+    let crosses = |path: &Path| {
+        path.is_closed()
+            && path
+                .iter_segments()
+                .any(|seg| !seg.to_kurbo().intersect_line(line).is_empty())
+    };
+    let crossed: Vec<bool> = paths.iter().map(crosses).collect();
+    let bounds: Vec<Rect> = paths.iter().map(|p| p.bezier().bounding_box()).collect();
+    let in_shape = |ix: usize| {
+        crossed[ix]
+            || (paths[ix].is_closed()
+                && (0..paths.len())
+                    .any(|other| crossed[other] && paths[other].encloses(&paths[ix])))
+    };
+
+    let mut out = Vec::new();
+    let mut shape = Vec::new();
+    let mut shape_bounds: Option<Rect> = None;
+    for (ix, path) in paths.iter().enumerate() {
+        if in_shape(ix) {
+            shape.push(path.clone());
+            shape_bounds = Some(shape_bounds.map_or(bounds[ix], |r| r.union(bounds[ix])));
+        } else {
+            out.push(path.clone());
+        }
+    }
+    let shape_bounds = match shape_bounds {
+        Some(rect) => rect.inflate(gap + 1.0, gap + 1.0),
+        None => return out,
+    };
+
+    for side in &[1.0, -1.0] {
+        if let Some(half) = half_plane(line, shape_bounds, *side, gap / 2.0) {
+            out.extend(boolean::boolean_op(&shape, &[half], BooleanOp::Intersect));
+        }
+    }
+    out
+}
+
+/// A rectangle covering the part of `bounds` on one side of the (infinite)
+/// line through `line`, starting `offset` units away from it.
+///
+/// `side` is `1.0` for the left of the line, and `-1.0` for the right.
+fn half_plane(line: Line, bounds: Rect, side: f64, offset: f64) -> Option<Path> {
+    let dir = line.p1 - line.p0;
+    if dir.hypot() == 0.0 {
+        return None;
+    }
+    let dir = dir.normalize();
+    let normal = Vec2::new(-dir.y, dir.x) * side;
+    // more than the diagonal of `bounds`, so the rectangle covers it
+    let extent = bounds.width() + bounds.height();
+    let center = line.p0 + dir * (bounds.center() - line.p0).dot(dir);
+    let near = center + normal * offset;
+    let far = center + normal * extent;
+
+    let mut bez = BezPath::new();
+    bez.move_to(near - dir * extent);
+    bez.line_to(near + dir * extent);
+    bez.line_to(far + dir * extent);
+    bez.line_to(far - dir * extent);
+    bez.close_path();
+    Path::from_bezpath(bez).ok()
+}
+
 /// Slice a path with a line.
 ///
 /// Resulting paths are pushed to the `acc` vec.
@@ -620,4 +772,81 @@ mod tests {
         assert!(path2.is_closed());
         assert_eq!(path2.points().len(), 5);
     }
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64, clockwise: bool) -> Path {
+        let mut bez = BezPath::new();
+        bez.move_to((x0, y0));
+        if clockwise {
+            bez.line_to((x0, y1));
+            bez.line_to((x1, y1));
+            bez.line_to((x1, y0));
+        } else {
+            bez.line_to((x1, y0));
+            bez.line_to((x1, y1));
+            bez.line_to((x0, y1));
+        }
+        bez.close_path();
+        Path::from_bezpath(bez).unwrap()
+    }
+
+    fn area(path: &Path) -> f64 {
+        path.bezier().area().abs()
+    }
+
+    #[test]
+    fn cut_compound_shape() {
+        // a square 'O', with a counter, and an unrelated square off to the side.
+        let paths = vec![
+            rect(0., 0., 100., 100., false),
+            rect(25., 25., 75., 75., true),
+            rect(200., 0., 250., 50., false),
+        ];
+        let line = Line::new((50., -10.), (50., 110.));
+
+        let separated = cut_shapes(&paths, line, 0.0);
+        assert_eq!(separated.len(), 3);
+        assert_equal_points!(separated[0], paths[2]);
+        assert!((area(&separated[1]) - 3750.).abs() < 1e-6);
+        assert!((area(&separated[2]) - 3750.).abs() < 1e-6);
+
+        let gapped = cut_shapes(&paths, line, 10.0);
+        assert_eq!(gapped.len(), 3);
+        assert!((area(&gapped[1]) - 3500.).abs() < 1e-6);
+        assert!((area(&gapped[2]) - 3500.).abs() < 1e-6);
+        assert!(gapped[1..].iter().all(Path::is_closed));
+    }
+
+    #[test]
+    fn nearby_contours_are_not_cut() {
+        // a slash with a ring tucked into the corner of its bounds, like half a '%'.
+        let mut bez = BezPath::new();
+        bez.move_to((0., 0.));
+        bez.line_to((20., 0.));
+        bez.line_to((100., 100.));
+        bez.line_to((80., 100.));
+        bez.close_path();
+        let slash = Path::from_bezpath(bez).unwrap();
+        let paths = vec![slash, rect(70., 0., 100., 30., false)];
+        let line = Line::new((30., 50.), (70., 50.));
+
+        let cut = cut_shapes(&paths, line, 0.0);
+        assert_eq!(cut.len(), 3);
+        assert_eq!(cut[0].id(), paths[1].id());
+        assert_equal_points!(cut[0], paths[1]);
+        assert!((area(&cut[1]) + area(&cut[2]) - area(&paths[0])).abs() < 1e-6);
+    }
+
+    #[test]
+    fn locked_paths_are_not_cut() {
+        let mut locked = rect(0., 0., 100., 100., false);
+        locked.set_locked(true);
+        let paths = vec![locked, rect(200., 0., 300., 100., false)];
+        let line = Line::new((-10., 50.), (310., 50.));
+
+        let cut = cut_editable(&paths, |paths| slice_paths(paths, line));
+        assert_eq!(cut.len(), 3);
+        assert_eq!(cut[0].id(), paths[0].id());
+        assert_equal_points!(cut[0], paths[0]);
+        assert!(cut[1..].iter().all(|path| path.id() != paths[0].id()));
+    }
 }
@@ -1,9 +1,11 @@
-use druid::kurbo::{Circle, Line, ParamCurve, Point, Rect, Size, Vec2};
-use druid::piet::{Color, FontFamily, RenderContext, Text, TextLayout, TextLayoutBuilder};
-use druid::{Data, Env, EventCtx, PaintCtx};
+use druid::kurbo::{Circle, Line, ParamCurve, Point, Vec2};
+use druid::piet::{Color, FontFamily, RenderContext, Text, TextLayoutBuilder};
+use druid::{Data, Env, EventCtx, KbKey, KeyEvent, PaintCtx};
 
 use crate::design_space::DPoint;
+use crate::draw::draw_info_bubble;
 use crate::edit_session::EditSession;
+use crate::measurement::{self, Measurement};
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
 use crate::tools::{EditType, Tool};
 
@@ -13,32 +15,12 @@ pub struct Measure {
 }
 
 const MEASURE_LINE_STROKE_COLOR: Color = Color::rgb8(0x73, 0x9B, 0xCB);
-const MEASURE_INFO_BG_COLOR: Color = Color::rgb8(0x70, 0x70, 0x70);
-const MEASURE_INFO_FG_COLOR: Color = Color::rgb8(0xf8, 0xf8, 0xf8);
 const MEASURE_INFO_ONCURVE_COLOR: Color = Color::rgb8(0x80, 0x80, 0xe0);
 const MEASURE_INFO_OFFCURVE_COLOR: Color = Color::rgb8(0x60, 0xc0, 0x60);
 const MEASURE_INFO_DELTA_COLOR: Color = Color::rgb8(0xa0, 0x20, 0x20);
 const MEASURE_INFO_FONT_SIZE: f64 = 9.0;
 const MEASURE_INTERSECTION_RADIUS: f64 = 3.0;
 
-// Don't report segments smaller than this.
-const MEASURE_FUZZY_TOLERANCE: f64 = 0.1;
-
-fn draw_info_bubble(ctx: &mut PaintCtx, pos: Point, label: impl Into<String>) {
-    let text = ctx.text();
-    let layout = text
-        .new_text_layout(label.into())
-        .font(FontFamily::SYSTEM_UI, MEASURE_INFO_FONT_SIZE)
-        .text_color(MEASURE_INFO_FG_COLOR)
-        .build()
-        .unwrap();
-    let width = layout.size().width;
-    let bubble = Rect::from_center_size(pos, Size::new(width + 6.0, 12.0)).to_rounded_rect(6.0);
-    let origin = pos - Vec2::new(0.5 * width, 6.5);
-    ctx.fill(bubble, &MEASURE_INFO_BG_COLOR);
-    ctx.draw_text(&layout, origin);
-}
-
 fn atan_to_angle(atan: f64) -> f64 {
     if !atan.is_finite() {
         return 0.0;
@@ -68,45 +50,12 @@ fn format_pt(pt: DPoint) -> String {
 }
 
 impl Measure {
-    #[allow(clippy::float_cmp)]
-    fn compute_measurement(&self, data: &EditSession, design_line: Line) -> Vec<f64> {
-        // We scale the intersections to fixed point to make them easier to sort.
-        const T_SCALE: f64 = (1u64 << 63) as f64;
-        let mut intersections = vec![0, T_SCALE as u64];
-        for path in &*data.paths {
-            for seg in path.iter_segments() {
-                let kurbo_seg = seg.to_kurbo();
-                for intersection in kurbo_seg.intersect_line(design_line) {
-                    let t_fixed = (intersection.line_t.max(0.0).min(1.0) * T_SCALE) as u64;
-                    intersections.push(t_fixed);
-                }
-            }
-        }
-        intersections.sort_unstable();
-
-        // Fuzzy deduplication
-        let thresh = MEASURE_FUZZY_TOLERANCE / (design_line.p1 - design_line.p0).hypot();
-        let mut result = Vec::with_capacity(intersections.len());
-        let mut t_cluster_start = -1.0;
-        let mut t_last = -1.0;
-        for t_fixed in intersections {
-            let t = t_fixed as f64 / T_SCALE;
-            if t - t_last > thresh {
-                t_cluster_start = t;
-                result.push(t);
-            } else {
-                let cluster_t = if t_cluster_start == 0.0 {
-                    0.0
-                } else if t == 1.0 {
-                    1.0
-                } else {
-                    0.5 * (t_cluster_start + t)
-                };
-                *result.last_mut().unwrap() = cluster_t;
-            }
-            t_last = t;
-        }
-        result
+    /// The current line, in design space.
+    fn design_line(&self, data: &EditSession) -> Option<Line> {
+        let line = self.line?;
+        let p0 = data.viewport.from_screen(line.p0);
+        let p1 = data.viewport.from_screen(line.p1);
+        Some(Line::new(p0.to_raw(), p1.to_raw()))
     }
 
     // This is split out in case we want to sometimes hide the coords.
@@ -155,12 +104,9 @@ impl Tool for Measure {
             let label = format!("{:.1}°", angle);
             draw_info_bubble(ctx, line.p1 + angle_offset, label);
             // TODO: compute earlier than paint
-            if let Some(line) = self.line {
-                let p0 = data.viewport.from_screen(line.p0);
-                let p1 = data.viewport.from_screen(line.p1);
-                let design_line = Line::new(p0.to_raw(), p1.to_raw());
+            if let Some(design_line) = self.design_line(data) {
                 let design_len = (design_line.p1 - design_line.p0).hypot();
-                let intersections = self.compute_measurement(data, design_line);
+                let intersections = measurement::intersections(&data.paths, design_line);
                 for t in &intersections {
                     let pt = line.p0.lerp(line.p1, *t);
                     let circle = Circle::new(pt, MEASURE_INTERSECTION_RADIUS);
@@ -180,6 +126,31 @@ impl Tool for Measure {
         }
     }
 
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Pin measurement".into()
+    }
+
+    /// Return pins the current measurement, so that it stays visible.
+    fn key_down(
+        &mut self,
+        key: &KeyEvent,
+        ctx: &mut EventCtx,
+        data: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        if key.key != KbKey::Enter {
+            return None;
+        }
+        let line = self.design_line(data)?;
+        data.add_measurement(Measurement::Line {
+            start: DPoint::from_raw(line.p0),
+            end: DPoint::from_raw(line.p1),
+        });
+        self.line = None;
+        ctx.request_paint();
+        Some(EditType::Normal)
+    }
+
     fn mouse_event(
         &mut self,
         event: TaggedEvent,
@@ -4,22 +4,35 @@ mod ellipse;
 mod knife;
 mod measure;
 mod pen;
+mod pencil;
+mod polygon;
 mod preview;
 mod rectangle;
 mod select;
+mod spline;
 
 pub use ellipse::Ellipse;
-pub use knife::Knife;
+pub use knife::{Knife, KnifeMode, KnifeSettings};
 pub use measure::Measure;
 pub use pen::Pen;
+pub use pencil::Pencil;
+pub use polygon::Polygon;
 pub use preview::Preview;
 pub use rectangle::Rectangle;
 pub use select::Select;
+pub use spline::SplinePen;
 
+use crate::component::BaseBounds;
+use crate::design_space::{DPoint, ViewPort};
 use crate::edit_session::EditSession;
 use crate::mouse::{Mouse, TaggedEvent};
-use druid::kurbo::Point;
-use druid::{Env, EventCtx, KeyEvent, PaintCtx};
+use crate::shapes::ShapeSettings;
+use crate::snap::{SnapTarget, Snapping};
+use druid::kurbo::{Circle, Line, Point, Rect, Shape, Vec2};
+use druid::piet::StrokeStyle;
+use druid::{Color, Env, EventCtx, KeyEvent, PaintCtx, RenderContext};
+
+const SNAP_TARGET_COLOR: Color = Color::rgb8(0xFF, 0x3B, 0x8B);
 
 /// Something to pass around instead of a Box<dyn Tool>
 pub type ToolId = &'static str;
@@ -47,6 +60,22 @@ pub enum EditType {
     DragUp,
 }
 
+/// What tools need to know beyond the `EditSession`: the user's preferences
+/// for how they behave, and the bounds of any component glyphs.
+///
+/// These come from the `Workspace`; the editor passes them to its tool
+/// with [`Tool::set_settings`] whenever they change.
+///
+/// [`Tool::set_settings`]: trait.Tool.html#method.set_settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolSettings {
+    /// What dragged points snap to.
+    pub snapping: Snapping,
+    pub knife: KnifeSettings,
+    pub shapes: ShapeSettings,
+    pub component_bounds: BaseBounds,
+}
+
 /// A trait for representing the logic of a tool; that is, something that handles
 /// mouse and keyboard events, and modifies the current [`EditSession`].
 pub trait Tool {
@@ -89,6 +118,11 @@ pub trait Tool {
         None
     }
 
+    /// Called when a tool is activated, and whenever the settings change
+    /// while it is active.
+    #[allow(unused)]
+    fn set_settings(&mut self, settings: &ToolSettings) {}
+
     /// Called whenever a tool is first activated, so that it can access or modify
     /// mouse settings.
     #[allow(unused)]
@@ -112,6 +146,15 @@ pub trait Tool {
         None
     }
 
+    /// Returns a short, human-readable description of an edit returned by
+    /// this tool, for display in the undo history.
+    ///
+    /// This is called when `edit` begins a new undo group.
+    #[allow(unused)]
+    fn edit_label(&self, edit: EditType, data: &EditSession) -> String {
+        edit.label().into()
+    }
+
     fn name(&self) -> ToolId;
 }
 
@@ -120,9 +163,12 @@ pub fn tool_for_id(id: ToolId) -> Option<Box<dyn Tool>> {
     match id {
         "Preview" => Some(Box::new(Preview::default())),
         "Pen" => Some(Box::new(Pen::default())),
+        "Pencil" => Some(Box::new(Pencil::default())),
+        "Spline" => Some(Box::new(SplinePen::default())),
         "Select" => Some(Box::new(Select::default())),
         "Rectangle" => Some(Box::new(Rectangle::default())),
         "Ellipse" => Some(Box::new(Ellipse::default())),
+        "Polygon" => Some(Box::new(Polygon::default())),
         "Knife" => Some(Box::new(Knife::default())),
         "Measure" => Some(Box::new(Measure::default())),
         _ => None,
@@ -141,6 +187,27 @@ impl EditType {
             _ => true,
         }
     }
+
+    /// A generic description of this type of edit.
+    pub fn label(self) -> &'static str {
+        match self {
+            EditType::Normal => "Edit",
+            EditType::NudgeLeft
+            | EditType::NudgeRight
+            | EditType::NudgeUp
+            | EditType::NudgeDown => "Nudge",
+            EditType::Drag | EditType::DragUp => "Drag",
+        }
+    }
+}
+
+/// A description of `count` points, such as "3 points".
+fn describe_points(count: usize) -> String {
+    if count == 1 {
+        "1 point".into()
+    } else {
+        format!("{} points", count)
+    }
 }
 
 /// Lock the smallest axis of `point` (from `prev`) to that axis on `prev`.
@@ -153,3 +220,68 @@ fn axis_locked_point(point: Point, prev: Point) -> Point {
         Point::new(prev.x, point.y)
     }
 }
+
+/// The rectangle, in design space, drawn by a shape tool dragged from `start`
+/// to `current`.
+///
+/// If `square` is set, the rectangle is made square (aka shift + drag); if
+/// `from_center` is set, `start` is its center rather than a corner (aka
+/// alt + drag).
+fn drag_rect(start: DPoint, current: DPoint, square: bool, from_center: bool) -> Rect {
+    let mut delta = current - start;
+    if square {
+        delta.y = delta.x.abs().copysign(delta.y);
+    }
+    let start = start.to_raw();
+    let current = start + delta.to_raw();
+    if from_center {
+        Rect::from_points(start - delta.to_raw(), current)
+    } else {
+        Rect::from_points(start, current)
+    }
+}
+
+/// Highlight the things that a dragged point has snapped to.
+fn paint_snap_targets(ctx: &mut PaintCtx, targets: &[SnapTarget], vport: ViewPort) {
+    let visible = ctx.region().bounding_box();
+    let to_screen = vport.affine();
+    for target in targets {
+        match *target {
+            SnapTarget::Point(pt) => {
+                ctx.stroke(Circle::new(to_screen * pt, 6.0), &SNAP_TARGET_COLOR, 1.5);
+            }
+            SnapTarget::Vertical(x) => {
+                let x = (to_screen * Point::new(x, 0.0)).x;
+                let line = Line::new((x, visible.y0), (x, visible.y1));
+                ctx.stroke(line, &SNAP_TARGET_COLOR, 1.0);
+            }
+            SnapTarget::Horizontal(y) => {
+                let y = (to_screen * Point::new(0.0, y)).y;
+                let line = Line::new((visible.x0, y), (visible.x1, y));
+                ctx.stroke(line, &SNAP_TARGET_COLOR, 1.0);
+            }
+            SnapTarget::Line(line) => {
+                let (p0, p1) = (to_screen * line.p0, to_screen * line.p1);
+                let dir = (p1 - p0).normalize();
+                let len = visible.width() + visible.height();
+                let line = Line::new(p0 - dir * len, p0 + dir * len);
+                ctx.stroke(line, &SNAP_TARGET_COLOR, 1.0);
+            }
+            SnapTarget::Grid(pt) => {
+                let pt = to_screen * pt;
+                let (dx, dy) = (Vec2::new(4.0, 0.0), Vec2::new(0.0, 4.0));
+                ctx.stroke(Line::new(pt - dx, pt + dx), &SNAP_TARGET_COLOR, 1.0);
+                ctx.stroke(Line::new(pt - dy, pt + dy), &SNAP_TARGET_COLOR, 1.0);
+            }
+        }
+    }
+}
+
+/// Draw the alignment hints for a dragged point as dashed lines.
+fn paint_alignment_hints(ctx: &mut PaintCtx, lines: &[Line], vport: ViewPort) {
+    let style = StrokeStyle::new().dash(vec![4.0, 3.0], 0.0);
+    for line in lines {
+        let line = Line::new(vport.affine() * line.p0, vport.affine() * line.p1);
+        ctx.stroke_styled(line, &SNAP_TARGET_COLOR, 1.0, &style);
+    }
+}
@@ -1,21 +1,28 @@
 //! The bezier pen tool.
 
-use druid::{Env, EventCtx, KbKey, KeyEvent, MouseEvent};
+use druid::{Env, EventCtx, KbKey, KeyEvent, MouseEvent, PaintCtx};
 
 use crate::edit_session::EditSession;
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
-use crate::tools::{EditType, Tool, ToolId};
+use crate::snap::{self, SnapTarget, Snapping};
+use crate::tools::{EditType, Tool, ToolId, ToolSettings};
 
 /// The state of the pen.
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Pen {
     this_edit_type: Option<EditType>,
+    /// A description of the most recent `EditType::Normal` edit, for undo.
+    last_edit_label: &'static str,
     is_draggable: bool,
+    /// The things the new point or handle is currently snapped to.
+    snap_targets: Vec<SnapTarget>,
+    snapping: Snapping,
 }
 
 impl MouseDelegate<EditSession> for Pen {
     fn cancel(&mut self, canvas: &mut EditSession) {
         canvas.selection.clear();
+        self.snap_targets.clear();
     }
 
     fn left_down(&mut self, event: &MouseEvent, data: &mut EditSession) {
@@ -29,6 +36,7 @@ impl MouseDelegate<EditSession> for Pen {
                         if let Some(path) = data.active_path_mut() {
                             let start = path.start_point().id;
                             self.this_edit_type = Some(EditType::Normal);
+                            self.last_edit_label = "Close path";
                             path.close();
                             data.selection.select_one(start);
                             self.is_draggable = true;
@@ -36,16 +44,20 @@ impl MouseDelegate<EditSession> for Pen {
                         }
                     }
                 }
-                // TODO: more stuff when clicking on points
-                // If selection is empty, and point is endpoint of open path,
-                // select that point.
-                // Otherwise maybe cut path at that point?
+                if data.connect_paths(hit) {
+                    self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Connect paths";
+                } else if data.is_open_endpoint(hit) && data.resume_path(hit) {
+                    self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Resume path";
+                }
                 return;
             }
 
             // Handle clicking on segment (split).
             if let Some((seg, t)) = data.hit_test_segments(event.pos, None) {
                 self.this_edit_type = Some(EditType::Normal);
+                self.last_edit_label = "Split segment";
                 let path = data.path_for_point_mut(seg.start_id()).unwrap();
                 path.split_segment_at_point(seg, t);
                 return;
@@ -57,10 +69,16 @@ impl MouseDelegate<EditSession> for Pen {
                     let last_point = path.points().last().unwrap().to_screen(vport);
                     super::axis_locked_point(event.pos, last_point)
                 }
-                _ => event.pos,
+                _ if snap::is_suspended(event.mods) => event.pos,
+                _ => {
+                    let snap = data.snap_point(event.pos, false, &self.snapping);
+                    self.snap_targets = snap.targets;
+                    snap.point
+                }
             };
 
             self.this_edit_type = Some(EditType::Normal);
+            self.last_edit_label = "Add point";
             data.add_point(point);
             self.is_draggable = true;
         } else if event.count == 2 {
@@ -71,6 +89,7 @@ impl MouseDelegate<EditSession> for Pen {
     }
 
     fn left_up(&mut self, _event: &MouseEvent, data: &mut EditSession) {
+        self.snap_targets.clear();
         if let Some(path) = data.active_path_mut() {
             if path.is_closed() || path.points().len() > 1 && !path.last_segment_is_curve() {
                 path.clear_trailing();
@@ -83,10 +102,15 @@ impl MouseDelegate<EditSession> for Pen {
             return;
         }
         let Drag { start, current, .. } = drag;
+        self.snap_targets.clear();
         let handle_point = if current.mods.shift() {
             super::axis_locked_point(current.pos, start.pos)
-        } else {
+        } else if snap::is_suspended(current.mods) {
             current.pos
+        } else {
+            let snap = data.snap_point(current.pos, true, &self.snapping);
+            self.snap_targets = snap.targets;
+            snap.point
         };
         data.update_for_drag(handle_point);
         self.this_edit_type = Some(EditType::Drag);
@@ -100,16 +124,24 @@ impl MouseDelegate<EditSession> for Pen {
 }
 
 impl Tool for Pen {
+    fn set_settings(&mut self, settings: &ToolSettings) {
+        self.snapping = settings.snapping.clone();
+    }
+
     fn mouse_event(
         &mut self,
         event: TaggedEvent,
         mouse: &mut Mouse,
-        _ctx: &mut EventCtx,
+        ctx: &mut EventCtx,
         data: &mut EditSession,
         _: &Env,
     ) -> Option<EditType> {
         assert!(self.this_edit_type.is_none());
+        let pre_targets = self.snap_targets.clone();
         mouse.mouse_event(event, data, self);
+        if pre_targets != self.snap_targets {
+            ctx.request_paint();
+        }
         self.this_edit_type.take()
     }
 
@@ -125,6 +157,7 @@ impl Tool for Pen {
             e if e.key == KbKey::Backspace => {
                 data.delete_selection();
                 self.this_edit_type = Some(EditType::Normal);
+                self.last_edit_label = "Delete";
             }
             // TODO: should support nudging; basically a lot of this should
             // be shared with selection.
@@ -133,6 +166,17 @@ impl Tool for Pen {
         self.this_edit_type.take()
     }
 
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, _env: &Env) {
+        super::paint_snap_targets(ctx, &self.snap_targets, data.viewport);
+    }
+
+    fn edit_label(&self, edit: EditType, _: &EditSession) -> String {
+        match edit {
+            EditType::Drag | EditType::DragUp => "Drag handle".into(),
+            _ => self.last_edit_label.into(),
+        }
+    }
+
     fn name(&self) -> ToolId {
         "Pen"
     }
@@ -0,0 +1,145 @@
+//! The pencil tool, for drawing freehand.
+
+use druid::kurbo::BezPath;
+use druid::{Color, Env, EventCtx, MouseEvent, PaintCtx, Point, RenderContext};
+
+use crate::edit_session::EditSession;
+use crate::fit;
+use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
+use crate::tools::{EditType, Tool};
+
+/// The maximum distance, in screen pixels, between the drawn trail and the
+/// path that is fit to it.
+const FIT_TOLERANCE: f64 = 2.0;
+/// The distance, in screen pixels, over which the direction of the trail is
+/// measured when looking for corners.
+const CORNER_WINDOW: f64 = 12.0;
+
+/// The state of the pencil tool.
+#[derive(Debug, Clone, Default)]
+pub struct Pencil {
+    gesture: GestureState,
+    /// The points of the current drag, in design space; these may be
+    /// fractional, hence not `DPoint`.
+    trail: Vec<Point>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GestureState {
+    Ready,
+    Down,
+    Drawing,
+    Finished,
+}
+
+impl Default for GestureState {
+    fn default() -> Self {
+        GestureState::Ready
+    }
+}
+
+impl Tool for Pencil {
+    fn name(&self) -> &'static str {
+        "Pencil"
+    }
+
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Draw with pencil".into()
+    }
+
+    fn init_mouse(&mut self, mouse: &mut Mouse) {
+        mouse.min_drag_distance = 2.0;
+    }
+
+    fn mouse_event(
+        &mut self,
+        event: TaggedEvent,
+        mouse: &mut Mouse,
+        ctx: &mut EventCtx,
+        data: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        let pre_len = self.trail.len();
+        let pre_state = self.gesture;
+        mouse.mouse_event(event, data, self);
+        if pre_state != self.gesture || pre_len != self.trail.len() {
+            ctx.request_paint();
+        }
+
+        if self.gesture == GestureState::Finished {
+            self.gesture = GestureState::Ready;
+            Some(EditType::Normal)
+        } else {
+            None
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, _env: &Env) {
+        if self.gesture == GestureState::Drawing {
+            let mut bez = BezPath::new();
+            let mut points = self.trail.iter().map(|pt| data.viewport.affine() * *pt);
+            if let Some(first) = points.next() {
+                bez.move_to(first);
+                points.for_each(|pt| bez.line_to(pt));
+            }
+            ctx.stroke(bez, &Color::BLACK, 1.0);
+        }
+    }
+}
+
+impl MouseDelegate<EditSession> for Pencil {
+    fn cancel(&mut self, _data: &mut EditSession) {
+        self.gesture = GestureState::Ready;
+        self.trail.clear();
+    }
+
+    fn left_down(&mut self, event: &MouseEvent, data: &mut EditSession) {
+        if event.count == 1 {
+            self.trail.clear();
+            self.trail.push(data.viewport.inverse_affine() * event.pos);
+            self.gesture = GestureState::Down;
+        }
+    }
+
+    fn left_up(&mut self, _event: &MouseEvent, _data: &mut EditSession) {
+        if self.gesture == GestureState::Down {
+            self.gesture = GestureState::Ready;
+        }
+    }
+
+    fn left_drag_began(&mut self, drag: Drag, data: &mut EditSession) {
+        if self.gesture == GestureState::Down {
+            self.trail
+                .push(data.viewport.inverse_affine() * drag.current.pos);
+            self.gesture = GestureState::Drawing;
+        }
+    }
+
+    fn left_drag_changed(&mut self, drag: Drag, data: &mut EditSession) {
+        if self.gesture == GestureState::Drawing {
+            self.trail
+                .push(data.viewport.inverse_affine() * drag.current.pos);
+        }
+    }
+
+    fn left_drag_ended(&mut self, drag: Drag, data: &mut EditSession) {
+        if self.gesture != GestureState::Drawing {
+            return;
+        }
+        self.trail
+            .push(data.viewport.inverse_affine() * drag.current.pos);
+
+        // the tolerances are in screen space, so that the fit is as close
+        // as the drawing was, at any zoom level.
+        let zoom = data.viewport.zoom;
+        let path = fit::fit_trail(&self.trail, FIT_TOLERANCE / zoom, CORNER_WINDOW / zoom);
+        self.trail.clear();
+        match path {
+            Some(path) => {
+                data.paste_paths(vec![path]);
+                self.gesture = GestureState::Finished;
+            }
+            None => self.gesture = GestureState::Ready,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! The polygon and star shape tool
+
+use druid::kurbo::Vec2;
+use druid::{Color, Env, EventCtx, KbKey, KeyEvent, MouseEvent, PaintCtx, RenderContext};
+
+use crate::consts;
+use crate::design_space::DPoint;
+use crate::edit_session::EditSession;
+use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
+use crate::path::Path;
+use crate::shapes::{self, ShapeKind, ShapeSettings};
+use crate::tools::{EditType, Tool, ToolSettings};
+
+/// The state of the polygon tool.
+///
+/// Polygons are drawn outwards from their center, with a point under the
+/// mouse; whether they are stars, and how many sides they have, is set in
+/// the workspace's shape settings.
+#[derive(Debug, Default, Clone)]
+pub struct Polygon {
+    gesture: GestureState,
+    shift_locked: bool,
+    settings: ShapeSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GestureState {
+    Ready,
+    Down(DPoint),
+    Begun {
+        center: DPoint,
+        current: DPoint,
+    },
+    /// The canvas was clicked without dragging.
+    Clicked(DPoint),
+    Finished,
+}
+
+impl Polygon {
+    /// The current polygon, in design space.
+    fn current_path(&self) -> Option<Path> {
+        if let GestureState::Begun { center, current } = self.gesture {
+            let mut first = (current - center).to_raw();
+            if self.shift_locked {
+                // keep the polygon upright
+                first = Vec2::new(0.0, first.hypot());
+            }
+            shapes::polygon_path(center.to_raw(), first, &self.settings)
+        } else {
+            None
+        }
+    }
+}
+
+impl Tool for Polygon {
+    fn name(&self) -> &'static str {
+        "Polygon"
+    }
+
+    fn set_settings(&mut self, settings: &ToolSettings) {
+        self.settings = settings.shapes;
+    }
+
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Draw polygon".into()
+    }
+
+    fn key_down(
+        &mut self,
+        key: &KeyEvent,
+        ctx: &mut EventCtx,
+        _: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        if key.key == KbKey::Shift {
+            self.shift_locked = true;
+            ctx.request_paint();
+        }
+        None
+    }
+
+    fn key_up(
+        &mut self,
+        key: &KeyEvent,
+        ctx: &mut EventCtx,
+        _: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        if key.key == KbKey::Shift {
+            self.shift_locked = false;
+            ctx.request_paint();
+        }
+        None
+    }
+
+    fn mouse_event(
+        &mut self,
+        event: TaggedEvent,
+        mouse: &mut Mouse,
+        ctx: &mut EventCtx,
+        data: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        let pre_state = self.gesture;
+        mouse.mouse_event(event, data, self);
+        if pre_state != self.gesture {
+            ctx.request_paint();
+        }
+
+        match self.gesture {
+            GestureState::Finished => {
+                self.gesture = GestureState::Ready;
+                Some(EditType::Normal)
+            }
+            GestureState::Clicked(center) => {
+                self.gesture = GestureState::Ready;
+                ctx.submit_command(
+                    consts::cmd::SHOW_SHAPE_PANEL.with((ShapeKind::Polygon, center)),
+                );
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, _env: &Env) {
+        if let Some(path) = self.current_path() {
+            ctx.stroke(
+                data.viewport.affine() * path.bezier(),
+                &Color::grey(0.7),
+                1.0,
+            );
+        }
+    }
+}
+
+impl MouseDelegate<EditSession> for Polygon {
+    fn cancel(&mut self, _data: &mut EditSession) {
+        self.gesture = GestureState::Ready;
+    }
+
+    fn left_down(&mut self, event: &MouseEvent, data: &mut EditSession) {
+        if event.count == 1 {
+            self.gesture = GestureState::Down(data.viewport.from_screen(event.pos));
+            self.shift_locked = event.mods.shift();
+        }
+    }
+
+    fn left_click(&mut self, _event: &MouseEvent, _data: &mut EditSession) {
+        if let GestureState::Down(pt) = self.gesture {
+            self.gesture = GestureState::Clicked(pt);
+        }
+    }
+
+    fn left_drag_began(&mut self, drag: Drag, data: &mut EditSession) {
+        if let GestureState::Down(center) = self.gesture {
+            let current = data.viewport.from_screen(drag.current.pos);
+            self.gesture = GestureState::Begun { center, current };
+        }
+    }
+
+    fn left_drag_changed(&mut self, drag: Drag, data: &mut EditSession) {
+        if let GestureState::Begun { current, .. } = &mut self.gesture {
+            *current = data.viewport.from_screen(drag.current.pos);
+        }
+    }
+
+    fn left_drag_ended(&mut self, _drag: Drag, data: &mut EditSession) {
+        if let GestureState::Begun { .. } = self.gesture {
+            self.gesture = match self.current_path() {
+                Some(path) => {
+                    data.paste_paths(vec![path]);
+                    GestureState::Finished
+                }
+                None => GestureState::Ready,
+            };
+        }
+    }
+}
+
+impl Default for GestureState {
+    fn default() -> Self {
+        GestureState::Ready
+    }
+}
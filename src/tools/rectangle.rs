@@ -5,18 +5,21 @@ use druid::{
     TextLayout,
 };
 
+use crate::consts;
 use crate::design_space::DPoint;
 use crate::edit_session::EditSession;
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
-use crate::path::{Path, PathPoint};
-use crate::tools::{EditType, Tool};
+use crate::shapes::{self, ShapeKind, ShapeSettings};
+use crate::tools::{EditType, Tool, ToolSettings};
 
 /// The state of the rectangle tool.
 #[derive(Debug, Clone)]
 pub struct Rectangle {
     gesture: GestureState,
     shift_locked: bool,
+    from_center: bool,
     coord_text: TextLayout<String>,
+    settings: ShapeSettings,
 }
 
 impl Default for Rectangle {
@@ -26,7 +29,9 @@ impl Default for Rectangle {
         Rectangle {
             gesture: Default::default(),
             shift_locked: false,
+            from_center: false,
             coord_text: layout,
+            settings: ShapeSettings::default(),
         }
     }
 }
@@ -35,36 +40,29 @@ impl Default for Rectangle {
 enum GestureState {
     Ready,
     Down(DPoint),
-    Begun { start: DPoint, current: DPoint },
+    Begun {
+        start: DPoint,
+        current: DPoint,
+    },
+    /// The canvas was clicked without dragging.
+    Clicked(DPoint),
     Finished,
 }
 
 impl Rectangle {
-    fn pts_for_rect(&self) -> Option<(DPoint, DPoint)> {
+    /// The current rectangle, in design space.
+    fn current_rect(&self) -> Option<Rect> {
         if let GestureState::Begun { start, current } = self.gesture {
-            let mut current = current;
-            if self.shift_locked {
-                let mut vec2 = current - start;
-                vec2.y = if vec2.y.signum() > 0.0 {
-                    vec2.x.abs()
-                } else {
-                    vec2.x.abs() * -1.0
-                };
-                current = start + vec2;
-            }
-            Some((start, current))
+            Some(super::drag_rect(
+                start,
+                current,
+                self.shift_locked,
+                self.from_center,
+            ))
         } else {
             None
         }
     }
-
-    fn current_drag_rect(&self, data: &EditSession) -> Option<Rect> {
-        let (start, current) = self.pts_for_rect()?;
-        Some(Rect::from_points(
-            data.viewport.to_screen(start),
-            data.viewport.to_screen(current),
-        ))
-    }
 }
 
 impl Tool for Rectangle {
@@ -72,6 +70,14 @@ impl Tool for Rectangle {
         "Rectangle"
     }
 
+    fn set_settings(&mut self, settings: &ToolSettings) {
+        self.settings = settings.shapes;
+    }
+
+    fn edit_label(&self, _: EditType, _: &EditSession) -> String {
+        "Draw rectangle".into()
+    }
+
     fn key_down(
         &mut self,
         key: &KeyEvent,
@@ -82,6 +88,9 @@ impl Tool for Rectangle {
         if key.key == KbKey::Shift {
             self.shift_locked = true;
             ctx.request_paint();
+        } else if key.key == KbKey::Alt {
+            self.from_center = true;
+            ctx.request_paint();
         }
         None
     }
@@ -96,6 +105,9 @@ impl Tool for Rectangle {
         if key.key == KbKey::Shift {
             self.shift_locked = false;
             ctx.request_paint();
+        } else if key.key == KbKey::Alt {
+            self.from_center = false;
+            ctx.request_paint();
         }
         None
     }
@@ -114,21 +126,31 @@ impl Tool for Rectangle {
             ctx.request_paint();
         }
 
-        if self.gesture == GestureState::Finished {
-            self.gesture = GestureState::Ready;
-            Some(EditType::Normal)
-        } else {
-            None
+        match self.gesture {
+            GestureState::Finished => {
+                self.gesture = GestureState::Ready;
+                Some(EditType::Normal)
+            }
+            GestureState::Clicked(origin) => {
+                self.gesture = GestureState::Ready;
+                ctx.submit_command(
+                    consts::cmd::SHOW_SHAPE_PANEL.with((ShapeKind::Rectangle, origin)),
+                );
+                None
+            }
+            _ => None,
         }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, env: &Env) {
         const LABEL_PADDING: f64 = 4.0;
-        if let Some(rect) = self.current_drag_rect(data) {
-            ctx.stroke(rect, &Color::BLACK, 1.0);
-            let (start, current) = self.pts_for_rect().unwrap();
-            let size = start - current;
-            let label_text = format!("{}, {}", size.x.abs(), size.y.abs());
+        if let Some(rect) = self.current_rect() {
+            let radius = self.settings.corner_radius;
+            if let Some(path) = shapes::rect_path(rect, radius) {
+                ctx.stroke(data.viewport.affine() * path.bezier(), &Color::BLACK, 1.0);
+            }
+            let label_text = format!("{}, {}", rect.width().round(), rect.height().round());
+            let rect = data.viewport.rect_to_screen(rect);
             self.coord_text.set_text(label_text);
             self.coord_text.rebuild_if_needed(ctx.text(), env);
             let text_size = self.coord_text.size();
@@ -156,14 +178,26 @@ impl MouseDelegate<EditSession> for Rectangle {
             let pt = data.viewport.from_screen(event.pos);
             self.gesture = GestureState::Down(pt);
             self.shift_locked = event.mods.shift();
+            self.from_center = event.mods.alt();
+        }
+    }
+
+    fn left_click(&mut self, _event: &MouseEvent, _data: &mut EditSession) {
+        if let GestureState::Down(pt) = self.gesture {
+            self.gesture = GestureState::Clicked(pt);
         }
     }
 
     fn left_up(&mut self, _event: &MouseEvent, data: &mut EditSession) {
-        if let Some((start, current)) = self.pts_for_rect() {
-            let path = make_rect_path(start, current);
-            data.paste_paths(vec![path]);
-            self.gesture = GestureState::Finished;
+        if let Some(rect) = self.current_rect() {
+            let radius = self.settings.corner_radius;
+            self.gesture = match shapes::rect_path(rect, radius) {
+                Some(path) => {
+                    data.paste_paths(vec![path]);
+                    GestureState::Finished
+                }
+                None => GestureState::Ready,
+            };
         }
     }
 
@@ -186,17 +220,3 @@ impl Default for GestureState {
         GestureState::Ready
     }
 }
-
-fn make_rect_path(p1: DPoint, p3: DPoint) -> Path {
-    let path_id = crate::path::next_id();
-    let p2 = DPoint::new(p3.x, p1.y);
-    let p4 = DPoint::new(p1.x, p3.y);
-    // first point goes last in closed paths
-    let points = vec![
-        PathPoint::on_curve(path_id, p2),
-        PathPoint::on_curve(path_id, p3),
-        PathPoint::on_curve(path_id, p4),
-        PathPoint::on_curve(path_id, p1),
-    ];
-    Path::from_raw_parts(path_id, points, None, true)
-}
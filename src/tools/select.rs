@@ -1,21 +1,28 @@
-use druid::kurbo::{BezPath, Circle, Insets, Point, Rect, Shape, Vec2};
+use druid::kurbo::{Affine, BezPath, Circle, Insets, Line, Point, Rect, Shape, Vec2};
 use druid::piet::{RenderContext, StrokeStyle};
 use druid::{Data, Env, EventCtx, HotKey, KbKey, KeyEvent, MouseEvent, PaintCtx, RawMods};
 
+use crate::component::BaseBounds;
 use crate::edit_session::EditSession;
+use crate::hints::AlignmentIndex;
 use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
-use crate::path::PathSeg;
-use crate::tools::{EditType, Tool, ToolId};
+use crate::path::{EntityId, PathSeg};
+use crate::tools::{EditType, Tool, ToolId, ToolSettings};
 use crate::{
     design_space::{DPoint, DVec2, ViewPort},
     quadrant::Quadrant,
     selection::Selection,
+    snap::{self, SnapTarget, Snapping, SNAP_DISTANCE},
     theme,
 };
 
 // distance from edges of the selection bbox to where we draw the handles
 const SELECTION_BBOX_HANDLE_PADDING: Insets = Insets::uniform(6.0);
 const SELECTION_HANDLE_RADIUS: f64 = 4.;
+/// With shift held, rotation snaps to multiples of this many degrees.
+const ROTATION_SNAP_DEGREES: f64 = 15.0;
+/// The minimum distance, in screen space, between the vertices of a lasso.
+const LASSO_MIN_SEGMENT_LENGTH: f64 = 2.0;
 
 /// A set of states that are possible while handling a mouse drag.
 #[derive(Debug, Clone)]
@@ -25,9 +32,21 @@ enum DragState {
         previous: Selection,
         rect: Rect,
     },
+    /// State for a drag that is a freehand (lasso) selection; this is begun
+    /// by dragging with alt held.
+    Lasso {
+        previous: Selection,
+        /// The vertices of the lasso, in screen space.
+        points: Vec<Point>,
+        /// The bounds of `points`, used to quickly skip far away points.
+        bounds: Rect,
+    },
     /// State for a drag that is moving a selected object.
     Move {
         delta: DVec2,
+        /// The starting position of the dragged point, if the drag began on
+        /// a point; this is the point that snaps.
+        anchor: Option<DPoint>,
     },
     /// State for a drag that is moving an off-curve point.
     MoveHandle,
@@ -36,6 +55,7 @@ enum DragState {
     Suppress,
     TransformSelection {
         quadrant: Quadrant,
+        mode: TransformMode,
         previous: EditSession,
         delta: DVec2,
         /// The paths before this transform; we want to draw these faintly
@@ -45,6 +65,17 @@ enum DragState {
     None,
 }
 
+/// The kind of transform performed by dragging a selection handle.
+///
+/// Handles scale by default; with alt held, corner handles rotate around
+/// the center of the selection, and edge handles skew it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TransformMode {
+    Scale,
+    Rotate,
+    Skew,
+}
+
 /// The state of the selection tool.
 #[derive(Debug, Default, Clone)]
 pub struct Select {
@@ -59,9 +90,28 @@ pub struct Select {
     /// It is an invariant that this is always `None`, except while we are in
     /// a `key_down`, `key_up`, or `mouse_event` method.
     this_edit_type: Option<EditType>,
+    /// A description of the most recent `EditType::Normal` edit, for undo.
+    last_edit_label: &'static str,
+    /// An already selected object that was clicked. If the mouse is released
+    /// without dragging, it becomes the key object for alignment.
+    key_candidate: Option<EntityId>,
+    /// The things the dragged point is currently snapped to.
+    snap_targets: Vec<SnapTarget>,
+    /// The points that a dragged point may line up with; this is built when
+    /// a drag begins.
+    hint_index: Option<AlignmentIndex>,
+    /// The alignment hints for the dragged point, in design space.
+    hint_lines: Vec<Line>,
+    snapping: Snapping,
+    component_bounds: BaseBounds,
 }
 
 impl Tool for Select {
+    fn set_settings(&mut self, settings: &ToolSettings) {
+        self.snapping = settings.snapping.clone();
+        self.component_bounds = settings.component_bounds.clone();
+    }
+
     fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, env: &Env) {
         let selection_stroke = env.get(theme::SELECTION_RECT_STROKE_COLOR);
         match &self.drag {
@@ -69,6 +119,11 @@ impl Tool for Select {
                 ctx.fill(rect, &env.get(theme::SELECTION_RECT_FILL_COLOR));
                 ctx.stroke(rect, &selection_stroke, 1.0);
             }
+            DragState::Lasso { points, .. } => {
+                let lasso = lasso_path(points);
+                ctx.fill(&lasso, &env.get(theme::SELECTION_RECT_FILL_COLOR));
+                ctx.stroke(&lasso, &selection_stroke, 1.0);
+            }
             // draw the selection bounding box
             DragState::None if data.selection.len() > 1 => {
                 let bbox = data.viewport.rect_to_screen(data.selection_dpoint_bbox());
@@ -104,6 +159,14 @@ impl Tool for Select {
             }
             _ => (),
         }
+
+        // the key object, that others are aligned to, gets a heavier outline
+        if let Some(bounds) = data.key_object_bounds(&self.component_bounds) {
+            let rect = data.viewport.rect_to_screen(bounds);
+            ctx.stroke(rect, &selection_stroke, 1.5);
+        }
+        super::paint_snap_targets(ctx, &self.snap_targets, data.viewport);
+        super::paint_alignment_hints(ctx, &self.hint_lines, data.viewport);
     }
 
     fn key_down(
@@ -125,6 +188,7 @@ impl Tool for Select {
             e if e.key == KbKey::Backspace => {
                 data.delete_selection();
                 self.this_edit_type = Some(EditType::Normal);
+                self.last_edit_label = "Delete";
             }
             e if HotKey::new(None, KbKey::Tab).matches(e) => data.select_next(),
             //TODO: add Shift to SysMods
@@ -144,13 +208,38 @@ impl Tool for Select {
     ) -> Option<EditType> {
         assert!(self.this_edit_type.is_none());
         let pre_rect = self.drag.drag_rect();
+        let pre_lasso = self.drag.lasso_len();
+        let pre_targets = self.snap_targets.clone();
+        let pre_hints = self.hint_lines.clone();
         mouse.mouse_event(event, data, self);
-        if !pre_rect.same(&self.drag.drag_rect()) {
+        if !pre_rect.same(&self.drag.drag_rect())
+            || pre_lasso != self.drag.lasso_len()
+            || pre_targets != self.snap_targets
+            || pre_hints != self.hint_lines
+        {
             ctx.request_paint();
         }
         self.this_edit_type.take()
     }
 
+    fn edit_label(&self, edit: EditType, data: &EditSession) -> String {
+        match &self.drag {
+            DragState::Move { .. } => {
+                format!("Move {}", super::describe_points(data.selection.len()))
+            }
+            DragState::TransformSelection { mode, .. } => {
+                let verb = match mode {
+                    TransformMode::Scale => "Scale",
+                    TransformMode::Rotate => "Rotate",
+                    TransformMode::Skew => "Skew",
+                };
+                format!("{} {}", verb, super::describe_points(data.selection.len()))
+            }
+            _ if edit == EditType::Normal => self.last_edit_label.into(),
+            _ => edit.label().into(),
+        }
+    }
+
     fn name(&self) -> ToolId {
         "Select"
     }
@@ -173,6 +262,7 @@ impl Select {
         }
 
         data.nudge_selection(DVec2::from_raw(nudge));
+        self.last_edit_label = "Nudge";
 
         // for the purposes of undo, we only combine single-unit nudges
         if nudge.hypot().abs() > 1.0 {
@@ -244,6 +334,7 @@ impl MouseDelegate<EditSession> for Select {
                     let path = data.path_for_point_mut(seg.start_id()).unwrap();
                     path.upgrade_line_seg(seg);
                     self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Convert to curve";
                     return;
                 }
                 if !append_mode && !all_selected {
@@ -253,6 +344,7 @@ impl MouseDelegate<EditSession> for Select {
                     // we allow a drag gesture to begin only if the clicked
                     // segment was previously selected.
                     self.drag = DragState::None;
+                    self.key_candidate = Some(seg.start_id());
                 } else if append_mode && all_selected {
                     for id in &ids {
                         data.selection.remove(id);
@@ -260,6 +352,18 @@ impl MouseDelegate<EditSession> for Select {
                 } else if append_mode {
                     data.selection.extend(ids);
                 }
+            } else if let Some(comp_id) =
+                data.hit_test_components(event.pos, &self.component_bounds)
+            {
+                if event.mods.shift() {
+                    if !data.selection.remove(&comp_id) {
+                        data.selection.insert(comp_id);
+                    }
+                } else if data.selection.contains(&comp_id) {
+                    self.key_candidate = Some(comp_id);
+                } else {
+                    data.selection.select_one(comp_id);
+                }
             } else if !event.mods.shift() {
                 data.selection.clear();
             }
@@ -274,10 +378,12 @@ impl MouseDelegate<EditSession> for Select {
                 {
                     data.toggle_selected_on_curve_type();
                     self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Toggle point type";
                 }
                 Some(id) if id.is_guide() => {
                     data.toggle_guide(id, event.pos);
                     self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Toggle guide";
                 }
                 _ => {
                     data.select_path(event.pos, event.mods.shift());
@@ -286,11 +392,15 @@ impl MouseDelegate<EditSession> for Select {
         }
     }
 
-    fn left_up(&mut self, _event: &MouseEvent, _data: &mut EditSession) {
+    fn left_up(&mut self, _event: &MouseEvent, data: &mut EditSession) {
+        if let Some(id) = self.key_candidate.take() {
+            data.set_key_object(Some(id));
+        }
         self.drag = DragState::None;
     }
 
     fn left_drag_began(&mut self, drag: Drag, data: &mut EditSession) {
+        self.key_candidate = None;
         if matches!(self.drag, DragState::Suppress) {
             return;
         }
@@ -298,9 +408,15 @@ impl MouseDelegate<EditSession> for Select {
         // are we dragging a selection rect handle?
         if let Some(quadrant) = self.selection_handle_hit(data, drag.start.pos) {
             let pre_paths = data.to_bezier();
+            let mode = match quadrant {
+                _ if !drag.start.mods.alt() => TransformMode::Scale,
+                q if q.modifies_x_axis() && q.modifies_y_axis() => TransformMode::Rotate,
+                _ => TransformMode::Skew,
+            };
             self.drag = DragState::TransformSelection {
                 delta: DVec2::ZERO,
                 quadrant,
+                mode,
                 previous: data.clone(),
                 pre_paths,
             };
@@ -315,31 +431,104 @@ impl MouseDelegate<EditSession> for Select {
             if is_dragging_handle {
                 DragState::MoveHandle
             } else {
-                DragState::Move { delta: DVec2::ZERO }
+                DragState::Move {
+                    delta: DVec2::ZERO,
+                    anchor: Some(pt.point),
+                }
             }
         } else if data.hit_test_segments(drag.start.pos, None).is_some() {
-            DragState::Move { delta: DVec2::ZERO }
+            DragState::Move {
+                delta: DVec2::ZERO,
+                anchor: None,
+            }
+        } else if data
+            .hit_test_components(drag.start.pos, &self.component_bounds)
+            .map(|id| data.selection.contains(&id))
+            .unwrap_or(false)
+        {
+            DragState::Move {
+                delta: DVec2::ZERO,
+                anchor: None,
+            }
+        } else if drag.start.mods.alt() {
+            DragState::Lasso {
+                previous: data.selection.clone(),
+                points: vec![drag.start.pos, drag.current.pos],
+                bounds: Rect::from_points(drag.start.pos, drag.current.pos),
+            }
         } else {
             // if we're starting a rectangular selection, we save the previous selection
             DragState::Select {
                 previous: data.selection.clone(),
                 rect: Rect::from_points(drag.start.pos, drag.current.pos),
             }
+        };
+
+        // alignment hints are shown for the point the drag began on
+        let shows_hints = match self.drag {
+            DragState::Move { anchor, .. } => anchor.is_some(),
+            DragState::MoveHandle => true,
+            _ => false,
+        };
+        if shows_hints {
+            let moving = data.points_moved_with_selection();
+            let others = data
+                .iter_visible_points()
+                .filter(|pt| !moving.contains(&pt.id))
+                .map(|pt| pt.point.to_raw());
+            self.hint_index = Some(AlignmentIndex::new(others));
         }
     }
 
     fn left_drag_changed(&mut self, drag: Drag, data: &mut EditSession) {
         self.last_pos = drag.current.pos;
+        self.snap_targets.clear();
+        self.hint_lines.clear();
+        let can_snap = !drag.current.mods.shift() && !snap::is_suspended(drag.current.mods);
+        // while snapping is off, hints are still shown for exact alignments
+        let hint_tolerance = if can_snap {
+            SNAP_DISTANCE / data.viewport.zoom
+        } else {
+            0.0
+        };
         match &mut self.drag {
             DragState::Select { previous, rect } => {
                 *rect = Rect::from_points(drag.current.pos, drag.start.pos);
-                update_selection_for_drag(data, previous, *rect, drag.current.mods.shift());
+                let shift = drag.current.mods.shift();
+                update_selection_for_drag(data, previous, *rect, shift, &self.component_bounds);
+            }
+            DragState::Lasso {
+                previous,
+                points,
+                bounds,
+            } => {
+                let last = points.last().copied().unwrap_or(drag.start.pos);
+                if last.distance(drag.current.pos) >= LASSO_MIN_SEGMENT_LENGTH {
+                    points.push(drag.current.pos);
+                    *bounds = bounds.union_pt(drag.current.pos);
+                    let shift = drag.current.mods.shift();
+                    let bases = &self.component_bounds;
+                    update_selection_for_lasso(data, previous, points, *bounds, shift, bases);
+                }
             }
-            DragState::Move { delta } => {
+            DragState::Move { delta, anchor } => {
                 let mut new_delta = delta_for_drag_change(&drag, data.viewport);
                 if drag.current.mods.shift() {
                     new_delta = new_delta.axis_locked();
                 }
+                if let Some(anchor) = anchor.filter(|_| can_snap) {
+                    let pos = (anchor + new_delta).to_screen(data.viewport);
+                    let snap = data.snap_point(pos, true, &self.snapping);
+                    new_delta = DPoint::from_screen(snap.point, data.viewport) - anchor;
+                    self.snap_targets = snap.targets;
+                }
+                if let (Some(anchor), Some(index)) = (*anchor, &self.hint_index) {
+                    if self.snap_targets.is_empty() {
+                        let hints = index.query((anchor + new_delta).to_raw(), hint_tolerance);
+                        new_delta = DPoint::from_raw(hints.point) - anchor;
+                        self.hint_lines = hints.lines;
+                    }
+                }
                 let drag_delta = new_delta - *delta;
                 if drag_delta.hypot() > 0. {
                     data.nudge_selection(drag_delta);
@@ -347,11 +536,24 @@ impl MouseDelegate<EditSession> for Select {
                 }
             }
             DragState::MoveHandle => {
-                data.update_handle(drag.current.pos, drag.current.mods.shift());
+                let mut pos = drag.current.pos;
+                if can_snap {
+                    let snap = data.snap_point(pos, true, &self.snapping);
+                    pos = snap.point;
+                    self.snap_targets = snap.targets;
+                }
+                if let (true, Some(index)) = (self.snap_targets.is_empty(), &self.hint_index) {
+                    let dpoint = data.viewport.from_screen(pos);
+                    let hints = index.query(dpoint.to_raw(), hint_tolerance);
+                    pos = DPoint::from_raw(hints.point).to_screen(data.viewport);
+                    self.hint_lines = hints.lines;
+                }
+                data.update_handle(pos, drag.current.mods.shift());
             }
             DragState::Suppress => (),
             DragState::TransformSelection {
                 quadrant,
+                mode: TransformMode::Scale,
                 previous,
                 delta,
                 ..
@@ -368,6 +570,37 @@ impl MouseDelegate<EditSession> for Select {
                     *data = new_data;
                 }
             }
+            DragState::TransformSelection {
+                quadrant,
+                mode,
+                previous,
+                delta,
+                ..
+            } => {
+                let new_delta = delta_for_drag_change(&drag, data.viewport);
+                if new_delta != *delta {
+                    *delta = new_delta;
+                    let sel_rect = previous.selection_dpoint_bbox();
+                    let (affine, anchor) = if *mode == TransformMode::Rotate {
+                        let center = sel_rect.center();
+                        let start = previous.viewport.from_screen(drag.start.pos).to_raw();
+                        let current = start + new_delta.to_raw();
+                        let mut angle = (current - center).atan2() - (start - center).atan2();
+                        if drag.current.mods.shift() {
+                            let snap = ROTATION_SNAP_DEGREES.to_radians();
+                            angle = (angle / snap).round() * snap;
+                        }
+                        (Affine::rotate(angle), center)
+                    } else {
+                        let handle = quadrant.point_in_dspace_rect(sel_rect);
+                        let anchor = quadrant.inverse().point_in_dspace_rect(sel_rect);
+                        (skew_for_drag(*quadrant, handle - anchor, new_delta), anchor)
+                    };
+                    let mut new_data = previous.clone();
+                    new_data.transform_selection(affine, DPoint::from_raw(anchor));
+                    *data = new_data;
+                }
+            }
             DragState::None => unreachable!("invalid state"),
         }
 
@@ -377,15 +610,24 @@ impl MouseDelegate<EditSession> for Select {
     }
 
     fn left_drag_ended(&mut self, _drag: Drag, _data: &mut EditSession) {
+        self.snap_targets.clear();
+        self.hint_index = None;
+        self.hint_lines.clear();
         if self.drag.is_move() || self.drag.is_transform() {
             self.this_edit_type = Some(EditType::DragUp);
         }
     }
 
     fn cancel(&mut self, data: &mut EditSession) {
+        self.snap_targets.clear();
+        self.hint_index = None;
+        self.hint_lines.clear();
         let old_state = std::mem::replace(&mut self.drag, DragState::None);
-        if let DragState::Select { previous, .. } = old_state {
-            data.selection = previous;
+        match old_state {
+            DragState::Select { previous, .. } | DragState::Lasso { previous, .. } => {
+                data.selection = previous;
+            }
+            _ => (),
         }
     }
 }
@@ -398,6 +640,24 @@ fn delta_for_drag_change(drag: &Drag, viewport: ViewPort) -> DVec2 {
     drag_pos - drag_start
 }
 
+/// The skew for a drag of an edge handle: top and bottom handles skew
+/// horizontally, and left and right handles skew vertically, leaving the
+/// opposite edge in place.
+fn skew_for_drag(quadrant: Quadrant, handle_offset: Vec2, drag: DVec2) -> Affine {
+    let drag = drag.to_raw();
+    if quadrant.modifies_y_axis() {
+        if handle_offset.y == 0.0 {
+            return Affine::default();
+        }
+        Affine::new([1.0, 0.0, drag.x / handle_offset.y, 1.0, 0.0, 0.0])
+    } else {
+        if handle_offset.x == 0.0 {
+            return Affine::default();
+        }
+        Affine::new([1.0, drag.y / handle_offset.x, 0.0, 1.0, 0.0, 0.0])
+    }
+}
+
 fn iter_handle_circles(session: &EditSession) -> impl Iterator<Item = (Quadrant, Circle)> {
     let bbox = session
         .viewport
@@ -422,17 +682,85 @@ fn update_selection_for_drag(
     prev_sel: &Selection,
     rect: Rect,
     shift: bool,
+    bases: &BaseBounds,
 ) {
-    let in_select_rect = data
-        .iter_points()
+    let points = data
+        .iter_editable_points()
         .filter(|p| rect.contains(p.to_screen(data.viewport)))
-        .map(|p| p.id)
-        .collect();
-    data.selection = if shift {
-        prev_sel.symmetric_difference(&in_select_rect)
-    } else {
-        prev_sel.union(&in_select_rect)
+        .map(|p| p.id);
+    let components = data
+        .components
+        .iter()
+        .filter(|comp| {
+            let bbox = data.viewport.rect_to_screen(comp.bounding_box(bases));
+            comp.is_editable() && rect.union(bbox) == rect
+        })
+        .map(|comp| comp.id);
+    let in_select_rect = points.chain(components).collect();
+    data.selection = combine_selection(prev_sel, &in_select_rect, shift);
+}
+
+/// Select the points and components inside the lasso with the vertices
+/// `lasso`, and the guides that pass through it.
+///
+/// `bounds` are the bounds of the lasso.
+fn update_selection_for_lasso(
+    data: &mut EditSession,
+    prev_sel: &Selection,
+    lasso: &[Point],
+    bounds: Rect,
+    shift: bool,
+    bases: &BaseBounds,
+) {
+    let path = lasso_path(lasso);
+    let contains = |pt: Point| {
+        let Rect { x0, y0, x1, y1 } = bounds;
+        pt.x >= x0 && pt.x <= x1 && pt.y >= y0 && pt.y <= y1 && path.winding(pt) != 0
     };
+    let points = data
+        .iter_editable_points()
+        .filter(|p| contains(p.to_screen(data.viewport)))
+        .map(|p| p.id);
+    let components = data
+        .components
+        .iter()
+        .filter(|comp| {
+            let Rect { x0, y0, x1, y1 } = data.viewport.rect_to_screen(comp.bounding_box(bases));
+            let corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+            comp.is_editable() && corners.iter().all(|pt| contains(Point::from(*pt)))
+        })
+        .map(|comp| comp.id);
+    let guides = data
+        .guides
+        .iter()
+        .filter(|guide| guide.crosses_screen_polygon(data.viewport, lasso))
+        .map(|guide| guide.id);
+    let in_lasso = points.chain(components).chain(guides).collect();
+    data.selection = combine_selection(prev_sel, &in_lasso, shift);
+}
+
+/// Combine the selection from before a drag with the items inside the drag's
+/// selection region: with shift held the items are toggled, and otherwise
+/// they are added.
+fn combine_selection(prev_sel: &Selection, new_sel: &Selection, shift: bool) -> Selection {
+    if shift {
+        prev_sel.symmetric_difference(new_sel)
+    } else {
+        prev_sel.union(new_sel)
+    }
+}
+
+/// The closed polygon through the vertices of a lasso.
+fn lasso_path(points: &[Point]) -> BezPath {
+    let mut path = BezPath::new();
+    if let Some((first, rest)) = points.split_first() {
+        path.move_to(*first);
+        for pt in rest {
+            path.line_to(*pt);
+        }
+        path.close_path();
+    }
+    path
 }
 
 impl Default for DragState {
@@ -450,6 +778,14 @@ impl DragState {
         }
     }
 
+    fn lasso_len(&self) -> Option<usize> {
+        if let DragState::Lasso { points, .. } = self {
+            Some(points.len())
+        } else {
+            None
+        }
+    }
+
     fn is_move(&self) -> bool {
         matches!(self, DragState::Move { .. })
     }
@@ -458,3 +794,97 @@ impl DragState {
         matches!(self, DragState::TransformSelection{ .. })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::Component;
+    use crate::data::Workspace;
+    use crate::guides::Guide;
+    use crate::path::Path;
+
+    fn square(x: f64, y: f64) -> Path {
+        Path::from_bezpath(Rect::new(x, y, x + 50., y + 50.).to_path(0.1)).unwrap()
+    }
+
+    /// A lasso with the vertices `points` in design space, in screen space,
+    /// along with its bounds.
+    fn screen_lasso(session: &EditSession, points: &[(f64, f64)]) -> (Vec<Point>, Rect) {
+        let lasso: Vec<Point> = points
+            .iter()
+            .map(|pt| session.viewport.affine() * Point::from(*pt))
+            .collect();
+        let bounds = lasso
+            .iter()
+            .fold(Rect::from_points(lasso[0], lasso[0]), |r, pt| {
+                r.union_pt(*pt)
+            });
+        (lasso, bounds)
+    }
+
+    #[test]
+    fn lasso_selects_points_components_and_guides() {
+        let mut workspace = Workspace::default();
+        let name = workspace.add_new_glyph();
+        let mut session = EditSession::new(&name, &workspace);
+        session.paste_paths(vec![square(0., 0.), square(300., 0.)]);
+        let component = Component {
+            base: "a".into(),
+            transform: Affine::translate((0., 200.)),
+            id: EntityId::new_for_component(),
+            locked: false,
+            hidden: false,
+        };
+        let component_id = component.id;
+        session.components_mut().push(component);
+        let bases = std::iter::once(("a".into(), Rect::new(0., 0., 50., 50.))).collect();
+        let crossing = Guide::horiz(DPoint::new(0., 100.));
+        let outside = Guide::vertical(DPoint::new(250., 0.));
+        let (crossing_id, outside_id) = (crossing.id, outside.id);
+        session.guides_mut().extend(vec![crossing, outside]);
+
+        // a lasso around the first square and the component
+        let (lasso, bounds) = screen_lasso(
+            &session,
+            &[(-10., -10.), (200., -10.), (200., 300.), (-10., 300.)],
+        );
+        let previous = Selection::new();
+        update_selection_for_lasso(&mut session, &previous, &lasso, bounds, false, &bases);
+
+        let first: Vec<_> = session.paths[0].points().iter().map(|p| p.id).collect();
+        assert!(first.iter().all(|id| session.selection.contains(id)));
+        assert!(session.paths[1]
+            .points()
+            .iter()
+            .all(|p| !session.selection.contains(&p.id)));
+        assert!(session.selection.contains(&component_id));
+        assert!(session.selection.contains(&crossing_id));
+        assert!(!session.selection.contains(&outside_id));
+        assert_eq!(session.selection.len(), first.len() + 2);
+
+        // with shift held, a lasso around just the first square deselects it
+        let mut previous = session.selection.clone();
+        let other_point = session.paths[1].points()[0].id;
+        previous.insert(other_point);
+        let (lasso, bounds) = screen_lasso(
+            &session,
+            &[(-10., -10.), (80., -10.), (80., 80.), (-10., 80.)],
+        );
+        update_selection_for_lasso(&mut session, &previous, &lasso, bounds, true, &bases);
+        assert!(first.iter().all(|id| !session.selection.contains(id)));
+        assert!(session.selection.contains(&component_id));
+        assert!(session.selection.contains(&crossing_id));
+        assert!(session.selection.contains(&other_point));
+        assert_eq!(session.selection.len(), 3);
+
+        // a locked component is left out
+        session.components_mut()[0].locked = true;
+        let (lasso, bounds) = screen_lasso(
+            &session,
+            &[(-10., -10.), (200., -10.), (200., 300.), (-10., 300.)],
+        );
+        let previous = Selection::new();
+        update_selection_for_lasso(&mut session, &previous, &lasso, bounds, false, &bases);
+        assert!(!session.selection.contains(&component_id));
+    }
+}
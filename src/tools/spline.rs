@@ -0,0 +1,246 @@
+//! The spline pen tool, for drawing with on-curve points only.
+
+use druid::kurbo::{BezPath, Circle, Rect, Vec2};
+use druid::{Color, Env, EventCtx, KbKey, KeyEvent, MouseEvent, PaintCtx, Point, RenderContext};
+
+use crate::design_space::DPoint;
+use crate::edit_session::{EditSession, MIN_CLICK_DISTANCE};
+use crate::mouse::{Drag, Mouse, MouseDelegate, TaggedEvent};
+use crate::path::Path;
+use crate::spline::{Spline, SplinePoint, SplinePointType};
+use crate::tools::{EditType, Tool, ToolId};
+
+const SPLINE_POINT_COLOR: Color = Color::rgb8(0x4c, 0x6e, 0xd6);
+const SPLINE_POINT_RADIUS: f64 = 4.0;
+
+/// The state of the spline pen.
+///
+/// Clicking adds a curve point to the spline being drawn, or starts a new
+/// spline; alt-clicking adds a corner. Clicking the first point closes the
+/// spline. Existing spline points can be dragged, and double-clicking one
+/// cycles through the point types.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SplinePen {
+    this_edit_type: Option<EditType>,
+    /// A description of the most recent `EditType::Normal` edit, for undo.
+    last_edit_label: &'static str,
+    /// The id of the open path we are adding points to.
+    active: Option<usize>,
+    /// The path id and index of the point being dragged.
+    dragging: Option<(usize, usize)>,
+}
+
+impl SplinePen {
+    /// Returns the path id and index of the spline point nearest `pos`,
+    /// ignoring locked and hidden paths.
+    fn hit_test(&self, data: &EditSession, pos: Point) -> Option<(usize, usize)> {
+        let vport = data.viewport;
+        data.paths
+            .iter()
+            .filter(|path| path.is_editable())
+            .filter_map(|path| path.spline().map(|spline| (path.id(), spline)))
+            .flat_map(|(id, spline)| {
+                spline
+                    .points()
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, pt)| (id, idx, pt.point.to_screen(vport).distance(pos)))
+            })
+            .filter(|(_, _, dist)| *dist <= MIN_CLICK_DISTANCE)
+            .min_by(|one, two| one.2.partial_cmp(&two.2).unwrap())
+            .map(|(id, idx, _)| (id, idx))
+    }
+
+    /// Applies `f` to the spline of the path with `id`, and solves it again.
+    fn edit_spline(data: &mut EditSession, id: usize, f: impl FnOnce(&mut Spline) -> bool) -> bool {
+        let path = match data.paths_mut().iter_mut().find(|p| p.id() == id) {
+            Some(path) => path,
+            None => return false,
+        };
+        let mut spline = match path.spline() {
+            Some(spline) => spline.clone(),
+            None => return false,
+        };
+        if !f(&mut spline) {
+            return false;
+        }
+        path.set_spline(spline);
+        true
+    }
+
+    /// The open spline we are adding points to, if it still exists.
+    fn active_spline<'a>(&self, data: &'a EditSession) -> Option<&'a Spline> {
+        let id = self.active?;
+        data.paths
+            .iter()
+            .find(|p| p.id() == id && p.is_editable())
+            .and_then(Path::spline)
+            .filter(|spline| !spline.is_closed())
+    }
+}
+
+impl MouseDelegate<EditSession> for SplinePen {
+    fn cancel(&mut self, _data: &mut EditSession) {
+        self.active = None;
+        self.dragging = None;
+    }
+
+    fn left_down(&mut self, event: &MouseEvent, data: &mut EditSession) {
+        self.dragging = None;
+        let hit = self.hit_test(data, event.pos);
+
+        if event.count == 2 {
+            if let Some((id, idx)) = hit {
+                let changed = Self::edit_spline(data, id, |spline| {
+                    let typ = spline.points()[idx].typ.next();
+                    spline.set_point_type(idx, typ);
+                    true
+                });
+                if changed {
+                    self.this_edit_type = Some(EditType::Normal);
+                    self.last_edit_label = "Change point type";
+                }
+            }
+            return;
+        } else if event.count != 1 {
+            return;
+        }
+
+        let active_len = self.active_spline(data).map(|s| s.points().len());
+        match (hit, self.active, active_len) {
+            (Some((id, 0)), Some(active), Some(len)) if id == active && len > 1 => {
+                Self::edit_spline(data, id, |spline| {
+                    spline.close();
+                    true
+                });
+                self.active = None;
+                self.this_edit_type = Some(EditType::Normal);
+                self.last_edit_label = "Close spline";
+            }
+            (Some(hit), _, _) => self.dragging = Some(hit),
+            (None, active, active_len) => {
+                let typ = if event.mods.alt() {
+                    SplinePointType::Corner
+                } else {
+                    SplinePointType::Curve
+                };
+                let point = SplinePoint::new(DPoint::from_screen(event.pos, data.viewport), typ);
+                match (active, active_len) {
+                    (Some(id), Some(_)) => {
+                        if !Self::edit_spline(data, id, |spline| spline.push(point)) {
+                            return;
+                        }
+                    }
+                    _ => {
+                        let path = Path::from_spline(Spline::new(vec![point], false));
+                        self.active = Some(path.id());
+                        data.paths_mut().push(path);
+                    }
+                }
+                data.selection.clear();
+                self.this_edit_type = Some(EditType::Normal);
+                self.last_edit_label = "Add spline point";
+            }
+        }
+    }
+
+    fn left_drag_changed(&mut self, drag: Drag, data: &mut EditSession) {
+        if let Some((id, idx)) = self.dragging {
+            let point = DPoint::from_screen(drag.current.pos, data.viewport);
+            if Self::edit_spline(data, id, |spline| spline.move_point(idx, point)) {
+                self.this_edit_type = Some(EditType::Drag);
+            }
+        }
+    }
+
+    fn left_drag_ended(&mut self, _: Drag, _: &mut EditSession) {
+        if self.dragging.take().is_some() {
+            self.this_edit_type = Some(EditType::DragUp);
+        }
+    }
+}
+
+impl Tool for SplinePen {
+    fn mouse_event(
+        &mut self,
+        event: TaggedEvent,
+        mouse: &mut Mouse,
+        ctx: &mut EventCtx,
+        data: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        assert!(self.this_edit_type.is_none());
+        mouse.mouse_event(event, data, self);
+        if self.this_edit_type.is_some() {
+            ctx.request_paint();
+        }
+        self.this_edit_type.take()
+    }
+
+    fn key_down(
+        &mut self,
+        event: &KeyEvent,
+        ctx: &mut EventCtx,
+        _data: &mut EditSession,
+        _: &Env,
+    ) -> Option<EditType> {
+        if event.key == KbKey::Escape || event.key == KbKey::Enter {
+            self.active = None;
+            ctx.request_paint();
+        }
+        None
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &EditSession, _env: &Env) {
+        let editable = data.paths.iter().filter(|path| path.is_editable());
+        for spline in editable.filter_map(Path::spline) {
+            for pt in spline.points() {
+                let center = pt.point.to_screen(data.viewport);
+                paint_spline_point(ctx, center, pt.typ);
+            }
+        }
+    }
+
+    fn edit_label(&self, edit: EditType, _: &EditSession) -> String {
+        match edit {
+            EditType::Drag | EditType::DragUp => "Drag spline point".into(),
+            _ => self.last_edit_label.into(),
+        }
+    }
+
+    fn name(&self) -> ToolId {
+        "Spline"
+    }
+}
+
+/// Each type of point has its own shape: circles for curve points (filled
+/// for G4), squares for corners, and half-diamonds for tangents, pointing
+/// towards the curve.
+fn paint_spline_point(ctx: &mut PaintCtx, center: Point, typ: SplinePointType) {
+    let r = SPLINE_POINT_RADIUS;
+    match typ {
+        SplinePointType::Curve => {
+            ctx.stroke(Circle::new(center, r), &SPLINE_POINT_COLOR, 1.5);
+        }
+        SplinePointType::G4 => {
+            ctx.fill(Circle::new(center, r), &SPLINE_POINT_COLOR);
+        }
+        SplinePointType::Corner => {
+            let rect = Rect::from_center_size(center, (r * 2.0, r * 2.0));
+            ctx.stroke(rect, &SPLINE_POINT_COLOR, 1.5);
+        }
+        SplinePointType::LeftTangent | SplinePointType::RightTangent => {
+            let dir = if typ == SplinePointType::LeftTangent {
+                1.0
+            } else {
+                -1.0
+            };
+            let mut bez = BezPath::new();
+            bez.move_to(center + Vec2::new(0.0, -r * 1.5));
+            bez.line_to(center + Vec2::new(dir * r * 1.5, 0.0));
+            bez.line_to(center + Vec2::new(0.0, r * 1.5));
+            bez.close_path();
+            ctx.fill(bez, &SPLINE_POINT_COLOR);
+        }
+    }
+}
@@ -1,68 +1,439 @@
 //! Managing undo state
+//!
+//! Rather than keeping a copy of every state, we keep only the current state
+//! along with a series of reversible diffs; undoing reverts the most recent
+//! diff, and redoing reapplies it. Old diffs are discarded when the total
+//! size of the stack exceeds a memory budget.
 
 use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-// for no good reason
-const DEFAULT_UNDO_STACK_SIZE: usize = 128;
+use crate::edit_session::EditSession;
+
+/// The default memory budget for a single undo stack, in bytes.
+pub(crate) const DEFAULT_UNDO_MEMORY_BUDGET: usize = 16 * 1024 * 1024;
+
+/// A type that can be stored in an `UndoState`.
+pub(crate) trait Undoable: Clone {
+    /// A reversible description of the changes between two states.
+    type Diff;
+
+    /// Returns the changes required to turn `self` into `new`.
+    fn diff(&self, new: &Self) -> Self::Diff;
+
+    /// Apply a diff previously created with `diff`.
+    fn apply_diff(&mut self, diff: &Self::Diff);
+
+    /// Undo the changes in a diff previously created with `diff`.
+    fn revert_diff(&mut self, diff: &Self::Diff);
+
+    /// An estimate of the memory retained by `diff`, in bytes.
+    fn diff_size(diff: &Self::Diff) -> usize;
+}
 
 /// A stack of states that can be undone and redone.
-#[derive(Debug)]
-pub(crate) struct UndoState<T> {
-    max_undo_count: usize,
-    stack: VecDeque<T>,
-    /// The index in `stack` of the current document.
+pub(crate) struct UndoState<T: Undoable> {
+    memory_budget: usize,
+    /// The state at `live_index`.
+    current: T,
+    /// `groups[i]` is the change between state `i` and state `i + 1`.
+    groups: VecDeque<UndoGroup<T::Diff>>,
+    /// The label of the oldest available state.
+    base_label: String,
+    /// The index of the current state. This is in the range `0..=groups.len()`.
     live_index: usize,
+    /// The sum of the sizes of all groups.
+    total_size: usize,
+    /// The state before the most recent group, while that group is being
+    /// updated with `update_current_undo`; the group's diff is out of date
+    /// until `settle` is called.
+    pending_base: Option<T>,
+}
+
+/// A single entry in the undo stack.
+#[derive(Debug)]
+struct UndoGroup<D> {
+    /// A human-readable description of the edit that produced this state.
+    label: String,
+    diff: D,
+    size: usize,
+}
+
+/// The undo state of an `EditSession`.
+///
+/// This is owned by the `Workspace`, so that history outlives any editor
+/// window; the editor for the session holds a clone of this handle while
+/// it is open.
+#[derive(Clone)]
+pub struct SessionUndo(Arc<Mutex<UndoState<EditSession>>>);
+
+/// A summary of an undo stack, suitable for display.
+#[derive(Debug, Clone, Default)]
+pub struct UndoHistory {
+    /// The label of each undo group, oldest first.
+    pub labels: Vec<String>,
+    /// The index in `labels` of the current state.
+    pub live_index: usize,
 }
 
-impl<T> UndoState<T> {
+impl<T: Undoable> UndoState<T> {
+    /// The label used for the initial state.
+    const INITIAL_LABEL: &'static str = "Open";
+
     pub(crate) fn new(init_state: T) -> Self {
-        Self::new_sized(DEFAULT_UNDO_STACK_SIZE, init_state)
+        Self::with_memory_budget(DEFAULT_UNDO_MEMORY_BUDGET, init_state)
     }
 
-    fn new_sized(max_undo_count: usize, init_state: T) -> Self {
-        let mut stack = VecDeque::new();
-        stack.push_back(init_state);
+    /// Create a new `UndoState` that will discard old undo groups when the
+    /// total size of the stack exceeds `memory_budget` bytes.
+    ///
+    /// The most recent undo group is always kept, regardless of its size.
+    pub(crate) fn with_memory_budget(memory_budget: usize, init_state: T) -> Self {
         UndoState {
-            max_undo_count,
-            stack,
+            memory_budget,
+            current: init_state,
+            groups: VecDeque::new(),
+            base_label: Self::INITIAL_LABEL.into(),
             live_index: 0,
+            total_size: 0,
+            pending_base: None,
         }
     }
 
+    /// The approximate memory used by the stored undo groups, in bytes.
+    #[cfg(test)]
+    pub(crate) fn memory_usage(&mut self) -> usize {
+        self.settle();
+        self.total_size
+    }
+
     pub(crate) fn undo(&mut self) -> Option<&T> {
+        self.settle();
         if self.live_index == 0 {
             return None;
         }
         self.live_index -= 1;
-        self.stack.get(self.live_index)
+        self.current.revert_diff(&self.groups[self.live_index].diff);
+        Some(&self.current)
     }
 
     pub(crate) fn redo(&mut self) -> Option<&T> {
-        if self.live_index == self.stack.len() - 1 {
+        self.settle();
+        if self.live_index == self.groups.len() {
             return None;
         }
+        self.current.apply_diff(&self.groups[self.live_index].diff);
         self.live_index += 1;
-        self.stack.get(self.live_index)
+        Some(&self.current)
     }
 
-    pub(crate) fn add_undo_group(&mut self, item: T) {
-        if self.live_index < self.stack.len() - 1 {
-            self.stack.truncate(self.live_index + 1);
+    /// Make the state at `index` the current state, as if by repeatedly
+    /// calling `undo` or `redo`.
+    ///
+    /// Returns `None` if `index` is out of bounds or is already current.
+    pub(crate) fn jump_to(&mut self, index: usize) -> Option<&T> {
+        self.settle();
+        if index > self.groups.len() || index == self.live_index {
+            return None;
         }
-
-        self.live_index += 1;
-        self.stack.push_back(item);
-
-        if self.stack.len() > self.max_undo_count {
-            self.stack.pop_front();
+        while self.live_index > index {
             self.live_index -= 1;
+            self.current.revert_diff(&self.groups[self.live_index].diff);
         }
+        while self.live_index < index {
+            self.current.apply_diff(&self.groups[self.live_index].diff);
+            self.live_index += 1;
+        }
+        Some(&self.current)
+    }
+
+    /// Add a new undo group, described by `label`.
+    ///
+    /// Any states that could previously have been redone are discarded.
+    pub(crate) fn add_undo_group(&mut self, item: T, label: impl Into<String>) {
+        self.settle();
+        while self.groups.len() > self.live_index {
+            let group = self.groups.pop_back().unwrap();
+            self.total_size -= group.size;
+        }
+
+        let diff = self.current.diff(&item);
+        let size = T::diff_size(&diff);
+        self.groups.push_back(UndoGroup {
+            label: label.into(),
+            diff,
+            size,
+        });
+        self.total_size += size;
+        self.current = item;
+        self.live_index += 1;
+        self.enforce_memory_budget();
     }
 
     /// Modify the state for the currently active undo group.
     /// This might be done if an edit occurs that combines with the previous undo,
     /// or if we want to save selection state.
+    ///
+    /// The label of the current group is unchanged. The group's diff is only
+    /// recomputed when it is next needed, so this is cheap to call for each
+    /// step of a drag.
     pub(crate) fn update_current_undo(&mut self, mut f: impl FnMut(&mut T)) {
-        f(self.stack.get_mut(self.live_index).unwrap())
+        if self.live_index > 0 && self.pending_base.is_none() {
+            let mut base = self.current.clone();
+            base.revert_diff(&self.groups[self.live_index - 1].diff);
+            self.pending_base = Some(base);
+        }
+        f(&mut self.current);
+    }
+
+    /// Bring the diff of the group modified by `update_current_undo` up to date.
+    fn settle(&mut self) {
+        if let Some(base) = self.pending_base.take() {
+            let group = &mut self.groups[self.live_index - 1];
+            group.diff = base.diff(&self.current);
+            self.total_size -= group.size;
+            group.size = T::diff_size(&group.diff);
+            self.total_size += group.size;
+        }
+    }
+
+    /// The current state.
+    pub(crate) fn current(&self) -> &T {
+        &self.current
+    }
+
+    /// Returns the labels of all undo groups, along with the current position.
+    pub(crate) fn history(&self) -> UndoHistory {
+        let labels = std::iter::once(self.base_label.clone())
+            .chain(self.groups.iter().map(|group| group.label.clone()))
+            .collect();
+        UndoHistory {
+            labels,
+            live_index: self.live_index,
+        }
+    }
+
+    /// Returns every state in the stack along with its label, oldest first,
+    /// and the index of the current state.
+    pub(crate) fn states(&mut self) -> (Vec<(String, T)>, usize) {
+        self.settle();
+        let mut states = Vec::with_capacity(self.groups.len() + 1);
+        let mut state = self.current.clone();
+        for group in self.groups.iter().take(self.live_index).rev() {
+            state.revert_diff(&group.diff);
+            states.push(state.clone());
+        }
+        states.reverse();
+        states.push(self.current.clone());
+
+        let mut state = self.current.clone();
+        for group in self.groups.iter().skip(self.live_index) {
+            state.apply_diff(&group.diff);
+            states.push(state.clone());
+        }
+
+        let labels = std::iter::once(self.base_label.clone())
+            .chain(self.groups.iter().map(|group| group.label.clone()));
+        (labels.zip(states).collect(), self.live_index)
+    }
+
+    /// Construct an `UndoState` from a list of states and their labels, as
+    /// returned by `states`.
+    ///
+    /// Returns `None` if `states` is empty or `live_index` is out of bounds.
+    pub(crate) fn from_states(states: Vec<(String, T)>, live_index: usize) -> Option<Self> {
+        Self::from_states_with_memory_budget(DEFAULT_UNDO_MEMORY_BUDGET, states, live_index)
+    }
+
+    /// Construct an `UndoState` from a list of states, as with `from_states`,
+    /// and then discard the oldest undo groups that do not fit in
+    /// `memory_budget`.
+    fn from_states_with_memory_budget(
+        memory_budget: usize,
+        states: Vec<(String, T)>,
+        live_index: usize,
+    ) -> Option<Self> {
+        if live_index >= states.len() {
+            return None;
+        }
+        let mut states = states.into_iter();
+        let (base_label, init_state) = states.next()?;
+        // discarding groups while we build the stack would shift the states
+        // out from under `live_index`, so we only do so once it is current.
+        let mut undo = UndoState::with_memory_budget(usize::MAX, init_state);
+        undo.base_label = base_label;
+        for (label, state) in states {
+            undo.add_undo_group(state, label);
+        }
+        undo.jump_to(live_index);
+        undo.memory_budget = memory_budget;
+        undo.enforce_memory_budget();
+        Some(undo)
+    }
+
+    /// Discard the oldest undo groups until we are under our memory budget.
+    fn enforce_memory_budget(&mut self) {
+        // we never discard the current state's group or anything after it
+        while self.total_size > self.memory_budget && self.live_index > 1 {
+            let group = self.groups.pop_front().unwrap();
+            self.total_size -= group.size;
+            self.base_label = group.label;
+            self.live_index -= 1;
+        }
+    }
+}
+
+impl SessionUndo {
+    pub(crate) fn new(session: EditSession) -> Self {
+        SessionUndo::from_undo_state(UndoState::new(session))
+    }
+
+    pub(crate) fn from_undo_state(undo: UndoState<EditSession>) -> Self {
+        SessionUndo(Arc::new(Mutex::new(undo)))
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<UndoState<EditSession>> {
+        self.0.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trivial diff that stores both states.
+    impl Undoable for i32 {
+        type Diff = (i32, i32);
+
+        fn diff(&self, new: &Self) -> Self::Diff {
+            (*self, *new)
+        }
+
+        fn apply_diff(&mut self, diff: &Self::Diff) {
+            assert_eq!(*self, diff.0);
+            *self = diff.1;
+        }
+
+        fn revert_diff(&mut self, diff: &Self::Diff) {
+            assert_eq!(*self, diff.1);
+            *self = diff.0;
+        }
+
+        fn diff_size(_: &Self::Diff) -> usize {
+            std::mem::size_of::<Self::Diff>()
+        }
+    }
+
+    #[test]
+    fn labels_follow_undo_groups() {
+        let mut undo = UndoState::new(0);
+        undo.add_undo_group(1, "one");
+        undo.add_undo_group(2, "two");
+        undo.update_current_undo(|state| *state = 3);
+
+        let history = undo.history();
+        assert_eq!(history.labels, vec!["Open", "one", "two"]);
+        assert_eq!(history.live_index, 2);
+        assert_eq!(undo.undo(), Some(&1));
+        assert_eq!(undo.redo(), Some(&3));
+    }
+
+    #[test]
+    fn updates_combine_with_current_group() {
+        let mut undo = UndoState::new(0);
+        undo.add_undo_group(1, "one");
+        for i in 2..10 {
+            undo.update_current_undo(|state| *state = i);
+        }
+        assert_eq!(undo.current(), &9);
+        undo.add_undo_group(10, "ten");
+
+        assert_eq!(undo.undo(), Some(&9));
+        assert_eq!(undo.undo(), Some(&0));
+        assert_eq!(undo.redo(), Some(&9));
+        assert_eq!(undo.memory_usage(), 2 * std::mem::size_of::<(i32, i32)>());
+    }
+
+    #[test]
+    fn jump_to() {
+        let mut undo = UndoState::new(0);
+        undo.add_undo_group(1, "one");
+        undo.add_undo_group(2, "two");
+        undo.add_undo_group(3, "three");
+
+        assert_eq!(undo.jump_to(1), Some(&1));
+        assert_eq!(undo.jump_to(1), None);
+        assert_eq!(undo.jump_to(4), None);
+        assert_eq!(undo.jump_to(3), Some(&3));
+        assert_eq!(undo.jump_to(0), Some(&0));
+
+        // a new edit discards the redo states
+        undo.add_undo_group(4, "four");
+        let history = undo.history();
+        assert_eq!(history.labels, vec!["Open", "four"]);
+        assert_eq!(history.live_index, 1);
+        assert_eq!(undo.undo(), Some(&0));
+        assert_eq!(undo.undo(), None);
+    }
+
+    #[test]
+    fn memory_budget_is_respected() {
+        let group_size = std::mem::size_of::<(i32, i32)>();
+        let mut undo = UndoState::with_memory_budget(group_size * 2, 0);
+        for i in 1..6 {
+            undo.add_undo_group(i, i.to_string());
+        }
+        assert_eq!(undo.memory_usage(), group_size * 2);
+        let history = undo.history();
+        assert_eq!(history.labels, vec!["3", "4", "5"]);
+        assert_eq!(history.live_index, 2);
+        assert_eq!(undo.undo(), Some(&4));
+        assert_eq!(undo.undo(), Some(&3));
+        assert_eq!(undo.undo(), None);
+
+        // even with no budget the most recent group is kept
+        let mut undo = UndoState::with_memory_budget(0, 0);
+        undo.add_undo_group(1, "one");
+        undo.add_undo_group(2, "two");
+        assert_eq!(undo.history().labels, vec!["one", "two"]);
+        assert_eq!(undo.undo(), Some(&1));
+        assert_eq!(undo.undo(), None);
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let mut undo = UndoState::new(0);
+        undo.add_undo_group(1, "one");
+        undo.add_undo_group(2, "two");
+        undo.add_undo_group(3, "three");
+        undo.jump_to(1);
+
+        let (states, live_index) = undo.states();
+        assert_eq!(live_index, 1);
+        let values: Vec<_> = states.iter().map(|(_, state)| *state).collect();
+        assert_eq!(values, vec![0, 1, 2, 3]);
+
+        let mut restored = UndoState::from_states(states, live_index).unwrap();
+        assert_eq!(restored.history().labels, undo.history().labels);
+        assert_eq!(restored.history().live_index, 1);
+        assert_eq!(restored.redo(), Some(&2));
+        assert!(UndoState::<i32>::from_states(Vec::new(), 0).is_none());
+    }
+
+    #[test]
+    fn restored_states_respect_memory_budget() {
+        let group_size = std::mem::size_of::<(i32, i32)>();
+        let states: Vec<_> = (0..6).map(|i| (i.to_string(), i)).collect();
+
+        let mut restored =
+            UndoState::from_states_with_memory_budget(group_size * 2, states, 3).unwrap();
+        assert_eq!(restored.current(), &3);
+        let history = restored.history();
+        // the groups after the live state are kept, whatever the budget
+        assert_eq!(history.labels, vec!["2", "3", "4", "5"]);
+        assert_eq!(history.live_index, 1);
+        assert_eq!(restored.undo(), Some(&2));
+        assert_eq!(restored.undo(), None);
+        assert_eq!(restored.jump_to(3), Some(&5));
     }
 }
@@ -0,0 +1,487 @@
+//! Saving undo history to disk.
+//!
+//! When enabled, the undo history of every edited glyph is written to a
+//! sidecar file in the font's backup directory each time the font is saved.
+//! When a glyph is next opened, its history is restored, provided the glyph
+//! has not been changed since the history was written.
+//!
+//! We store the outline, components and advance of each state, including
+//! which contours and components are locked or hidden and the splines of
+//! spline contours; guides and selection are not part of the saved history.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path as FsPath, PathBuf};
+
+use druid::kurbo::Affine;
+use norad::glyph::Advance;
+use norad::GlyphName;
+
+use crate::component::Component;
+use crate::data;
+use crate::design_space::DPoint;
+use crate::edit_session::EditSession;
+use crate::path::{EntityId, Path, PathPoint, PointType};
+use crate::spline::{Spline, SplinePoint, SplinePointType};
+use crate::undo::{SessionUndo, UndoState};
+
+const UNDO_FILE_NAME: &str = "undo_history.plist";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UndoFile {
+    glyphs: HashMap<String, GlyphHistory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GlyphHistory {
+    live_index: usize,
+    states: Vec<SavedState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedState {
+    label: String,
+    #[serde(flatten)]
+    outline: SavedOutline,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedOutline {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    advance: Option<(f64, f64)>,
+    contours: Vec<SavedContour>,
+    components: Vec<SavedComponent>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedContour {
+    closed: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    hidden: bool,
+    points: Vec<SavedPoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spline: Option<SavedSpline>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedPoint {
+    x: f64,
+    y: f64,
+    typ: SavedPointType,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedSpline {
+    closed: bool,
+    points: Vec<SavedSplinePoint>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedSplinePoint {
+    x: f64,
+    y: f64,
+    /// The type of the point, as it is named in the glyph lib.
+    typ: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum SavedPointType {
+    OnCurve,
+    OnCurveSmooth,
+    OnCurveTangent,
+    OffCurve,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SavedComponent {
+    base: String,
+    transform: [f64; 6],
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    hidden: bool,
+}
+
+/// The ids used when restoring the states of one glyph's history.
+///
+/// Contours and components are matched up by index, so that the same contour
+/// has the same ids in every state, and a contour that is unchanged from the
+/// last state restored is shared outright. This keeps the diffs between
+/// restored states as small as those between the original states.
+struct RestoredIds {
+    /// The path most recently restored at each index.
+    paths: Vec<Path>,
+    components: Vec<EntityId>,
+}
+
+/// The location of the undo history for the font at `font_path`.
+///
+/// This lives next to the backups created when the font is saved.
+fn undo_file_path(font_path: &FsPath) -> PathBuf {
+    data::backup_dir(font_path).join(UNDO_FILE_NAME)
+}
+
+/// Returns `true` if undo history has been saved for the font at `font_path`.
+///
+/// This is how we remember, between sessions, that history should be kept.
+pub(crate) fn exists(font_path: &FsPath) -> bool {
+    undo_file_path(font_path).exists()
+}
+
+/// Delete any undo history saved for the font at `font_path`.
+pub(crate) fn remove(font_path: &FsPath) -> Result<(), std::io::Error> {
+    let path = undo_file_path(font_path);
+    if path.exists() {
+        log::info!("removing saved undo history at {:?}", path);
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Write the undo history for the given sessions.
+///
+/// Any previously saved history for glyphs not in `sessions` is preserved.
+pub(crate) fn save<'a>(
+    font_path: &FsPath,
+    sessions: impl Iterator<Item = (&'a GlyphName, &'a SessionUndo)>,
+) -> Result<(), Box<dyn Error>> {
+    let path = undo_file_path(font_path);
+    let mut file = read_undo_file(&path).unwrap_or_default();
+    for (name, undo) in sessions {
+        let (states, live_index) = undo.lock().states();
+        let states = states
+            .iter()
+            .map(|(label, session)| SavedState {
+                label: label.clone(),
+                outline: SavedOutline::from_session(session),
+            })
+            .collect();
+        file.glyphs
+            .insert(name.to_string(), GlyphHistory { live_index, states });
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    plist::to_writer_xml(File::create(&path)?, &file)?;
+    Ok(())
+}
+
+/// Attempt to load the saved undo history for `session`.
+///
+/// Returns `None` if there is no saved history, or if the current state of
+/// the saved history does not match `session`.
+pub(crate) fn load(font_path: &FsPath, session: &EditSession) -> Option<UndoState<EditSession>> {
+    let mut file = read_undo_file(&undo_file_path(font_path))?;
+    let history = file.glyphs.remove(&*session.name)?;
+    let live_state = history.states.get(history.live_index)?;
+    if live_state.outline != SavedOutline::from_session(session) {
+        log::info!("discarding stale undo history for '{}'", session.name);
+        return None;
+    }
+
+    let live_index = history.live_index;
+    let mut ids = RestoredIds::new(session);
+    let states = history
+        .states
+        .into_iter()
+        .enumerate()
+        .map(|(i, state)| {
+            // the live state should be exactly the session, including ids,
+            // so that subsequent diffs are small.
+            let restored = if i == live_index {
+                session.clone()
+            } else {
+                state.outline.to_session(session, &mut ids)
+            };
+            (state.label, restored)
+        })
+        .collect();
+    UndoState::from_states(states, live_index)
+}
+
+fn read_undo_file(path: &FsPath) -> Option<UndoFile> {
+    if !path.exists() {
+        return None;
+    }
+    File::open(path)
+        .map_err(|e| Box::new(e) as Box<dyn Error>)
+        .and_then(|file| plist::from_reader(file).map_err(|e| Box::new(e) as Box<dyn Error>))
+        .map_err(|e| log::warn!("failed to read undo history at {:?}: '{}'", path, e))
+        .ok()
+}
+
+impl SavedOutline {
+    fn from_session(session: &EditSession) -> Self {
+        let advance = session
+            .glyph
+            .advance
+            .as_ref()
+            .map(|adv| (adv.width as f64, adv.height as f64));
+        let contours = session.paths.iter().map(SavedContour::from_path).collect();
+        let components = session
+            .components
+            .iter()
+            .map(|comp| SavedComponent {
+                base: comp.base.to_string(),
+                transform: comp.transform.as_coeffs(),
+                locked: comp.locked,
+                hidden: comp.hidden,
+            })
+            .collect();
+        SavedOutline {
+            advance,
+            contours,
+            components,
+        }
+    }
+
+    /// Create a session with this outline, using `base` for everything else.
+    fn to_session(&self, base: &EditSession, ids: &mut RestoredIds) -> EditSession {
+        let mut session = base.clone();
+        session.selection.clear();
+        *session.paths_mut() = self
+            .contours
+            .iter()
+            .filter(|contour| !contour.points.is_empty())
+            .enumerate()
+            .map(|(idx, contour)| ids.path(idx, contour))
+            .collect();
+        *session.components_mut() = self
+            .components
+            .iter()
+            .enumerate()
+            .map(|(idx, comp)| Component {
+                base: comp.base.as_str().into(),
+                transform: Affine::new(comp.transform),
+                id: ids.component(idx),
+                locked: comp.locked,
+                hidden: comp.hidden,
+            })
+            .collect();
+        let glyph = std::sync::Arc::make_mut(&mut session.glyph);
+        glyph.advance = self.advance.map(|(width, height)| Advance {
+            width: width as f32,
+            height: height as f32,
+        });
+        session.rebuild_glyph();
+        session
+    }
+}
+
+impl SavedContour {
+    fn from_path(path: &Path) -> Self {
+        SavedContour {
+            closed: path.is_closed(),
+            locked: path.is_locked(),
+            hidden: path.is_hidden(),
+            points: path
+                .points()
+                .iter()
+                .map(|pt| SavedPoint {
+                    x: pt.point.x,
+                    y: pt.point.y,
+                    typ: pt.typ.into(),
+                })
+                .collect(),
+            spline: path.spline().map(SavedSpline::from_spline),
+        }
+    }
+}
+
+impl SavedSpline {
+    fn from_spline(spline: &Spline) -> Self {
+        SavedSpline {
+            closed: spline.is_closed(),
+            points: spline
+                .points()
+                .iter()
+                .map(|pt| SavedSplinePoint {
+                    x: pt.point.x,
+                    y: pt.point.y,
+                    typ: pt.typ.to_str().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn to_spline(&self) -> Option<Spline> {
+        let points = self
+            .points
+            .iter()
+            .map(|pt| {
+                let typ = SplinePointType::from_str(&pt.typ)?;
+                Some(SplinePoint::new(DPoint::from_raw((pt.x, pt.y)), typ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if points.is_empty() {
+            return None;
+        }
+        Some(Spline::new(points, self.closed))
+    }
+}
+
+impl RestoredIds {
+    /// Start from the ids of the live state, which is restored as is.
+    fn new(live: &EditSession) -> Self {
+        RestoredIds {
+            paths: live.paths.to_vec(),
+            components: live.components.iter().map(|comp| comp.id).collect(),
+        }
+    }
+
+    /// The path for `contour`, the contour at `idx`.
+    fn path(&mut self, idx: usize, contour: &SavedContour) -> Path {
+        let prev = self.paths.get(idx);
+        if let Some(prev) = prev {
+            if SavedContour::from_path(prev) == *contour {
+                return prev.clone();
+            }
+        }
+        let path_id = prev.map(Path::id).unwrap_or_else(crate::path::next_id);
+        let points = contour
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, pt)| PathPoint {
+                id: prev
+                    .and_then(|prev| prev.points().get(i))
+                    .map(|prev| prev.id)
+                    .unwrap_or_else(|| EntityId::new_with_parent(path_id)),
+                point: DPoint::from_raw((pt.x, pt.y)),
+                typ: pt.typ.into(),
+            })
+            .collect();
+        let mut path = Path::from_raw_parts(path_id, points, None, contour.closed);
+        path.set_locked(contour.locked);
+        path.set_hidden(contour.hidden);
+        if let Some(spline) = contour.spline.as_ref().and_then(SavedSpline::to_spline) {
+            path.attach_spline(spline);
+        }
+        if idx < self.paths.len() {
+            self.paths[idx] = path.clone();
+        } else {
+            self.paths.push(path.clone());
+        }
+        path
+    }
+
+    /// The id for the component at `idx`.
+    fn component(&mut self, idx: usize) -> EntityId {
+        while self.components.len() <= idx {
+            self.components.push(EntityId::new_for_component());
+        }
+        self.components[idx]
+    }
+}
+
+impl From<PointType> for SavedPointType {
+    fn from(src: PointType) -> SavedPointType {
+        match src {
+            PointType::OnCurve => SavedPointType::OnCurve,
+            PointType::OnCurveSmooth => SavedPointType::OnCurveSmooth,
+            PointType::OnCurveTangent => SavedPointType::OnCurveTangent,
+            PointType::OffCurve => SavedPointType::OffCurve,
+        }
+    }
+}
+
+impl From<SavedPointType> for PointType {
+    fn from(src: SavedPointType) -> PointType {
+        match src {
+            SavedPointType::OnCurve => PointType::OnCurve,
+            SavedPointType::OnCurveSmooth => PointType::OnCurveSmooth,
+            SavedPointType::OnCurveTangent => PointType::OnCurveTangent,
+            SavedPointType::OffCurve => PointType::OffCurve,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Workspace;
+    use druid::kurbo::{BezPath, Shape};
+    use druid::Data;
+
+    #[test]
+    fn outline_round_trip() {
+        let mut workspace = Workspace::default();
+        let name = workspace.add_new_glyph();
+        let mut session = EditSession::new(&name, &workspace);
+        let mut bez = BezPath::new();
+        bez.move_to((10.0, 10.0));
+        bez.curve_to((20.0, 40.0), (60.0, 40.0), (70.0, 10.0));
+        bez.close_path();
+        let mut locked = Path::from_bezpath(bez).unwrap();
+        locked.set_locked(true);
+        let spline = Spline::new(
+            vec![
+                SplinePoint::new(DPoint::new(0., 0.), SplinePointType::Corner),
+                SplinePoint::new(DPoint::new(100., 50.), SplinePointType::Curve),
+                SplinePoint::new(DPoint::new(200., 0.), SplinePointType::Corner),
+            ],
+            false,
+        );
+        let mut hidden = Path::from_spline(spline.clone());
+        hidden.set_hidden(true);
+        session.paste_paths(vec![locked, hidden]);
+        session.components_mut().push(Component {
+            base: "a".into(),
+            transform: Affine::translate((10., 0.)),
+            id: EntityId::new_for_component(),
+            locked: true,
+            hidden: true,
+        });
+
+        let saved = SavedOutline::from_session(&session);
+        let restored = saved.to_session(&session, &mut RestoredIds::new(&session));
+        assert_eq!(SavedOutline::from_session(&restored), saved);
+        assert!(restored.selection.is_empty());
+        assert!(restored.paths[0].is_locked());
+        assert!(restored.paths[1].is_hidden());
+        assert_eq!(restored.paths[1].spline(), Some(&spline));
+        assert!(restored.components[0].locked && restored.components[0].hidden);
+
+        let mut data = Vec::new();
+        plist::to_writer_xml(&mut data, &saved).unwrap();
+        let reloaded: SavedOutline = plist::from_reader(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(reloaded, saved);
+    }
+
+    #[test]
+    fn restored_states_share_ids() {
+        let mut workspace = Workspace::default();
+        let name = workspace.add_new_glyph();
+        let mut session = EditSession::new(&name, &workspace);
+        let paths = (0..2)
+            .map(|i| {
+                let x = i as f64 * 100.;
+                let rect = druid::kurbo::Rect::new(x, 0., x + 50., 50.);
+                Path::from_bezpath(rect.to_path(0.1)).unwrap()
+            })
+            .collect();
+        session.paste_paths(paths);
+
+        // an earlier state, where one point of the first contour was elsewhere
+        let mut saved = SavedOutline::from_session(&session);
+        saved.contours[0].points[0].x += 10.;
+        let mut ids = RestoredIds::new(&session);
+        let one = saved.to_session(&session, &mut ids);
+        let two = saved.to_session(&session, &mut ids);
+
+        for restored in &[&one, &two] {
+            for (path, live) in restored.paths.iter().zip(session.paths.iter()) {
+                assert_eq!(path.id(), live.id());
+                let point_ids = path.points().iter().map(|pt| pt.id);
+                assert!(point_ids.eq(live.points().iter().map(|pt| pt.id)));
+            }
+        }
+        assert!(one.paths[0].same(&two.paths[0]));
+        assert!(one.paths[1].same(&session.paths[1]));
+        assert!(!one.paths[0].same(&session.paths[0]));
+    }
+}
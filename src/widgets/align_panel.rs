@@ -0,0 +1,96 @@
+//! A panel for aligning and distributing the selected contours and
+//! components.
+//!
+//! Unlike the other panels, this stays open after an operation, so that
+//! several can be applied in turn.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, CrossAxisAlignment, Flex, RadioGroup};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::setting;
+use crate::align::{AlignOp, AlignSettings, AlignTarget, Alignment, Axis, Spacing};
+use crate::consts;
+use crate::data::Workspace;
+use crate::widgets::ModalHost;
+
+pub fn align_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting(
+            "Align to:",
+            target_picker().lens(AlignSettings::target),
+        ))
+        .with_default_spacer()
+        .with_child(setting(
+            "Align:",
+            Flex::column()
+                .with_child(
+                    Flex::row()
+                        .with_child(op_button("Left", AlignOp::Align(Alignment::Left)))
+                        .with_child(op_button("Center", AlignOp::Align(Alignment::Center)))
+                        .with_child(op_button("Right", AlignOp::Align(Alignment::Right))),
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(op_button("Top", AlignOp::Align(Alignment::Top)))
+                        .with_child(op_button("Middle", AlignOp::Align(Alignment::Middle)))
+                        .with_child(op_button("Bottom", AlignOp::Align(Alignment::Bottom))),
+                ),
+        ))
+        .with_default_spacer()
+        .with_child(setting(
+            "Distribute:",
+            Flex::column()
+                .with_child(
+                    Flex::row()
+                        .with_child(op_button(
+                            "Horizontal centers",
+                            AlignOp::Distribute(Axis::Horizontal, Spacing::Centers),
+                        ))
+                        .with_child(op_button(
+                            "Horizontal gaps",
+                            AlignOp::Distribute(Axis::Horizontal, Spacing::Gaps),
+                        )),
+                )
+                .with_child(
+                    Flex::row()
+                        .with_child(op_button(
+                            "Vertical centers",
+                            AlignOp::Distribute(Axis::Vertical, Spacing::Centers),
+                        ))
+                        .with_child(op_button(
+                            "Vertical gaps",
+                            AlignOp::Distribute(Axis::Vertical, Spacing::Gaps),
+                        )),
+                ),
+        ))
+        .with_default_spacer()
+        .with_child(
+            Button::new("Done").on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::align_settings)
+}
+
+fn target_picker() -> impl Widget<AlignTarget> {
+    RadioGroup::new(vec![
+        ("Selection", AlignTarget::Selection),
+        ("Metrics", AlignTarget::Metrics),
+        ("Key object", AlignTarget::KeyObject),
+    ])
+}
+
+/// A button that performs `op`, leaving the panel open.
+fn op_button(title: &str, op: AlignOp) -> impl Widget<AlignSettings> {
+    Button::new(title)
+        .on_click(move |ctx, settings: &mut AlignSettings, _| {
+            let args = consts::cmd::AlignArgs {
+                op,
+                target: settings.target,
+            };
+            ctx.submit_command(consts::cmd::ALIGN_OBJECTS.with(args));
+        })
+        .padding(2.0)
+}
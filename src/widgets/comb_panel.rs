@@ -0,0 +1,35 @@
+//! A panel for choosing how curvature combs are drawn.
+//!
+//! Changes take effect immediately.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, Checkbox, CrossAxisAlignment, Flex};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::setting;
+use crate::curvature::CombSettings;
+use crate::data::Workspace;
+use crate::widgets::{EditableLabel, ModalHost};
+
+pub fn comb_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(Checkbox::new("Show curvature comb").lens(CombSettings::visible))
+        .with_default_spacer()
+        .with_child(setting(
+            "Scale:",
+            EditableLabel::parse().lens(CombSettings::scale),
+        ))
+        .with_default_spacer()
+        .with_child(setting(
+            "Teeth per segment:",
+            EditableLabel::parse().lens(CombSettings::density),
+        ))
+        .with_default_spacer()
+        .with_child(
+            Button::new("Done").on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::comb_settings)
+}
@@ -7,7 +7,11 @@ use crate::consts;
 use crate::data::{AppState, EditorState};
 use crate::edit_session::EditSession;
 use crate::menus;
-use crate::widgets::{CoordPane, FloatingPanel, GlyphPane, Toolbar};
+use crate::widgets::{
+    align_panel, comb_panel, corner_panel, expand_stroke_panel, knife_panel, offset_path_panel,
+    shape_options_panel, shape_panel, simplify_panel, snap_panel, CoordPane, FloatingPanel,
+    GlyphPane, ModalHost, Toolbar, UndoPane,
+};
 
 /// the distance from the edge of a floating panel to the edge of the window.
 const FLOATING_PANEL_PADDING: f64 = 20.0;
@@ -57,6 +61,7 @@ pub struct EditorController<W> {
     toolbar: WidgetPod<(), FloatingPanel<Toolbar>>,
     coord_panel: WidgetPod<EditorState, FloatingPanel<Box<dyn Widget<EditorState>>>>,
     glyph_panel: WidgetPod<EditorState, FloatingPanel<Box<dyn Widget<EditorState>>>>,
+    undo_panel: WidgetPod<(), FloatingPanel<UndoPane>>,
 }
 
 impl<W> EditorController<W> {
@@ -70,6 +75,7 @@ impl<W> EditorController<W> {
                     .boxed(),
             )),
             glyph_panel: WidgetPod::new(FloatingPanel::new(GlyphPane::new().boxed())),
+            undo_panel: WidgetPod::new(FloatingPanel::new(UndoPane::default())),
         }
     }
 }
@@ -86,9 +92,58 @@ impl<W: Widget<EditorState>> Widget<EditorState> for EditorController<W> {
                 return;
             }
         }
+        // the panels for these commands are shown by the `ModalHost` that
+        // wraps the editor window.
+        if let Event::Command(cmd) = event {
+            if cmd.is(consts::cmd::SHOW_OFFSET_PATH_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(offset_path_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_EXPAND_STROKE_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(expand_stroke_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_CORNER_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(corner_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_SIMPLIFY_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(simplify_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_ALIGN_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(align_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_SNAP_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(snap_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_KNIFE_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(knife_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_SHAPE_OPTIONS_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(shape_options_panel));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_SHAPE_PANEL) {
+                let (kind, origin) = *cmd.get_unchecked(consts::cmd::SHOW_SHAPE_PANEL);
+                ctx.submit_command(ModalHost::make_modal_command(move || {
+                    shape_panel(kind, origin)
+                }));
+                ctx.set_handled();
+                return;
+            } else if cmd.is(consts::cmd::SHOW_COMB_PANEL) {
+                ctx.submit_command(ModalHost::make_modal_command(comb_panel));
+                ctx.set_handled();
+                return;
+            }
+        }
         self.toolbar.event(ctx, event, &mut (), env);
         self.coord_panel.event(ctx, event, data, env);
         self.glyph_panel.event(ctx, event, data, env);
+        self.undo_panel.event(ctx, event, &mut (), env);
         if !ctx.is_handled() {
             self.inner.event(ctx, event, data, env);
         }
@@ -111,6 +166,7 @@ impl<W: Widget<EditorState>> Widget<EditorState> for EditorController<W> {
         self.toolbar.lifecycle(ctx, event, &(), env);
         self.coord_panel.lifecycle(ctx, event, data, env);
         self.glyph_panel.lifecycle(ctx, event, data, env);
+        self.undo_panel.lifecycle(ctx, event, &(), env);
         self.inner.lifecycle(ctx, event, data, env);
     }
 
@@ -155,6 +211,14 @@ impl<W: Widget<EditorState>> Widget<EditorState> for EditorController<W> {
         );
         let frame = Rect::from_origin_size(orig, size);
         self.glyph_panel.set_layout_rect(ctx, data, env, frame);
+
+        let size = self.undo_panel.layout(ctx, &child_bc, &(), env);
+        let orig = (
+            our_size.width - size.width - FLOATING_PANEL_PADDING,
+            FLOATING_PANEL_PADDING,
+        );
+        let frame = Rect::from_origin_size(orig, size);
+        self.undo_panel.set_layout_rect(ctx, &(), env, frame);
         our_size
     }
 
@@ -162,6 +226,7 @@ impl<W: Widget<EditorState>> Widget<EditorState> for EditorController<W> {
         self.inner.paint(ctx, data, env);
         self.coord_panel.paint(ctx, data, env);
         self.glyph_panel.paint(ctx, data, env);
+        self.undo_panel.paint(ctx, &(), env);
         self.toolbar.paint(ctx, &(), env);
     }
 }
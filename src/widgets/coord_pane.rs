@@ -1,8 +1,10 @@
 //! The floating panel that displays the coordinate of the currently
 //! selected point.
 
-use druid::kurbo::Circle;
-use druid::widget::{prelude::*, Controller, CrossAxisAlignment, Either, Flex, Label, SizedBox};
+use druid::kurbo::{Affine, Circle};
+use druid::widget::{
+    prelude::*, Button, Controller, CrossAxisAlignment, Either, Flex, Label, SizedBox,
+};
 use druid::{Color, FontDescriptor, FontFamily, FontStyle, Point, WidgetExt};
 
 use crate::design_space::{DPoint, DVec2};
@@ -48,6 +50,18 @@ impl<W: Widget<CoordinateSelection>> Controller<CoordinateSelection, W> for Coor
                 origin: DPoint::from_raw(scale_origin),
             };
             ctx.submit_command(consts::cmd::SCALE_SELECTION.with(args));
+        } else if child_data.rotate != 0.0 || child_data.skew != 0.0 {
+            let affine = if child_data.rotate != 0.0 {
+                Affine::rotate(child_data.rotate.to_radians())
+            } else {
+                Affine::new([1.0, 0.0, child_data.skew.to_radians().tan(), 1.0, 0.0, 0.0])
+            };
+            let origin = child_data.quadrant.point_in_dspace_rect(data.frame);
+            let args = consts::cmd::TransformSelectionArgs {
+                affine,
+                origin: DPoint::from_raw(origin),
+            };
+            ctx.submit_command(consts::cmd::TRANSFORM_SELECTION.with(args));
         }
 
         // suppress clicks so that the editor doesn't handle them.
@@ -181,7 +195,7 @@ fn build_widget() -> impl Widget<CoordinateSelection> {
                 Flex::row()
                     .with_child(
                         Label::new("h")
-                            .with_font(coord_label_font)
+                            .with_font(coord_label_font.clone())
                             .with_text_color(theme::SECONDARY_TEXT_COLOR),
                     )
                     .with_spacer(4.0)
@@ -196,10 +210,61 @@ fn build_widget() -> impl Widget<CoordinateSelection> {
         SizedBox::empty(),
     );
 
+    let transform_info = Either::new(
+        |d, _| d.count > 1,
+        Flex::column()
+            .with_child(
+                Flex::row()
+                    .cross_axis_alignment(CrossAxisAlignment::Baseline)
+                    .with_child(
+                        Label::new("rot")
+                            .with_font(coord_label_font.clone())
+                            .with_text_color(theme::SECONDARY_TEXT_COLOR),
+                    )
+                    .with_spacer(4.0)
+                    .with_child(
+                        EditableLabel::parse()
+                            .with_font(theme::UI_DETAIL_FONT)
+                            .lens(CoordinateSelection::rotate)
+                            .fix_width(40.0),
+                    ),
+            )
+            .with_child(
+                Flex::row()
+                    .cross_axis_alignment(CrossAxisAlignment::Baseline)
+                    .with_child(
+                        Label::new("skew")
+                            .with_font(coord_label_font)
+                            .with_text_color(theme::SECONDARY_TEXT_COLOR),
+                    )
+                    .with_spacer(4.0)
+                    .with_child(
+                        EditableLabel::parse()
+                            .with_font(theme::UI_DETAIL_FONT)
+                            .lens(CoordinateSelection::skew)
+                            .fix_width(40.0),
+                    ),
+            )
+            .with_child(
+                Flex::row()
+                    .with_child(
+                        Button::new("↔")
+                            .on_click(|ctx, _, _| ctx.submit_command(consts::cmd::FLIP_HORIZONTAL)),
+                    )
+                    .with_child(
+                        Button::new("↕")
+                            .on_click(|ctx, _, _| ctx.submit_command(consts::cmd::FLIP_VERTICAL)),
+                    ),
+            )
+            .padding((8.0, 0., 0., 0.)),
+        SizedBox::empty(),
+    );
+
     let picker_and_editor = Flex::row()
         .with_child(coord_picker)
         .with_child(coord_editor)
         .with_child(bbox_info)
+        .with_child(transform_info)
         .padding(4.0);
 
     // if we have any points selected, show the numerical adjust widget, else an empty widget
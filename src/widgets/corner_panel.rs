@@ -0,0 +1,38 @@
+//! A panel for choosing the settings of the 'corners' command.
+
+use druid::widget::prelude::*;
+use druid::widget::{CrossAxisAlignment, Flex, RadioGroup};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::{buttons, setting};
+use crate::consts;
+use crate::corner::{CornerKind, CornerSettings};
+use crate::data::Workspace;
+use crate::widgets::EditableLabel;
+
+pub fn corner_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting("Shape:", kind_picker().lens(CornerSettings::kind)))
+        .with_default_spacer()
+        .with_child(setting(
+            "Size:",
+            EditableLabel::parse().lens(CornerSettings::size),
+        ))
+        .with_default_spacer()
+        .with_child(buttons("Apply", |settings: &CornerSettings| {
+            consts::cmd::MODIFY_CORNERS.with(*settings)
+        }))
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::corner_settings)
+}
+
+fn kind_picker() -> impl Widget<CornerKind> {
+    RadioGroup::new(vec![
+        ("Round", CornerKind::Round),
+        ("Soft round", CornerKind::SoftRound),
+        ("Chamfer", CornerKind::Chamfer),
+        ("Ink trap", CornerKind::InkTrap),
+    ])
+}
@@ -5,13 +5,17 @@ use std::sync::Arc;
 use druid::widget::prelude::*;
 use druid::{Application, Clipboard, ClipboardFormat, Color, Command, ContextMenu, Data, KbKey};
 
+use crate::align::AlignOp;
+use crate::boolean::BooleanOp;
 use crate::consts::{self, CANVAS_SIZE};
 use crate::data::EditorState;
 use crate::draw;
 use crate::edit_session::EditSession;
 use crate::mouse::{Mouse, TaggedEvent};
-use crate::tools::{EditType, Select, Tool};
-use crate::undo::UndoState;
+use crate::shapes::ShapeKind;
+use crate::snap::Snapping;
+use crate::tools::{EditType, Select, Tool, ToolSettings};
+use crate::undo::SessionUndo;
 
 /// The root widget of the glyph editor window.
 pub struct Editor {
@@ -20,22 +24,42 @@ pub struct Editor {
     // in the case of the temporary preview (spacebar) this is the tool
     // that will be restored when spacebar is released.
     //prev_tool: Option<Box<dyn Tool>>,
-    undo: UndoState<Arc<EditSession>>,
+    /// The undo history; this is owned by the workspace, so that it outlives
+    /// the editor window.
+    undo: SessionUndo,
     last_edit: EditType,
+    /// Set when the undo stack changes, so that we can notify the history panel.
+    history_changed: bool,
     /// If true, this session should be drawn with all glyphs filled and
     /// with no non-glyph items visible.
     draw_filled_outlines: bool,
+    /// The settings most recently passed to the tool.
+    tool_settings: ToolSettings,
 }
 
 impl Editor {
-    pub fn new(session: Arc<EditSession>) -> Editor {
+    pub fn new(session: Arc<EditSession>, undo: SessionUndo) -> Editor {
+        // the session may have been changed while no editor was open
+        // (for instance by renaming a glyph) so make sure the undo state agrees.
+        {
+            let mut state = undo.lock();
+            let current = state.current();
+            let unchanged = current.paths.same(&session.paths)
+                && current.components.same(&session.components)
+                && current.glyph.same(&session.glyph);
+            if !unchanged {
+                state.add_undo_group(session.as_ref().clone(), "External edit");
+            }
+        }
         Editor {
             mouse: Mouse::default(),
             tool: Box::new(Select::default()),
             //prev_tool: None,
-            undo: UndoState::new(session),
+            undo,
             last_edit: EditType::Normal,
+            history_changed: true,
             draw_filled_outlines: false,
+            tool_settings: ToolSettings::default(),
         }
     }
 
@@ -66,12 +90,37 @@ impl Editor {
         None
     }
 
-    fn update_undo(&mut self, edit: Option<EditType>, data: &Arc<EditSession>) {
+    /// Pass the settings in the workspace to the tool, if they have changed.
+    fn update_tool_settings(&mut self, data: &EditorState) {
+        let settings = ToolSettings {
+            snapping: Snapping::new(data.font.snap_settings, data.metrics.heights()),
+            knife: data.font.knife_settings,
+            shapes: data.font.shape_settings,
+            component_bounds: data.component_base_bounds(),
+        };
+        if settings != self.tool_settings {
+            self.tool.set_settings(&settings);
+            self.tool_settings = settings;
+        }
+    }
+
+    fn update_undo(&mut self, edit: Option<EditType>, event: &Event, data: &Arc<EditSession>) {
         match edit {
             Some(edit) if self.last_edit.needs_new_undo_group(edit) => {
-                self.undo.add_undo_group(data.clone())
+                let label = match event {
+                    Event::Command(cmd) => undo_label_for_cmd(cmd).to_string(),
+                    Event::Paste(_) => "Paste".to_string(),
+                    _ => self.tool.edit_label(edit, data),
+                };
+                self.undo
+                    .lock()
+                    .add_undo_group(data.as_ref().clone(), label);
+                self.history_changed = true;
             }
-            Some(_) => self.undo.update_current_undo(|state| *state = data.clone()),
+            Some(_) => self
+                .undo
+                .lock()
+                .update_current_undo(|state| *state = data.as_ref().clone()),
             // I'm not sure what to do here? I wanted to check if selections had
             // changed, and then update the current undo if necessary?
             // but that requires us to pass in the previous data. We can do that!
@@ -81,12 +130,19 @@ impl Editor {
         self.last_edit = edit.unwrap_or(self.last_edit);
     }
 
-    fn do_undo(&mut self) -> Option<&Arc<EditSession>> {
-        self.undo.undo()
+    fn do_undo(&mut self) -> Option<EditSession> {
+        self.history_changed = true;
+        self.undo.lock().undo().cloned()
+    }
+
+    fn do_redo(&mut self) -> Option<EditSession> {
+        self.history_changed = true;
+        self.undo.lock().redo().cloned()
     }
 
-    fn do_redo(&mut self) -> Option<&Arc<EditSession>> {
-        self.undo.redo()
+    fn do_jump_to_undo_state(&mut self, index: usize) -> Option<EditSession> {
+        self.history_changed = true;
+        self.undo.lock().jump_to(index).cloned()
     }
 
     fn do_copy(&self, data: &EditSession) {
@@ -152,6 +208,14 @@ impl Editor {
         match cmd {
             c if c.is(consts::cmd::SELECT_ALL) => data.session_mut().select_all(),
             c if c.is(consts::cmd::DESELECT_ALL) => data.session_mut().selection.clear(),
+            c if c.is(consts::cmd::SELECT_CONTOURS) => data.session_mut().select_contours(),
+            c if c.is(consts::cmd::INVERT_SELECTION) => data.session_mut().invert_selection(),
+            c if c.is(consts::cmd::GROW_SELECTION) => data.session_mut().grow_selection(),
+            c if c.is(consts::cmd::SHRINK_SELECTION) => data.session_mut().shrink_selection(),
+            c if c.is(consts::cmd::SELECT_SIMILAR) => {
+                let similar = c.get_unchecked(consts::cmd::SELECT_SIMILAR);
+                data.session_mut().select_similar(*similar);
+            }
             c if c.is(consts::cmd::DELETE) => data.session_mut().delete_selection(),
             c if c.is(consts::cmd::TOGGLE_PREVIEW_TOOL) => {
                 let is_mouse_down: &bool = cmd.get_unchecked(consts::cmd::TOGGLE_PREVIEW_TOOL);
@@ -172,6 +236,16 @@ impl Editor {
                 data.session_mut().toggle_guide(*id, *pos);
                 return (true, Some(EditType::Normal));
             }
+            c if c.is(consts::cmd::ADD_STEM_PROBE) => {
+                let consts::cmd::StemProbeCmdArgs { horizontal, pos } =
+                    cmd.get_unchecked(consts::cmd::ADD_STEM_PROBE);
+                data.session_mut().add_stem_probe(*horizontal, *pos);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::CLEAR_MEASUREMENTS) => {
+                data.session_mut().clear_measurements();
+                return (true, Some(EditType::Normal));
+            }
             c if c.is(druid::commands::COPY) => self.do_copy(&data.session),
             c if c.is(druid::commands::UNDO) => {
                 if let Some(prev) = self.do_undo() {
@@ -179,14 +253,22 @@ impl Editor {
                     //want to jump around during undo/redo, we always manually
                     //reuse the current viewport when handling these actions.
                     let saved_viewport = data.session.viewport;
-                    data.session = prev.clone();
+                    data.session = Arc::new(prev);
                     data.session_mut().viewport = saved_viewport;
                 }
             }
             c if c.is(druid::commands::REDO) => {
                 if let Some(next) = self.do_redo() {
                     let saved_viewport = data.session.viewport;
-                    data.session = next.clone();
+                    data.session = Arc::new(next);
+                    data.session_mut().viewport = saved_viewport;
+                }
+            }
+            c if c.is(consts::cmd::JUMP_TO_UNDO_STATE) => {
+                let index = c.get_unchecked(consts::cmd::JUMP_TO_UNDO_STATE);
+                if let Some(state) = self.do_jump_to_undo_state(*index) {
+                    let saved_viewport = data.session.viewport;
+                    data.session = Arc::new(state);
                     data.session_mut().viewport = saved_viewport;
                 }
             }
@@ -194,6 +276,15 @@ impl Editor {
                 data.session_mut().align_selection();
                 return (true, Some(EditType::Normal));
             }
+            c if c.is(consts::cmd::ALIGN_OBJECTS) => {
+                let consts::cmd::AlignArgs { op, target } =
+                    c.get_unchecked(consts::cmd::ALIGN_OBJECTS);
+                let metrics = data.layout_bounds();
+                let bases = data.component_base_bounds();
+                data.session_mut()
+                    .align_objects(*op, *target, metrics, &bases);
+                return (true, Some(EditType::Normal));
+            }
             c if c.is(consts::cmd::NUDGE_SELECTION) => {
                 let nudge = c.get_unchecked(consts::cmd::NUDGE_SELECTION);
                 data.session_mut().nudge_selection(*nudge);
@@ -211,10 +302,105 @@ impl Editor {
                     c.get_unchecked(consts::cmd::SCALE_SELECTION);
                 data.session_mut().scale_selection(*scale, *origin);
             }
+            c if c.is(consts::cmd::TRANSFORM_SELECTION) => {
+                let consts::cmd::TransformSelectionArgs { affine, origin } =
+                    c.get_unchecked(consts::cmd::TRANSFORM_SELECTION);
+                data.session_mut().transform_selection(*affine, *origin);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::FLIP_HORIZONTAL) => {
+                data.session_mut().flip_selection(true);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::FLIP_VERTICAL) => {
+                data.session_mut().flip_selection(false);
+                return (true, Some(EditType::Normal));
+            }
             c if c.is(consts::cmd::REVERSE_CONTOURS) => {
                 data.session_mut().reverse_contours();
                 return (true, Some(EditType::Normal));
             }
+            c if c.is(consts::cmd::CORRECT_DIRECTIONS) => {
+                data.session_mut().correct_directions();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::SET_START_POINT) => {
+                data.session_mut().set_start_points();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::LOCK_SELECTION) => {
+                data.session_mut().lock_selection();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::UNLOCK_ALL) => {
+                data.session_mut().unlock_all();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::HIDE_SELECTION) => {
+                data.session_mut().hide_selection();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::SHOW_ALL) => {
+                data.session_mut().show_all();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::BOOLEAN_OP) => {
+                let op = c.get_unchecked(consts::cmd::BOOLEAN_OP);
+                data.session_mut().boolean_op(*op);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::REMOVE_OVERLAP) => {
+                data.session_mut().remove_overlap();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::REMOVE_OVERLAP_IN_GLYPH) => {
+                let session = data.session_mut();
+                // with nothing selected, every path in the session is merged
+                session.selection.clear();
+                session.remove_overlap();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::ADD_EXTREMES) => {
+                data.session_mut().add_extremes();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::BREAK_CONTOUR) => {
+                data.session_mut().break_contours();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::HARMONIZE) => {
+                data.session_mut().harmonize();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::REMOVE_REDUNDANT_POINTS) => {
+                data.session_mut().remove_redundant_points();
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::SIMPLIFY) => {
+                let settings = c.get_unchecked(consts::cmd::SIMPLIFY);
+                data.session_mut().simplify(*settings);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::OFFSET_PATH) => {
+                let settings = c.get_unchecked(consts::cmd::OFFSET_PATH);
+                data.session_mut().offset_paths(*settings);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::EXPAND_STROKE) => {
+                let settings = c.get_unchecked(consts::cmd::EXPAND_STROKE);
+                data.session_mut().expand_strokes(*settings);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::ADD_SHAPE) => {
+                let args = c.get_unchecked(consts::cmd::ADD_SHAPE);
+                data.session_mut().add_shape(*args);
+                return (true, Some(EditType::Normal));
+            }
+            c if c.is(consts::cmd::MODIFY_CORNERS) => {
+                let settings = c.get_unchecked(consts::cmd::MODIFY_CORNERS);
+                data.session_mut().modify_corners(*settings);
+                return (true, Some(EditType::Normal));
+            }
             // all unhandled commands:
             _ => return (false, None),
         }
@@ -226,11 +412,63 @@ impl Editor {
     fn set_tool(&mut self, tool: Box<dyn Tool>) {
         self.draw_filled_outlines = tool.name() == "Preview";
         self.tool = tool;
+        self.tool.set_settings(&self.tool_settings);
         self.mouse.reset();
         self.tool.init_mouse(&mut self.mouse);
     }
 }
 
+/// A description of the edit performed by a command, for the undo history.
+fn undo_label_for_cmd(cmd: &Command) -> &'static str {
+    match cmd {
+        c if c.is(consts::cmd::ADD_GUIDE) => "Add guide",
+        c if c.is(consts::cmd::TOGGLE_GUIDE) => "Toggle guide",
+        c if c.is(consts::cmd::ADD_STEM_PROBE) => "Add stem probe",
+        c if c.is(consts::cmd::CLEAR_MEASUREMENTS) => "Clear measurements",
+        c if c.is(consts::cmd::ALIGN_SELECTION) => "Align selection",
+        c if c.is(consts::cmd::ALIGN_OBJECTS) => {
+            match c.get_unchecked(consts::cmd::ALIGN_OBJECTS).op {
+                AlignOp::Align(_) => "Align",
+                AlignOp::Distribute(..) => "Distribute",
+            }
+        }
+        c if c.is(consts::cmd::NUDGE_SELECTION) => "Move selection",
+        c if c.is(consts::cmd::ADJUST_SIDEBEARING) => "Adjust sidebearing",
+        c if c.is(consts::cmd::TRANSFORM_SELECTION) => "Transform selection",
+        c if c.is(consts::cmd::FLIP_HORIZONTAL) => "Flip horizontally",
+        c if c.is(consts::cmd::FLIP_VERTICAL) => "Flip vertically",
+        c if c.is(consts::cmd::REVERSE_CONTOURS) => "Reverse contours",
+        c if c.is(consts::cmd::CORRECT_DIRECTIONS) => "Correct path directions",
+        c if c.is(consts::cmd::SET_START_POINT) => "Set start point",
+        c if c.is(consts::cmd::LOCK_SELECTION) => "Lock selection",
+        c if c.is(consts::cmd::UNLOCK_ALL) => "Unlock all",
+        c if c.is(consts::cmd::HIDE_SELECTION) => "Hide selection",
+        c if c.is(consts::cmd::SHOW_ALL) => "Show all",
+        c if c.is(consts::cmd::BOOLEAN_OP) => match c.get_unchecked(consts::cmd::BOOLEAN_OP) {
+            BooleanOp::Union => "Union",
+            BooleanOp::Subtract => "Subtract",
+            BooleanOp::Intersect => "Intersect",
+            BooleanOp::Exclude => "Exclude",
+        },
+        c if c.is(consts::cmd::REMOVE_OVERLAP) => "Remove overlap",
+        c if c.is(consts::cmd::REMOVE_OVERLAP_IN_GLYPH) => "Remove overlap",
+        c if c.is(consts::cmd::ADD_EXTREMES) => "Add extremes",
+        c if c.is(consts::cmd::BREAK_CONTOUR) => "Break contour",
+        c if c.is(consts::cmd::HARMONIZE) => "Harmonize",
+        c if c.is(consts::cmd::REMOVE_REDUNDANT_POINTS) => "Remove redundant points",
+        c if c.is(consts::cmd::SIMPLIFY) => "Simplify",
+        c if c.is(consts::cmd::OFFSET_PATH) => "Offset path",
+        c if c.is(consts::cmd::EXPAND_STROKE) => "Expand stroke",
+        c if c.is(consts::cmd::MODIFY_CORNERS) => "Modify corners",
+        c if c.is(consts::cmd::ADD_SHAPE) => match c.get_unchecked(consts::cmd::ADD_SHAPE).kind {
+            ShapeKind::Rectangle => "Draw rectangle",
+            ShapeKind::Ellipse => "Draw ellipse",
+            ShapeKind::Polygon => "Draw polygon",
+        },
+        _ => "Edit",
+    }
+}
+
 impl Widget<EditorState> for Editor {
     fn paint(&mut self, ctx: &mut PaintCtx, data: &EditorState, env: &Env) {
         let rect = (CANVAS_SIZE * data.session.viewport.zoom).to_rect();
@@ -301,7 +539,10 @@ impl Widget<EditorState> for Editor {
             _ => None,
         };
 
-        self.update_undo(edit, &data.session);
+        self.update_undo(edit, event, &data.session);
+        if std::mem::take(&mut self.history_changed) {
+            ctx.submit_command(consts::cmd::UNDO_HISTORY_CHANGED.with(self.undo.lock().history()));
+        }
         if edit.is_some() || !pre_selection.same(&data.session.selection) {
             ctx.request_paint();
         }
@@ -311,10 +552,15 @@ impl Widget<EditorState> for Editor {
         }
     }
 
-    fn lifecycle(&mut self, _: &mut LifeCycleCtx, _: &LifeCycle, _: &EditorState, _: &Env) {}
+    fn lifecycle(&mut self, _: &mut LifeCycleCtx, event: &LifeCycle, data: &EditorState, _: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.update_tool_settings(data);
+        }
+    }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old: &EditorState, new: &EditorState, _env: &Env) {
         if !old.same(new) {
+            self.update_tool_settings(new);
             ctx.request_paint();
         }
     }
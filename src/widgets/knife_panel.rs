@@ -0,0 +1,36 @@
+//! A panel for choosing what the knife tool does.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, CrossAxisAlignment, Flex, RadioGroup};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::setting;
+use crate::data::Workspace;
+use crate::tools::{KnifeMode, KnifeSettings};
+use crate::widgets::{EditableLabel, ModalHost};
+
+pub fn knife_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting(
+            "Mode:",
+            RadioGroup::new(vec![
+                ("Slice paths", KnifeMode::Slice),
+                ("Separate shapes", KnifeMode::Separate),
+                ("Cut with gap", KnifeMode::Gap),
+            ])
+            .lens(KnifeSettings::mode),
+        ))
+        .with_default_spacer()
+        .with_child(setting(
+            "Gap width:",
+            EditableLabel::parse().lens(KnifeSettings::gap),
+        ))
+        .with_default_spacer()
+        .with_child(
+            Button::new("Done").on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::knife_settings)
+}
@@ -1,29 +1,47 @@
 //! Druid `Widget`s.
 
+mod align_panel;
+mod comb_panel;
 mod controller;
 mod coord_pane;
+mod corner_panel;
 mod editable_label;
 mod editor;
 mod fontinfo;
 mod glyph;
 mod glyph_pane;
 mod grid;
+mod knife_panel;
 mod maybe;
 mod modal_host;
+mod offset_panel;
 mod scroll_zoom;
+mod shape_panel;
 mod sidebar;
+mod simplify_panel;
+mod snap_panel;
 mod toolbar;
+mod undo_pane;
 
+pub use align_panel::align_panel;
+pub use comb_panel::comb_panel;
 pub use controller::{EditorController, RootWindowController};
 pub use coord_pane::CoordPane;
+pub use corner_panel::corner_panel;
 pub use editable_label::EditableLabel;
 pub use editor::Editor;
 pub use fontinfo::font_info;
 pub use glyph::GlyphPainter;
 pub use glyph_pane::GlyphPane;
 pub use grid::GlyphGrid;
+pub use knife_panel::knife_panel;
 use maybe::Maybe;
 pub use modal_host::ModalHost;
+pub use offset_panel::{expand_stroke_panel, offset_path_panel};
 pub use scroll_zoom::ScrollZoom;
+pub use shape_panel::{shape_options_panel, shape_panel};
 pub use sidebar::Sidebar;
+pub use simplify_panel::simplify_panel;
+pub use snap_panel::snap_panel;
 pub use toolbar::{FloatingPanel, Toolbar};
+pub use undo_pane::UndoPane;
@@ -0,0 +1,96 @@
+//! Panels for choosing the settings of the 'offset path' and 'expand stroke'
+//! commands.
+//!
+//! These are shown as modals in the editor window. The settings are kept in
+//! the workspace, so they are remembered the next time a panel is shown.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, CrossAxisAlignment, Flex, Label, RadioGroup};
+use druid::{Color, Command, Data, WidgetExt};
+
+use crate::consts;
+use crate::data::Workspace;
+use crate::offset::{Cap, Join, OffsetSettings, StrokeSettings};
+use crate::theme;
+use crate::widgets::{EditableLabel, ModalHost};
+
+pub fn offset_path_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting(
+            "Distance:",
+            EditableLabel::parse().lens(OffsetSettings::distance),
+        ))
+        .with_default_spacer()
+        .with_child(setting("Join:", join_picker().lens(OffsetSettings::join)))
+        .with_default_spacer()
+        .with_child(buttons("Offset", |settings: &OffsetSettings| {
+            consts::cmd::OFFSET_PATH.with(*settings)
+        }))
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::offset_settings)
+}
+
+pub fn expand_stroke_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting(
+            "Width:",
+            EditableLabel::parse().lens(StrokeSettings::width),
+        ))
+        .with_default_spacer()
+        .with_child(setting("Cap:", cap_picker().lens(StrokeSettings::cap)))
+        .with_default_spacer()
+        .with_child(setting("Join:", join_picker().lens(StrokeSettings::join)))
+        .with_default_spacer()
+        .with_child(buttons("Expand", |settings: &StrokeSettings| {
+            consts::cmd::EXPAND_STROKE.with(*settings)
+        }))
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::stroke_settings)
+}
+
+/// A labeled row.
+pub(super) fn setting<T: Data>(label: &str, widget: impl Widget<T> + 'static) -> impl Widget<T> {
+    Flex::row()
+        .with_child(Label::new(label).with_text_color(theme::SECONDARY_TEXT_COLOR))
+        .with_default_spacer()
+        .with_child(widget)
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+}
+
+fn join_picker() -> impl Widget<Join> {
+    RadioGroup::new(vec![
+        ("Miter", Join::Miter),
+        ("Round", Join::Round),
+        ("Bevel", Join::Bevel),
+    ])
+}
+
+fn cap_picker() -> impl Widget<Cap> {
+    RadioGroup::new(vec![
+        ("Butt", Cap::Butt),
+        ("Round", Cap::Round),
+        ("Square", Cap::Square),
+    ])
+}
+
+/// A 'Cancel' button, and a button that sends the command made by `make_cmd`
+/// to the editor.
+pub(super) fn buttons<T: Data>(
+    title: &str,
+    make_cmd: impl Fn(&T) -> Command + 'static,
+) -> impl Widget<T> {
+    Flex::row()
+        .with_child(
+            Button::new("Cancel")
+                .on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .with_default_spacer()
+        .with_child(Button::new(title).on_click(move |ctx, data, _| {
+            ctx.submit_command(make_cmd(data));
+            ctx.submit_command(ModalHost::DISMISS_MODAL);
+        }))
+}
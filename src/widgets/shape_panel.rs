@@ -0,0 +1,95 @@
+//! Panels for the options of the shape tools, and for entering the exact
+//! dimensions of a shape.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, Checkbox, CrossAxisAlignment, Flex};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::{buttons, setting};
+use crate::consts;
+use crate::data::Workspace;
+use crate::design_space::DPoint;
+use crate::shapes::{ShapeArgs, ShapeKind, ShapeSettings};
+use crate::widgets::{EditableLabel, ModalHost};
+
+/// The options used when drawing shapes by dragging.
+pub fn shape_options_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(corner_radius())
+        .with_default_spacer()
+        .with_child(polygon_options())
+        .with_default_spacer()
+        .with_child(
+            Button::new("Done").on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::shape_settings)
+}
+
+/// The dimensions of a shape of `kind` to add at `origin`.
+pub fn shape_panel(kind: ShapeKind, origin: DPoint) -> impl Widget<Workspace> {
+    let mut column = Flex::column();
+    match kind {
+        ShapeKind::Rectangle | ShapeKind::Ellipse => {
+            column.add_child(setting(
+                "Width:",
+                EditableLabel::parse().lens(ShapeSettings::width),
+            ));
+            column.add_default_spacer();
+            column.add_child(setting(
+                "Height:",
+                EditableLabel::parse().lens(ShapeSettings::height),
+            ));
+            if kind == ShapeKind::Rectangle {
+                column.add_default_spacer();
+                column.add_child(corner_radius());
+            }
+        }
+        ShapeKind::Polygon => {
+            column.add_child(setting(
+                "Radius:",
+                EditableLabel::parse().lens(ShapeSettings::radius),
+            ));
+            column.add_default_spacer();
+            column.add_child(polygon_options());
+        }
+    }
+    column.add_default_spacer();
+    column.add_child(buttons("Add", move |settings: &ShapeSettings| {
+        consts::cmd::ADD_SHAPE.with(ShapeArgs {
+            kind,
+            origin,
+            settings: *settings,
+        })
+    }));
+    column
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::shape_settings)
+}
+
+fn corner_radius() -> impl Widget<ShapeSettings> {
+    setting(
+        "Corner radius:",
+        EditableLabel::parse().lens(ShapeSettings::corner_radius),
+    )
+}
+
+fn polygon_options() -> impl Widget<ShapeSettings> {
+    Flex::column()
+        .with_child(setting(
+            "Sides:",
+            EditableLabel::parse().lens(ShapeSettings::sides),
+        ))
+        .with_default_spacer()
+        .with_child(Checkbox::new("Star").lens(ShapeSettings::star))
+        .with_default_spacer()
+        .with_child(setting(
+            "Inner radius:",
+            EditableLabel::parse().lens(ShapeSettings::inner_radius),
+        ))
+        .cross_axis_alignment(CrossAxisAlignment::End)
+}
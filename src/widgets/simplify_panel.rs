@@ -0,0 +1,27 @@
+//! A panel for choosing the tolerance of the 'simplify' command.
+
+use druid::widget::prelude::*;
+use druid::widget::{CrossAxisAlignment, Flex};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::{buttons, setting};
+use crate::consts;
+use crate::data::Workspace;
+use crate::fit::SimplifySettings;
+use crate::widgets::EditableLabel;
+
+pub fn simplify_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(setting(
+            "Tolerance:",
+            EditableLabel::parse().lens(SimplifySettings::tolerance),
+        ))
+        .with_default_spacer()
+        .with_child(buttons("Simplify", |settings: &SimplifySettings| {
+            consts::cmd::SIMPLIFY.with(*settings)
+        }))
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::simplify_settings)
+}
@@ -0,0 +1,41 @@
+//! A panel for choosing what points snap to while they are dragged.
+//!
+//! Changes take effect immediately; holding ctrl while dragging suspends
+//! snapping.
+
+use druid::widget::prelude::*;
+use druid::widget::{Button, Checkbox, CrossAxisAlignment, Flex};
+use druid::{Color, WidgetExt};
+
+use super::offset_panel::setting;
+use crate::data::Workspace;
+use crate::snap::SnapSettings;
+use crate::widgets::{EditableLabel, ModalHost};
+
+pub fn snap_panel() -> impl Widget<Workspace> {
+    Flex::column()
+        .with_child(
+            Flex::column()
+                .with_child(Checkbox::new("Grid").lens(SnapSettings::grid))
+                .with_spacer(4.0)
+                .with_child(Checkbox::new("Points").lens(SnapSettings::points))
+                .with_spacer(4.0)
+                .with_child(Checkbox::new("Guides").lens(SnapSettings::guides))
+                .with_spacer(4.0)
+                .with_child(Checkbox::new("Metrics").lens(SnapSettings::metrics))
+                .cross_axis_alignment(CrossAxisAlignment::Start),
+        )
+        .with_default_spacer()
+        .with_child(setting(
+            "Grid spacing:",
+            EditableLabel::parse().lens(SnapSettings::grid_spacing),
+        ))
+        .with_default_spacer()
+        .with_child(
+            Button::new("Done").on_click(|ctx, _, _| ctx.submit_command(ModalHost::DISMISS_MODAL)),
+        )
+        .cross_axis_alignment(CrossAxisAlignment::End)
+        .padding(16.0)
+        .background(Color::WHITE)
+        .lens(Workspace::snap_settings)
+}
@@ -219,6 +219,18 @@ impl Default for Toolbar {
             hotkey: HotKey::new(None, "p"),
         };
 
+        let pencil = ToolbarItem {
+            name: "Pencil",
+            icon: constrain_path(pencil_path()),
+            hotkey: HotKey::new(SysMods::Shift, "p"),
+        };
+
+        let spline = ToolbarItem {
+            name: "Spline",
+            icon: constrain_path(spline_path()),
+            hotkey: HotKey::new(None, "b"),
+        };
+
         let preview = ToolbarItem {
             name: "Preview",
             icon: constrain_path(preview_path()),
@@ -237,6 +249,12 @@ impl Default for Toolbar {
             hotkey: HotKey::new(SysMods::Shift, "u"),
         };
 
+        let polygon = ToolbarItem {
+            name: "Polygon",
+            icon: constrain_path(polygon_path()),
+            hotkey: HotKey::new(None, "y"),
+        };
+
         let knife = ToolbarItem {
             name: "Knife",
             icon: constrain_path(knife_path()),
@@ -250,7 +268,7 @@ impl Default for Toolbar {
         };
 
         Toolbar::new(vec![
-            select, pen, knife, preview, measure, rectangle, ellipse,
+            select, pen, pencil, spline, knife, preview, measure, rectangle, ellipse, polygon,
         ])
     }
 }
@@ -308,6 +326,45 @@ fn pen_path() -> BezPath {
     bez
 }
 
+fn pencil_path() -> BezPath {
+    let mut bez = BezPath::new();
+
+    bez.move_to((0.0, 0.0));
+    bez.line_to((100.0, 0.0));
+    bez.line_to((100.0, 400.0));
+    bez.line_to((50.0, 520.0));
+    bez.line_to((0.0, 400.0));
+    bez.line_to((0.0, 0.0));
+    bez.close_path();
+
+    bez.move_to((0.0, 80.0));
+    bez.line_to((100.0, 80.0));
+    bez.close_path();
+
+    bez.move_to((0.0, 400.0));
+    bez.line_to((100.0, 400.0));
+    bez.close_path();
+
+    bez.apply_affine(Affine::rotate(-0.6));
+    let origin = bez.bounding_box().origin();
+    bez.apply_affine(Affine::translate(-origin.to_vec2()));
+    bez
+}
+
+fn spline_path() -> BezPath {
+    let mut bez = BezPath::new();
+
+    bez.move_to((50.0, 550.0));
+    bez.curve_to((50.0, 350.0), (450.0, 450.0), (450.0, 300.0));
+    bez.curve_to((450.0, 150.0), (50.0, 250.0), (50.0, 50.0));
+
+    for center in &[(50.0, 550.0), (450.0, 300.0), (50.0, 50.0)] {
+        let circle = Circle::new(*center, 50.0);
+        bez.extend(circle.path_elements(0.1));
+    }
+    bez
+}
+
 fn preview_path() -> BezPath {
     let mut bez = BezPath::new();
 
@@ -372,6 +429,24 @@ fn rect_path() -> BezPath {
     bez
 }
 
+fn polygon_path() -> BezPath {
+    let mut bez = BezPath::new();
+
+    bez.move_to((150.0, 0.0));
+    bez.line_to((188.0, 97.0));
+    bez.line_to((293.0, 104.0));
+    bez.line_to((212.0, 170.0));
+    bez.line_to((238.0, 271.0));
+    bez.line_to((150.0, 215.0));
+    bez.line_to((62.0, 271.0));
+    bez.line_to((88.0, 170.0));
+    bez.line_to((7.0, 104.0));
+    bez.line_to((112.0, 97.0));
+    bez.line_to((150.0, 0.0));
+    bez.close_path();
+    bez
+}
+
 fn knife_path() -> BezPath {
     let mut bez = BezPath::new();
 
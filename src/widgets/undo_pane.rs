@@ -0,0 +1,148 @@
+//! The floating panel that displays the undo history.
+
+use druid::kurbo::{Point, Rect, Size};
+use druid::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use druid::widget::prelude::*;
+use druid::{Color, Data};
+
+use crate::consts;
+use crate::undo::UndoHistory;
+
+const ROW_HEIGHT: f64 = 18.0;
+const PANEL_WIDTH: f64 = 160.0;
+const TEXT_PADDING: f64 = 8.0;
+const FONT_SIZE: f64 = 12.0;
+/// We only show the most recent entries; older entries can still be reached
+/// by undoing.
+const MAX_VISIBLE_ROWS: usize = 12;
+
+const LIVE_ROW_BG_COLOR: Color = Color::grey8(0xAD);
+const HOT_ROW_BG_COLOR: Color = Color::grey8(0xC8);
+const TEXT_COLOR: Color = Color::BLACK;
+/// The color for states that have been undone, and can be redone.
+const UNDONE_TEXT_COLOR: Color = Color::grey8(0x80);
+
+/// A panel listing the undo history of the editor.
+///
+/// Clicking on an entry makes that state current.
+///
+/// Like the toolbar, this keeps its own state, which is updated by the
+/// `UNDO_HISTORY_CHANGED` command sent by the editor.
+#[derive(Debug, Default)]
+pub struct UndoPane {
+    history: UndoHistory,
+    hot_row: Option<usize>,
+}
+
+impl UndoPane {
+    /// The index in the history of the first visible row.
+    fn first_visible(&self) -> usize {
+        let first = self.history.labels.len().saturating_sub(MAX_VISIBLE_ROWS);
+        first.min(self.history.live_index)
+    }
+
+    fn visible_rows(&self) -> usize {
+        (self.history.labels.len() - self.first_visible()).min(MAX_VISIBLE_ROWS)
+    }
+
+    /// The history index of the entry at `pos`, if any.
+    fn entry_at(&self, pos: Point) -> Option<usize> {
+        if pos.y < 0.0 || pos.x < 0.0 || pos.x > PANEL_WIDTH {
+            return None;
+        }
+        let row = (pos.y / ROW_HEIGHT) as usize;
+        if row < self.visible_rows() {
+            Some(self.first_visible() + row)
+        } else {
+            None
+        }
+    }
+
+    fn row_rect(row: usize) -> Rect {
+        Rect::from_origin_size(
+            (0.0, row as f64 * ROW_HEIGHT),
+            Size::new(PANEL_WIDTH, ROW_HEIGHT),
+        )
+    }
+}
+
+impl<T: Data> Widget<T> for UndoPane {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut T, _env: &Env) {
+        match event {
+            Event::Command(cmd) => {
+                if let Some(history) = cmd.get(consts::cmd::UNDO_HISTORY_CHANGED) {
+                    self.history = history.clone();
+                    ctx.request_layout();
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseMove(m) => {
+                let hot_row = if ctx.is_hot() {
+                    self.entry_at(m.pos)
+                } else {
+                    None
+                };
+                if hot_row != self.hot_row {
+                    self.hot_row = hot_row;
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseDown(_) => {
+                ctx.set_active(true);
+                ctx.set_handled();
+            }
+            Event::MouseUp(m) => {
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    if let Some(index) = self.entry_at(m.pos) {
+                        ctx.submit_command(consts::cmd::JUMP_TO_UNDO_STATE.with(index));
+                    }
+                }
+                ctx.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        if let LifeCycle::HotChanged(false) = event {
+            self.hot_row = None;
+            ctx.request_paint();
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        let height = self.visible_rows().max(1) as f64 * ROW_HEIGHT;
+        bc.constrain(Size::new(PANEL_WIDTH, height))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &T, _env: &Env) {
+        let first = self.first_visible();
+        for row in 0..self.visible_rows() {
+            let index = first + row;
+            let rect = UndoPane::row_rect(row);
+            if index == self.history.live_index {
+                ctx.fill(rect, &LIVE_ROW_BG_COLOR);
+            } else if Some(index) == self.hot_row {
+                ctx.fill(rect, &HOT_ROW_BG_COLOR);
+            }
+
+            let color = if index > self.history.live_index {
+                UNDONE_TEXT_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            let layout = ctx
+                .text()
+                .new_text_layout(self.history.labels[index].clone())
+                .font(FontFamily::SYSTEM_UI, FONT_SIZE)
+                .text_color(color)
+                .build()
+                .unwrap();
+            let text_y = rect.y0 + (ROW_HEIGHT - layout.size().height) / 2.0;
+            ctx.draw_text(&layout, (TEXT_PADDING, text_y));
+        }
+    }
+}